@@ -0,0 +1,124 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use crate::deadfiles::manifest_dir;
+use crate::encoding;
+
+/// Public items in the Rust files of one crate.
+#[derive(Default)]
+struct ApiSurface {
+    fns: usize,
+    /// Structs, enums, unions and type aliases.
+    types: usize,
+    traits: usize,
+    /// Constants, statics, modules, macros and re-exports.
+    other: usize,
+}
+
+impl ApiSurface {
+    fn total(&self) -> usize {
+        self.fns + self.types + self.traits + self.other
+    }
+
+    /// Classify a line of Rust code and count it if it declares a public item.
+    ///
+    /// Only plain `pub` counts, since `pub(crate)` and similar are not part of the API.
+    fn add_line(&mut self, line: &str) {
+        let mut rest = match line.trim_start().strip_prefix("pub ") {
+            Some(rest) => rest,
+            None => {
+                if line.trim_start().starts_with("#[macro_export]") {
+                    self.other += 1;
+                }
+                return;
+            }
+        };
+
+        // skip qualifiers like `pub const unsafe extern "C" fn`
+        loop {
+            let next = ["async ", "unsafe ", "extern \"C\" ", "extern "]
+                .iter()
+                .find_map(|q| rest.strip_prefix(q));
+            match next {
+                Some(next) => rest = next,
+                None => break,
+            }
+        }
+
+        let keyword = rest.split(|c: char| !c.is_alphanumeric()).next();
+        match keyword {
+            Some("fn") => self.fns += 1,
+            Some("const") if rest.starts_with("const fn") => self.fns += 1,
+            Some("struct" | "enum" | "union" | "type") => self.types += 1,
+            Some("trait") => self.traits += 1,
+            Some("const" | "static" | "mod" | "use") => self.other += 1,
+            _ => (),
+        }
+    }
+}
+
+/// Get the package name from a `Cargo.toml`, falling back to the name of its directory.
+fn crate_name(pkg: &Path) -> String {
+    let manifest = fs::read_to_string(pkg.join("Cargo.toml")).unwrap_or_default();
+    let mut in_package = false;
+
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+        } else if let (true, Some((key, value))) = (in_package, line.split_once('=')) {
+            if key.trim() == "name" {
+                if let Some(name) = value.split('"').nth(1) {
+                    return name.to_owned();
+                }
+            }
+        }
+    }
+
+    pkg.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| pkg.display().to_string())
+}
+
+/// Print the number of public items (functions, types, traits and others) in the Rust
+/// files of each crate, as a measure of how large its API is.
+pub fn print_api_surface(files: &[PathBuf]) {
+    let packages = files
+        .iter()
+        .filter(|f| f.file_name().map(|n| n == "Cargo.toml").unwrap_or(false))
+        .filter_map(|f| f.parent().map(Path::to_owned))
+        .collect::<HashSet<_>>();
+    let mut crates: BTreeMap<String, ApiSurface> = BTreeMap::new();
+
+    for f in files {
+        if f.extension().map(|e| e != "rs").unwrap_or(true) {
+            continue;
+        }
+        let file = match File::open(f) {
+            Ok(file) => file,
+            Err(_) => continue,
+        };
+
+        let name = match manifest_dir(f, &packages) {
+            Some(pkg) => crate_name(pkg),
+            None => "(no crate)".to_owned(),
+        };
+        let s = crates.entry(name).or_default();
+        for line in encoding::lines(BufReader::new(file)).map_while(Result::ok) {
+            s.add_line(&line);
+        }
+    }
+
+    for (name, s) in crates {
+        println!(
+            "Crate {} has {} public items ({} functions, {} types, {} traits, {} other).",
+            name,
+            s.total(),
+            s.fns,
+            s.types,
+            s.traits,
+            s.other
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+use crate::exit;
+
+/// Convert a label of a source file in the main repository, like `//pkg/sub:file.cc` or
+/// `root//pkg:file.cc` (Buck cells), into a path relative to the workspace root.
+/// Labels in external repositories have no file in the tree and give `None`.
+fn label_to_path(label: &str) -> Option<PathBuf> {
+    let (repo, rest) = label.split_once("//")?;
+    if !(repo.is_empty() || repo == "@" || repo == "@@" || repo == "root") {
+        return None;
+    }
+
+    let path = match rest.split_once(':') {
+        Some(("", name)) => name.to_owned(),
+        Some((pkg, name)) => format!("{}/{}", pkg, name),
+        None => rest.to_owned(),
+    };
+    Some(PathBuf::from(path))
+}
+
+/// Ask Bazel (or a compatible tool like Buck) for the source files of the targets matched by
+/// a query expression, returning their paths under the workspace root.
+///
+/// Exits the process with the tool's error message if the query fails.
+pub fn query_files(root: &Path, expr: &str, tool: &str) -> Vec<PathBuf> {
+    let query = format!("kind(\"source file\", labels(srcs, {}))", expr);
+    let output = Command::new(tool)
+        .current_dir(root)
+        .args(["query", &query, "--output=label"])
+        .output()
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to run {}: {}", tool, e);
+            process::exit(exit::USAGE);
+        });
+
+    if !output.status.success() {
+        eprintln!(
+            "{} query {}: {}",
+            tool,
+            expr,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        process::exit(exit::USAGE);
+    }
+
+    let mut files = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|l| label_to_path(l.trim()))
+        .map(|p| root.join(p))
+        .filter(|p| p.is_file())
+        .collect::<Vec<_>>();
+    files.sort();
+    files.dedup();
+    files
+}
@@ -0,0 +1,181 @@
+use clap::ArgEnum;
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::encoding;
+
+/// Extensions of file types that are always binary, so they are skipped without reading.
+const BINARY_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "bmp", "ico", "webp", "tif", "tiff", "psd", "pdf", "zip", "gz",
+    "tgz", "bz2", "xz", "zst", "7z", "rar", "jar", "war", "whl", "exe", "dll", "so", "dylib", "a",
+    "lib", "o", "obj", "class", "pyc", "pyo", "wasm", "bin", "dat", "db", "sqlite", "mp3", "mp4",
+    "mov", "avi", "mkv", "wav", "flac", "ogg", "ttf", "otf", "woff", "woff2", "eot",
+];
+
+/// Default number of bytes at the start of a file that are checked for NUL bytes.
+pub const DEFAULT_SNIFF_BYTES: u64 = 8192;
+
+/// How binary files are recognized.
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum BinaryPolicy {
+    /// Known binary extensions, and files with a NUL byte near the start
+    NulSniff,
+    /// Known binary extensions only, without reading any file
+    Extension,
+    /// Treat every file as text
+    None,
+}
+
+/// Decides which files are binary and skipped, following a policy.
+#[derive(Clone, Debug)]
+pub struct BinaryDetector {
+    pub policy: BinaryPolicy,
+    /// Number of bytes at the start of a file checked for NUL bytes by `NulSniff`.
+    pub sniff_bytes: u64,
+    /// Extensions of files that are always counted, like `sql` for dumps with embedded
+    /// NUL bytes.
+    pub always_count: Vec<String>,
+}
+
+impl Default for BinaryDetector {
+    fn default() -> Self {
+        BinaryDetector {
+            policy: BinaryPolicy::NulSniff,
+            sniff_bytes: DEFAULT_SNIFF_BYTES,
+            always_count: Vec::new(),
+        }
+    }
+}
+
+impl BinaryDetector {
+    /// Check whether a file is binary, either from its extension or because its first few
+    /// kilobytes contain a NUL byte, which text files in any common encoding except UTF-16
+    /// never do.
+    pub fn is_binary(&self, path: &Path) -> bool {
+        // files that cannot be read are left to the counting pass to report
+        self.binary_by_name(path).unwrap_or_else(|| {
+            read_head(path, self.sniff_bytes)
+                .map(|h| has_nul(&h))
+                .unwrap_or(false)
+        })
+    }
+
+    /// Check whether contents that are not on disk, like a git blob, are binary, the same
+    /// way as [`is_binary`](Self::is_binary) checks a file at `path`.
+    pub fn is_binary_contents(&self, path: &Path, contents: &[u8]) -> bool {
+        self.binary_by_name(path)
+            .unwrap_or_else(|| has_nul(&contents[..contents.len().min(self.sniff_bytes as usize)]))
+    }
+
+    /// Decide whether a file is binary from its name alone, if the policy allows that
+    /// without looking at its contents.
+    fn binary_by_name(&self, path: &Path) -> Option<bool> {
+        if self.policy == BinaryPolicy::None {
+            return Some(false);
+        }
+
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        if self
+            .always_count
+            .iter()
+            .any(|e| e.eq_ignore_ascii_case(&ext))
+        {
+            return Some(false);
+        }
+        if BINARY_EXTENSIONS.contains(&ext.as_str()) {
+            return Some(true);
+        }
+        match self.policy {
+            BinaryPolicy::NulSniff => None,
+            _ => Some(false),
+        }
+    }
+}
+
+/// Check whether the start of a file has a NUL byte and is not UTF-16 text, which is full
+/// of them.
+fn has_nul(head: &[u8]) -> bool {
+    head.contains(&0) && !encoding::is_utf16(head)
+}
+
+/// Read up to `len` bytes from the start of a file.
+fn read_head(path: &Path, len: u64) -> Option<Vec<u8>> {
+    let mut head = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(len)
+        .read_to_end(&mut head)
+        .ok()?;
+    Some(head)
+}
+
+/// Magic bytes at the start of common binary formats, with their MIME types.
+const MAGIC: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"BM", "image/bmp"),
+    (b"\x00\x00\x01\x00", "image/vnd.microsoft.icon"),
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"BZh", "application/x-bzip2"),
+    (b"\xfd7zXZ\x00", "application/x-xz"),
+    (b"\x28\xb5\x2f\xfd", "application/zstd"),
+    (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+    (b"\x7fELF", "application/x-elf"),
+    (b"MZ", "application/vnd.microsoft.portable-executable"),
+    (b"\xcf\xfa\xed\xfe", "application/x-mach-binary"),
+    (b"\xca\xfe\xba\xbe", "application/java-vm"),
+    (b"\x00asm", "application/wasm"),
+    (b"SQLite format 3\x00", "application/vnd.sqlite3"),
+    (b"ID3", "audio/mpeg"),
+    (b"OggS", "audio/ogg"),
+    (b"fLaC", "audio/flac"),
+    (b"wOFF", "font/woff"),
+    (b"wOF2", "font/woff2"),
+    (b"\x00\x01\x00\x00\x00", "font/ttf"),
+    (b"OTTO", "font/otf"),
+];
+
+/// Sniff the MIME type of a file from the magic bytes at its start, falling back to
+/// `text/plain` for text and `application/octet-stream` for unrecognized binary data.
+pub fn mime_type(path: &Path) -> &'static str {
+    let head = match read_head(path, DEFAULT_SNIFF_BYTES) {
+        Some(head) => head,
+        None => return "application/octet-stream",
+    };
+
+    if let Some((_, mime)) = MAGIC.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return mime;
+    }
+    // RIFF and ISO media containers put the format after the container header
+    match (head.get(..4), head.get(8..12)) {
+        (Some(b"RIFF"), Some(b"WEBP")) => return "image/webp",
+        (Some(b"RIFF"), Some(b"WAVE")) => return "audio/wav",
+        (Some(b"RIFF"), Some(b"AVI ")) => return "video/x-msvideo",
+        _ => (),
+    }
+    if head.get(4..8) == Some(b"ftyp") {
+        return "video/mp4";
+    }
+
+    if head.starts_with(b"\xff\xfe") {
+        return "text/plain; charset=utf-16le";
+    }
+    if head.starts_with(b"\xfe\xff") {
+        return "text/plain; charset=utf-16be";
+    }
+    match std::str::from_utf8(&head) {
+        // the head can end in the middle of a character
+        Err(e) if e.error_len().is_some() => "application/octet-stream",
+        _ if head.contains(&0) => "application/octet-stream",
+        _ => "text/plain; charset=utf-8",
+    }
+}
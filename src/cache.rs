@@ -0,0 +1,221 @@
+use rayon::prelude::*;
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::walk::WalkOptions;
+use crate::{count_lines_in_file, languages, Results};
+
+/// Get the per-user cache directory, `$XDG_CACHE_HOME/rust-cloc` or `~/.cache/rust-cloc`
+/// (`%LOCALAPPDATA%\rust-cloc` on Windows).
+pub fn cache_dir() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CACHE_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|h| PathBuf::from(h).join(".cache")))?;
+    Some(dir.join("rust-cloc"))
+}
+
+/// What a file looked like when it was counted, and its results.
+#[derive(Clone, Copy)]
+struct Entry {
+    size: u64,
+    /// Modification time in nanoseconds since the Unix epoch.
+    mtime: u128,
+    /// Hash of the contents, so a file that was only touched is not counted again.
+    hash: u64,
+    res: Results,
+}
+
+/// Get the size and modification time of a file.
+pub fn stat(path: &Path) -> Option<(u64, u128)> {
+    let meta = fs::metadata(path).ok()?;
+    let mtime = meta
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_nanos();
+    Some((meta.len(), mtime))
+}
+
+/// Hash the contents of a file.
+pub fn hash_file(path: &Path) -> io::Result<u64> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hasher = DefaultHasher::new();
+    let mut buf = [0; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            return Ok(hasher.finish());
+        }
+        hasher.write(&buf[..n]);
+    }
+}
+
+/// Get the first line of a cache or checkpoint file, which names everything besides a file's
+/// contents that its results depend on: the version of this program, which decides the built-in
+/// rules for blank lines, the language table, whether LFS pointers are resolved, and the
+/// other `settings` of the scan, like how binary files are detected.
+pub fn header(settings: &str, resolve_lfs: bool) -> String {
+    format!(
+        "# rust-cloc {} languages {:016x} lfs {} {}",
+        env!("CARGO_PKG_VERSION"),
+        languages::table_hash(),
+        resolve_lfs,
+        settings
+    )
+}
+
+/// Read a cache file.
+///
+/// After the header, each line of the cache is
+/// "size<TAB>mtime<TAB>hash<TAB>code<TAB>comment<TAB>empty<TAB>path". A cache with another
+/// header was written with different settings and is ignored as a whole. Lines that cannot
+/// be parsed are ignored and those files are simply counted again.
+fn load(cache: &Path, header: &str) -> HashMap<PathBuf, Entry> {
+    let mut entries = HashMap::new();
+    let file = match File::open(cache) {
+        Ok(f) => f,
+        Err(_) => return entries,
+    };
+
+    let mut lines = BufReader::new(file).lines();
+    if !matches!(lines.next(), Some(Ok(first)) if first == header) {
+        return entries;
+    }
+    for line in lines {
+        let line = match line {
+            Ok(l) => l,
+            _ => break,
+        };
+
+        let mut fields = line.splitn(7, '\t');
+        let mut num = || fields.next().and_then(|f| f.parse::<u128>().ok());
+        if let (Some(size), Some(mtime), Some(hash), Some(code), Some(comment), Some(empty)) =
+            (num(), num(), num(), num(), num(), num())
+        {
+            if let Some(path) = fields.next() {
+                entries.insert(
+                    PathBuf::from(path),
+                    Entry {
+                        size: size as u64,
+                        mtime,
+                        hash: hash as u64,
+                        res: Results {
+                            lines_of_code: code as usize,
+                            comment_lines: comment as usize,
+                            empty_lines: empty as usize,
+                        },
+                    },
+                );
+            }
+        }
+    }
+
+    entries
+}
+
+/// Get the results of a file from the cache if it has not changed, or count it otherwise.
+///
+/// A file with the same size and modification time as when it was cached is assumed to be
+/// unchanged. A file with the same size but a new modification time, like after switching
+/// git branches back and forth, is only counted again if its contents changed.
+fn count_file(path: &Path, cached: Option<&Entry>, opts: &WalkOptions) -> (Results, Option<Entry>) {
+    let (size, mtime) = match stat(path) {
+        Some(stat) => stat,
+        None => return (count_lines_in_file(path, opts), None),
+    };
+
+    match cached {
+        Some(e) if e.size == size && e.mtime == mtime => {
+            opts.tally.record_counted(size, e.res.total_lines());
+            return (e.res, Some(*e));
+        }
+        Some(e) if e.size == size => {
+            if let Ok(hash) = hash_file(path) {
+                if hash == e.hash {
+                    opts.tally.record_counted(size, e.res.total_lines());
+                    return (e.res, Some(Entry { mtime, ..*e }));
+                }
+            }
+        }
+        _ => (),
+    }
+
+    let res = count_lines_in_file(path, opts);
+    // a file that changed while it was read would be cached with the wrong results
+    let entry = match (hash_file(path), stat(path)) {
+        (Ok(hash), Some(after)) if after == (size, mtime) => Some(Entry {
+            size,
+            mtime,
+            hash,
+            res,
+        }),
+        _ => None,
+    };
+    (res, entry)
+}
+
+/// Count lines in each file found with `opts`, reusing the results of files that have not
+/// changed since they were saved in a cache file by an earlier run with the same `settings`.
+/// The cache is then replaced with the results of the current files, so files that no
+/// longer exist are forgotten.
+pub fn count_lines_per_file_cached(
+    files: &[PathBuf],
+    threads: usize,
+    cache: &Path,
+    settings: &str,
+    opts: &WalkOptions,
+) -> Vec<Results> {
+    let header = header(settings, opts.resolve_lfs);
+    let cached = load(cache, &header);
+    let counted = if threads > 1 {
+        files
+            .par_iter()
+            .map(|f| count_file(f, cached.get(f), opts))
+            .collect::<Vec<_>>()
+    } else {
+        files
+            .iter()
+            .map(|f| count_file(f, cached.get(f), opts))
+            .collect()
+    };
+
+    let mut out = format!("{}\n", header);
+    for (f, (_, entry)) in files.iter().zip(&counted) {
+        // paths with newlines cannot be stored in the line-based format, so they are just
+        // counted again every time
+        if let (Some(e), Some(path)) = (entry, f.to_str().filter(|p| !p.contains('\n'))) {
+            out.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                e.size,
+                e.mtime,
+                e.hash,
+                e.res.lines_of_code,
+                e.res.comment_lines,
+                e.res.empty_lines,
+                path
+            ));
+        }
+    }
+
+    // write the new cache next to the old one and swap it in, so an interrupted run never
+    // leaves a truncated cache behind
+    let mut tmp_name = cache.file_name().unwrap_or_default().to_owned();
+    tmp_name.push(format!(".tmp{}", std::process::id()));
+    let tmp = cache.with_file_name(tmp_name);
+    if let Err(e) = fs::write(&tmp, out).and_then(|_| fs::rename(&tmp, cache)) {
+        let _ = fs::remove_file(&tmp);
+        eprintln!("Unable to write cache {}: {}", cache.display(), e);
+    }
+
+    counted.into_iter().map(|(res, _)| res).collect()
+}
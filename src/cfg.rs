@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use crate::encoding;
+use crate::languages::{self, Classifier, LineKind};
+
+/// Languages with conditional compilation that can be recognized line by line.
+#[derive(Clone, Copy, PartialEq)]
+enum Lang {
+    C,
+    Rust,
+}
+
+impl Lang {
+    fn for_path(path: &Path) -> Option<Self> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("c" | "h" | "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx") => Some(Lang::C),
+            Some("rs") => Some(Lang::Rust),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Lang::C => "C/C++",
+            Lang::Rust => "Rust",
+        }
+    }
+}
+
+/// Lines of code split by whether they are always compiled.
+#[derive(Default)]
+struct CfgStats {
+    files: usize,
+    unconditional: usize,
+    /// Lines that are only compiled under some configuration.
+    conditional: usize,
+    /// Lines that are never compiled, like those in `#if 0` blocks.
+    disabled: usize,
+}
+
+/// An open preprocessor block.
+#[derive(Clone, Copy, PartialEq)]
+enum Block {
+    /// An include guard, which wraps the whole header but does not make it conditional.
+    Guard,
+    Conditional,
+    Disabled,
+}
+
+/// Get the directive and its argument from a preprocessor line like `#  ifdef FOO`.
+fn directive(line: &str) -> Option<(&str, &str)> {
+    let rest = line.trim().strip_prefix('#')?.trim_start();
+    Some(rest.split_once(char::is_whitespace).unwrap_or((rest, "")))
+}
+
+/// Split the code lines of a C or C++ file by the preprocessor blocks they are in.
+fn count_c(code: &[String], stats: &mut CfgStats) {
+    let mut blocks: Vec<Block> = Vec::new();
+
+    for (i, line) in code.iter().enumerate() {
+        // directive lines count as part of the enclosing block
+        if blocks.contains(&Block::Disabled) {
+            stats.disabled += 1;
+        } else if blocks.contains(&Block::Conditional) {
+            stats.conditional += 1;
+        } else {
+            stats.unconditional += 1;
+        }
+
+        let (name, arg) = match directive(line) {
+            Some(d) => d,
+            None => continue,
+        };
+        match name {
+            "if" if arg.trim() == "0" => blocks.push(Block::Disabled),
+            "ifndef" if blocks.is_empty() => {
+                // `#ifndef FOO_H` directly followed by `#define FOO_H` is an include guard
+                let guard =
+                    code.get(i + 1).and_then(|l| directive(l)) == Some(("define", arg.trim()));
+                blocks.push(if guard {
+                    Block::Guard
+                } else {
+                    Block::Conditional
+                });
+            }
+            "if" | "ifdef" | "ifndef" => blocks.push(Block::Conditional),
+            "else" | "elif" => {
+                // the other branch of `#if 0` is compiled, but only because of the condition
+                if let Some(b @ Block::Disabled) = blocks.last_mut() {
+                    *b = Block::Conditional;
+                }
+            }
+            "endif" => {
+                blocks.pop();
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Split the code lines of a Rust file by whether they are in items gated by `#[cfg(...)]`.
+///
+/// A file with an inner `#![cfg(...)]` attribute is conditional as a whole. Otherwise the
+/// item after a `#[cfg(...)]` attribute is conditional, up to its closing brace or semicolon.
+/// Modules declared with `#[cfg(...)] mod foo;` live in other files and are not followed.
+fn count_rust(code: &[String], stats: &mut CfgStats) {
+    if code.iter().any(|l| l.trim().starts_with("#![cfg(")) {
+        stats.conditional += code.len();
+        return;
+    }
+
+    // whether a `#[cfg(...)]` attribute was seen, and once its item started, the brace
+    // depth and whether the item's body was opened yet
+    let mut pending = false;
+    let mut item: Option<(usize, bool)> = None;
+
+    for line in code.iter().map(|l| l.trim()) {
+        if let Some((depth, opened)) = item.as_mut() {
+            stats.conditional += 1;
+            let open = line.matches('{').count();
+            *opened |= open > 0;
+            *depth = (*depth + open).saturating_sub(line.matches('}').count());
+            if (*opened && *depth == 0) || (!*opened && line.ends_with(';')) {
+                item = None;
+            }
+            continue;
+        }
+
+        if line.starts_with("#[cfg(") {
+            pending = true;
+            stats.conditional += 1;
+        } else if !pending {
+            stats.unconditional += 1;
+        } else if line.starts_with("#[") {
+            // further attributes on the gated item
+            stats.conditional += 1;
+        } else {
+            pending = false;
+            let open = line.matches('{').count();
+            let depth = open.saturating_sub(line.matches('}').count());
+            stats.conditional += 1;
+            if !((open > 0 && depth == 0) || (open == 0 && line.ends_with(';'))) {
+                item = Some((depth, open > 0));
+            }
+        }
+    }
+}
+
+/// Print how many lines of code are always compiled, only compiled under some
+/// configuration, or never compiled, for C/C++ and Rust files.
+pub fn print_cfg_stats(files: &[PathBuf]) {
+    let mut stats: BTreeMap<&str, CfgStats> = BTreeMap::new();
+
+    for f in files {
+        let lang = match Lang::for_path(f) {
+            Some(l) => l,
+            None => continue,
+        };
+        let file = match File::open(f) {
+            Ok(file) => file,
+            Err(_) => continue,
+        };
+        // only lines of code are split, so comments and empty lines are left out
+        let mut classifier = Classifier::new(languages::for_path(f));
+        let code = encoding::lines(BufReader::new(file))
+            .map_while(Result::ok)
+            .filter(|l| !l.trim().is_empty() && classifier.classify(l) == LineKind::Code)
+            .collect::<Vec<_>>();
+
+        let s = stats.entry(lang.name()).or_default();
+        s.files += 1;
+        match lang {
+            Lang::C => count_c(&code, s),
+            Lang::Rust => count_rust(&code, s),
+        }
+    }
+
+    for (name, s) in stats {
+        println!(
+            "In {} {} files, {} lines of code are unconditional, {} are conditionally compiled and \
+             {} are disabled.",
+            s.files, name, s.unconditional, s.conditional, s.disabled
+        );
+    }
+}
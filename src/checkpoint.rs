@@ -0,0 +1,182 @@
+use rayon::prelude::*;
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use crate::cache;
+use crate::walk::WalkOptions;
+use crate::{count_lines_in_file_checked, exit, Results};
+
+/// Number of files counted between two writes of the checkpoint file.
+const BATCH_SIZE: usize = 1000;
+
+/// Get the default checkpoint file for a scan root, which lives in the per-user cache
+/// directory so that an interrupted scan can find it again without extra flags. The
+/// directory is created if needed.
+pub fn default_path(root: &Path) -> Option<PathBuf> {
+    let root = fs::canonicalize(root).unwrap_or_else(|_| root.to_owned());
+    let mut hasher = DefaultHasher::new();
+    root.hash(&mut hasher);
+    let dir = cache::cache_dir()?.join("checkpoints");
+    let _ = fs::create_dir_all(&dir);
+    Some(dir.join(format!("{:016x}.checkpoint", hasher.finish())))
+}
+
+/// What a file looked like when it was counted, and its results.
+struct Entry {
+    size: u64,
+    /// Modification time in nanoseconds since the Unix epoch.
+    mtime: u128,
+    res: Results,
+}
+
+/// Read the per-file results saved by a previous, interrupted scan, or nothing if there is
+/// no checkpoint with the same `header`.
+///
+/// The checkpoint starts with the same header as a cache file. After it, each line is
+/// "size<TAB>mtime<TAB>code<TAB>comment<TAB>empty<TAB>path". A checkpoint with another
+/// header was written with different settings and is ignored as a whole. Lines that cannot
+/// be parsed, like a partially written last line, are ignored and those files are simply
+/// counted again.
+fn load(checkpoint: &Path, header: &str) -> Option<HashMap<PathBuf, Entry>> {
+    let file = File::open(checkpoint).ok()?;
+    let mut lines = BufReader::new(file).lines();
+    if !matches!(lines.next(), Some(Ok(first)) if first == header) {
+        return None;
+    }
+
+    let mut done = HashMap::new();
+    for line in lines {
+        let line = match line {
+            Ok(l) => l,
+            _ => break,
+        };
+
+        let mut fields = line.splitn(6, '\t');
+        let mut num = || fields.next().and_then(|f| f.parse::<u128>().ok());
+        if let (Some(size), Some(mtime), Some(code), Some(comment), Some(empty)) =
+            (num(), num(), num(), num(), num())
+        {
+            if let Some(path) = fields.next() {
+                done.insert(
+                    PathBuf::from(path),
+                    Entry {
+                        size: size as u64,
+                        mtime,
+                        res: Results {
+                            lines_of_code: code as usize,
+                            comment_lines: comment as usize,
+                            empty_lines: empty as usize,
+                        },
+                    },
+                );
+            }
+        }
+    }
+
+    Some(done)
+}
+
+/// Count a file, and get its size and modification time if it can be saved in the
+/// checkpoint: it was read completely and did not change while it was read.
+fn count_file(path: &Path, opts: &WalkOptions) -> (Results, Option<(u64, u128)>) {
+    let before = cache::stat(path);
+    let (res, complete) = count_lines_in_file_checked(path, opts);
+    let stat = before.filter(|&before| complete && cache::stat(path) == Some(before));
+    (res, stat)
+}
+
+/// Count lines in each file, periodically saving per-file results to a checkpoint file.
+/// Files recorded in the checkpoint by an earlier run with the same `settings` are not read
+/// again unless their size or modification time changed since. Files that could not be read completely are not
+/// recorded, so they are tried again when the scan is resumed.
+/// The checkpoint is removed once every file has been counted.
+pub fn count_lines_per_file_resumable(
+    files: &[PathBuf],
+    threads: usize,
+    checkpoint: &Path,
+    settings: &str,
+    opts: &WalkOptions,
+) -> Vec<Results> {
+    let header = cache::header(settings, opts.resolve_lfs);
+    let done = load(checkpoint, &header);
+    // a checkpoint from a scan with other settings is started over
+    let fresh = done.is_none();
+    let done = done.unwrap_or_default();
+    let mut per_file = files
+        .iter()
+        .map(|f| {
+            let e = done.get(f)?;
+            (cache::stat(f) == Some((e.size, e.mtime))).then_some(e.res)
+        })
+        .collect::<Vec<_>>();
+    let todo = (0..files.len())
+        .filter(|&i| per_file[i].is_none())
+        .collect::<Vec<_>>();
+
+    if !todo.is_empty() && files.len() > todo.len() {
+        eprintln!(
+            "Resuming scan: {} of {} files were already counted.",
+            files.len() - todo.len(),
+            files.len()
+        );
+    }
+
+    let failed = |e: io::Error| -> ! {
+        eprintln!(
+            "Unable to write checkpoint file {}: {}",
+            checkpoint.display(),
+            e
+        );
+        process::exit(exit::USAGE);
+    };
+    let mut writer = BufWriter::new(
+        OpenOptions::new()
+            .create(true)
+            .append(!fresh)
+            .write(true)
+            .truncate(fresh)
+            .open(checkpoint)
+            .unwrap_or_else(|e| failed(e)),
+    );
+    if fresh {
+        writeln!(writer, "{}", header).unwrap_or_else(|e| failed(e));
+    }
+
+    for batch in todo.chunks(BATCH_SIZE) {
+        let batch_res = if threads > 1 {
+            batch
+                .par_iter()
+                .map(|&i| count_file(&files[i], opts))
+                .collect::<Vec<_>>()
+        } else {
+            batch.iter().map(|&i| count_file(&files[i], opts)).collect()
+        };
+
+        for (&i, (res, stat)) in batch.iter().zip(batch_res) {
+            // paths with newlines cannot be stored in the line-based format, so they are
+            // just counted again if the scan is resumed
+            let path = files[i].to_str().filter(|p| !p.contains('\n'));
+            if let (Some(path), Some((size, mtime))) = (path, stat) {
+                writeln!(
+                    writer,
+                    "{}\t{}\t{}\t{}\t{}\t{}",
+                    size, mtime, res.lines_of_code, res.comment_lines, res.empty_lines, path
+                )
+                .unwrap_or_else(|e| failed(e));
+            }
+            per_file[i] = Some(res);
+        }
+
+        writer.flush().unwrap_or_else(|e| failed(e));
+    }
+
+    drop(writer);
+    let _ = fs::remove_file(checkpoint);
+    per_file.into_iter().map(Option::unwrap).collect()
+}
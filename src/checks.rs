@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::Results;
+
+/// How a measured share is compared against the limit of a rule.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Op {
+    fn holds(self, value: f64, limit: f64) -> bool {
+        match self {
+            Op::Gt => value > limit,
+            Op::Ge => value >= limit,
+            Op::Lt => value < limit,
+            Op::Le => value <= limit,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+        }
+    }
+}
+
+/// A rule like `js>40%` or `ts>=80% of js+ts` that fails when the share of code lines
+/// in some file types, out of all code (or out of the code in other file types), is
+/// not within the limit.
+///
+/// The rule describes the condition that should fail the run, so `js>40%` fails when
+/// more than 40% of the code is JavaScript, while `ts>=80% of js+ts` is written the
+/// other way around with `--fail-if-lang-share 'ts<80% of js+ts'`.
+#[derive(Debug, Clone)]
+pub struct ShareRule {
+    exts: Vec<String>,
+    op: Op,
+    percent: f64,
+    of: Option<Vec<String>>,
+}
+
+impl ShareRule {
+    /// Compute the share of code lines matched by this rule, as a percentage.
+    fn share(&self, res_map: &HashMap<String, Results>) -> f64 {
+        let code_in = |exts: &[String]| -> usize {
+            exts.iter()
+                .filter_map(|e| res_map.get(e))
+                .map(|r| r.lines_of_code)
+                .sum()
+        };
+
+        let part = code_in(&self.exts);
+        let whole = match &self.of {
+            Some(of) => code_in(of),
+            None => res_map.values().map(|r| r.lines_of_code).sum(),
+        };
+
+        if whole == 0 {
+            0.0
+        } else {
+            (part as f64) / (whole as f64) * 100.0f64
+        }
+    }
+}
+
+impl FromStr for ShareRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split_exts = |s: &str| {
+            s.split('+')
+                .map(|e| e.trim().trim_start_matches('.').to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        let (rule, of) = match s.split_once(" of ") {
+            Some((rule, of)) => (rule, Some(split_exts(of))),
+            None => (s, None),
+        };
+
+        // check the two-character operators first so `>=` is not read as `>`
+        let (exts, op, limit) = [("<=", Op::Le), (">=", Op::Ge), ("<", Op::Lt), (">", Op::Gt)]
+            .iter()
+            .find_map(|&(sym, op)| rule.split_once(sym).map(|(a, b)| (a, op, b)))
+            .ok_or_else(|| format!("missing comparison operator in \"{}\"", s))?;
+
+        let percent = limit
+            .trim()
+            .trim_end_matches('%')
+            .parse::<f64>()
+            .map_err(|_| format!("invalid percentage in \"{}\"", s))?;
+
+        Ok(ShareRule {
+            exts: split_exts(exts),
+            op,
+            percent,
+            of,
+        })
+    }
+}
+
+impl fmt::Display for ShareRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}%",
+            self.exts.join("+"),
+            self.op.symbol(),
+            self.percent
+        )?;
+        if let Some(of) = &self.of {
+            write!(f, " of {}", of.join("+"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Evaluate every share rule against per-file-type results, returning a message for
+/// each rule that was triggered.
+pub fn check_shares(rules: &[ShareRule], res_map: &HashMap<String, Results>) -> Vec<String> {
+    rules
+        .iter()
+        .filter_map(|rule| {
+            let share = rule.share(res_map);
+            if rule.op.holds(share, rule.percent) {
+                Some(format!(
+                    "Language share check failed: {} (actual {:.2}%)",
+                    rule, share
+                ))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Limits on the size of the counted code. Each one that is set fails the run when it is
+/// exceeded.
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    /// Maximum number of lines of code in total.
+    pub max_lines: Option<usize>,
+    /// Maximum number of lines, including comments and blank lines, in any one file.
+    pub max_file_lines: Option<usize>,
+    /// Maximum percentage of blank lines out of all lines.
+    pub max_percent_blank: Option<f64>,
+}
+
+impl Limits {
+    /// Check whether any limit is set.
+    pub fn is_empty(&self) -> bool {
+        self.max_lines.is_none()
+            && self.max_file_lines.is_none()
+            && self.max_percent_blank.is_none()
+    }
+
+    /// Check whether the limits need the results of each file.
+    pub fn need_per_file(&self) -> bool {
+        self.max_file_lines.is_some()
+    }
+}
+
+/// Evaluate the limits against per-file-type results and, for `max_file_lines`, the
+/// results of each file, returning a message for each limit that was exceeded.
+pub fn check_limits(
+    limits: &Limits,
+    res_map: &HashMap<String, Results>,
+    per_file: Option<(&[PathBuf], &[Results])>,
+) -> Vec<String> {
+    let mut violations = Vec::new();
+    let total = res_map.values().fold(Results::new(), |mut acc, r| {
+        acc.lines_of_code += r.lines_of_code;
+        acc.comment_lines += r.comment_lines;
+        acc.empty_lines += r.empty_lines;
+        acc
+    });
+
+    if let Some(max) = limits.max_lines {
+        if total.lines_of_code > max {
+            violations.push(format!(
+                "Limit exceeded: --max-lines {} (actual {} lines of code)",
+                max, total.lines_of_code
+            ));
+        }
+    }
+
+    if let (Some(max), Some((paths, per_file))) = (limits.max_file_lines, per_file) {
+        let mut too_long = paths
+            .iter()
+            .zip(per_file)
+            .map(|(p, r)| (p, r.lines_of_code + r.comment_lines + r.empty_lines))
+            .filter(|&(_, lines)| lines > max)
+            .collect::<Vec<_>>();
+        too_long.sort();
+        for (path, lines) in too_long {
+            violations.push(format!(
+                "Limit exceeded: --max-file-lines {} ({} has {} lines)",
+                max,
+                path.display(),
+                lines
+            ));
+        }
+    }
+
+    if let Some(max) = limits.max_percent_blank {
+        let lines = total.lines_of_code + total.comment_lines + total.empty_lines;
+        let percent = if lines == 0 {
+            0.0
+        } else {
+            (total.empty_lines as f64) / (lines as f64) * 100.0f64
+        };
+        if percent > max {
+            violations.push(format!(
+                "Limit exceeded: --max-percent-blank {} (actual {:.2}%)",
+                max, percent
+            ));
+        }
+    }
+
+    violations
+}
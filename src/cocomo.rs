@@ -0,0 +1,87 @@
+use clap::ArgEnum;
+
+/// Average annual salary of a developer used for cost estimates, the same default as
+/// sloccount and scc.
+pub const DEFAULT_ANNUAL_SALARY: f64 = 56286.0;
+
+/// Multiplier on salaries for the overhead of employing developers (office space,
+/// equipment, management, ...), the same as sloccount.
+const OVERHEAD: f64 = 2.4;
+
+/// Kinds of projects in the basic COCOMO model, from small teams on familiar problems to
+/// projects with tight hardware and operational constraints.
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum CocomoMode {
+    /// Small teams with good experience working on well-understood requirements
+    Organic,
+    /// Medium teams with mixed experience and a mix of rigid and less rigid requirements
+    Semidetached,
+    /// Projects developed under tight hardware, software and operational constraints
+    Embedded,
+}
+
+impl CocomoMode {
+    /// Get the `a`, `b`, `c` and `d` coefficients of the mode, where effort is
+    /// `a * KLOC^b` person-months and the schedule is `c * effort^d` months.
+    fn coefficients(self) -> (f64, f64, f64, f64) {
+        match self {
+            CocomoMode::Organic => (2.4, 1.05, 2.5, 0.38),
+            CocomoMode::Semidetached => (3.0, 1.12, 2.5, 0.35),
+            CocomoMode::Embedded => (3.6, 1.20, 2.5, 0.32),
+        }
+    }
+}
+
+/// Effort, schedule and cost estimated by the basic COCOMO model.
+#[derive(Clone, Copy, Debug)]
+pub struct Estimate {
+    /// Effort in person-months.
+    pub effort: f64,
+    /// Development time in months.
+    pub schedule: f64,
+    /// Average number of developers working at the same time.
+    pub people: f64,
+    /// Cost of the effort, with overhead, in the currency of `annual_salary`.
+    pub cost: f64,
+}
+
+/// Estimate the effort, schedule and cost of developing `code` lines of code, where
+/// developers are paid `annual_salary` a year.
+pub fn estimate(code: usize, mode: CocomoMode, annual_salary: f64) -> Estimate {
+    let (a, b, c, d) = mode.coefficients();
+    let kloc = code as f64 / 1000.0;
+    let effort = a * kloc.powf(b);
+    let schedule = c * effort.powf(d);
+    let people = if schedule > 0.0 {
+        effort / schedule
+    } else {
+        0.0
+    };
+
+    Estimate {
+        effort,
+        schedule,
+        people,
+        cost: effort * annual_salary / 12.0 * OVERHEAD,
+    }
+}
+
+/// Print a COCOMO estimate for `code` lines of code.
+pub fn print_estimate(code: usize, mode: CocomoMode, annual_salary: f64) {
+    let e = estimate(code, mode, annual_salary);
+    println!(
+        "Basic COCOMO estimate ({}):",
+        mode.to_possible_value().unwrap().get_name()
+    );
+    println!(
+        "  Effort:   {:.2} person-months ({:.2} person-years)",
+        e.effort,
+        e.effort / 12.0
+    );
+    println!("  Schedule: {:.2} months", e.schedule);
+    println!("  People:   {:.2}", e.people);
+    println!(
+        "  Cost:     {:.0} (at an annual salary of {:.0}, with overhead of {})",
+        e.cost, annual_salary, OVERHEAD
+    );
+}
@@ -0,0 +1,96 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use crate::encoding;
+
+/// Number of files listed as the worst offenders.
+const TOP_FILES: usize = 10;
+
+/// Beginnings of statements that are unlikely to start a sentence of prose.
+const KEYWORDS: &[&str] = &[
+    "if (", "if(", "for (", "for(", "while (", "while(", "} else", "return;", "let mut ", "def ",
+    "fn ", "import ", "#include", "print(", "printf(",
+];
+
+/// Get the line comment marker for a file, if it is a type of source file that is checked.
+fn comment_marker(path: &Path) -> Option<&'static str> {
+    match path.extension().and_then(|e| e.to_str())? {
+        "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "hh" | "rs" | "go" | "java" | "kt" | "scala"
+        | "swift" | "cs" | "js" | "jsx" | "mjs" | "ts" | "tsx" | "php" => Some("//"),
+        "py" | "rb" | "sh" | "bash" | "pl" | "r" | "R" => Some("#"),
+        "sql" | "lua" | "hs" => Some("--"),
+        _ => None,
+    }
+}
+
+/// Check whether the text of a comment looks like a line of code, based on how it ends,
+/// whether it calls something, and how it starts.
+fn looks_like_code(text: &str) -> bool {
+    let text = text.trim();
+    if text.is_empty() {
+        return false;
+    }
+
+    text.ends_with(';')
+        || text.ends_with('{')
+        || text == "}"
+        || text.contains(") {")
+        || KEYWORDS.iter().any(|k| text.starts_with(k))
+        || (text.contains(" = ") && text.ends_with(')'))
+}
+
+/// Count the comment lines in a file that are part of blocks that mostly look like code.
+fn count_commented_code(path: &Path, marker: &str) -> usize {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return 0,
+    };
+
+    let mut total = 0;
+    // size of the current block of consecutive comment lines, and how many look like code
+    let mut block = (0, 0);
+    let mut end_block = |block: &mut (usize, usize)| {
+        if block.1 > 0 && block.1 * 2 >= block.0 {
+            total += block.1;
+        }
+        *block = (0, 0);
+    };
+
+    for line in encoding::lines(BufReader::new(file)).map_while(Result::ok) {
+        let comment = line.trim_start().strip_prefix(marker);
+        match comment {
+            // doc comments like `///` and `//!` are documentation by definition
+            Some(text) if !text.starts_with('/') && !text.starts_with('!') => {
+                block.0 += 1;
+                if looks_like_code(text) {
+                    block.1 += 1;
+                }
+            }
+            _ => end_block(&mut block),
+        }
+    }
+    end_block(&mut block);
+
+    total
+}
+
+/// Print how many comment lines look like commented-out code rather than documentation,
+/// followed by the files with the most of them.
+pub fn print_commented_code(files: &[PathBuf]) {
+    let mut counts = files
+        .iter()
+        .filter_map(|f| comment_marker(f).map(|m| (f, count_commented_code(f, m))))
+        .filter(|(_, n)| *n > 0)
+        .collect::<Vec<_>>();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    println!(
+        "There are {} lines of commented-out code in {} files.",
+        counts.iter().map(|(_, n)| n).sum::<usize>(),
+        counts.len()
+    );
+    for (f, n) in counts.iter().take(TOP_FILES) {
+        println!("  {} {}", n, f.display());
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::cache::hash_file;
+use crate::report::{LanguageStats, Report};
+use crate::walk::WalkOptions;
+use crate::{file_size, Results};
+
+/// One file counted in a scan.
+#[derive(Clone, Debug)]
+pub struct ScannedFile {
+    /// Path relative to the scanned directory.
+    pub path: PathBuf,
+    pub res: Results,
+    pub bytes: u64,
+    /// Hash of the contents, used to recognize files that were moved, if they could be read.
+    pub hash: Option<u64>,
+}
+
+impl ScannedFile {
+    /// Read the size and hash of a counted file found below `root` with `opts`.
+    pub fn new(root: &Path, file: &Path, res: Results, opts: &WalkOptions) -> Self {
+        ScannedFile {
+            path: file.strip_prefix(root).unwrap_or(file).to_owned(),
+            res,
+            bytes: file_size(file, opts),
+            hash: hash_file(&opts.contents_path(file)).ok(),
+        }
+    }
+
+    /// Get the hash a moved file is recognized by. Files that could not be read and empty
+    /// files have none, since their contents say nothing about where they came from.
+    fn rename_key(&self) -> Option<u64> {
+        self.hash.filter(|_| self.bytes > 0)
+    }
+
+    /// Check whether a file at the same path changed. Without hashes, the counts are compared.
+    fn differs_from(&self, other: &ScannedFile) -> bool {
+        match (self.hash, other.hash) {
+            (Some(a), Some(b)) => a != b,
+            _ => (self.res, self.bytes) != (other.res, other.bytes),
+        }
+    }
+}
+
+/// Everything that was counted in a directory together with the results of each file, so
+/// it can be compared with another scan. Paths that could not be counted are listed in the
+/// report.
+pub struct Scan {
+    pub report: Report,
+    /// Files sorted by path.
+    pub files: Vec<ScannedFile>,
+}
+
+/// Stats for a group of files before and after a change.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Delta {
+    pub before: LanguageStats,
+    pub after: LanguageStats,
+}
+
+impl Delta {
+    /// Get the change in the number of files.
+    pub fn files(&self) -> i64 {
+        self.after.files as i64 - self.before.files as i64
+    }
+
+    /// Get the change in lines of code.
+    pub fn code(&self) -> i64 {
+        self.after.res.lines_of_code as i64 - self.before.res.lines_of_code as i64
+    }
+
+    /// Get the change in comment lines.
+    pub fn comments(&self) -> i64 {
+        self.after.res.comment_lines as i64 - self.before.res.comment_lines as i64
+    }
+
+    /// Get the change in empty lines.
+    pub fn empty(&self) -> i64 {
+        self.after.res.empty_lines as i64 - self.before.res.empty_lines as i64
+    }
+
+    /// Get the change in total size.
+    pub fn bytes(&self) -> i64 {
+        self.after.bytes as i64 - self.before.bytes as i64
+    }
+}
+
+/// How a file differs between two scans.
+#[derive(Clone, Debug)]
+pub enum FileChange {
+    Added(ScannedFile),
+    Removed(ScannedFile),
+    /// A file at the same path with different contents.
+    Modified {
+        before: ScannedFile,
+        after: ScannedFile,
+    },
+    /// A file that was moved to another path without changing its contents.
+    Renamed {
+        before: ScannedFile,
+        after: ScannedFile,
+    },
+}
+
+impl FileChange {
+    /// Get the path of the file after the change, or before it for a removed file.
+    pub fn path(&self) -> &Path {
+        match self {
+            FileChange::Added(f) | FileChange::Removed(f) => &f.path,
+            FileChange::Modified { after, .. } | FileChange::Renamed { after, .. } => &after.path,
+        }
+    }
+}
+
+/// The differences between two scans, in total, for each file type or language and for
+/// each file.
+///
+/// Both scans should be made with the same settings, so their file types are comparable.
+pub struct Comparison {
+    pub total: Delta,
+    /// Stats for each file type or language in either scan, sorted by name.
+    pub types: Vec<(String, Delta)>,
+    /// Files that were added, removed, modified or renamed, sorted by path. Files that did
+    /// not change are left out.
+    pub files: Vec<FileChange>,
+}
+
+impl Comparison {
+    /// Compare a scan with a later one.
+    pub fn between(before: &Scan, after: &Scan) -> Self {
+        Comparison {
+            files: file_changes(&before.files, &after.files),
+            ..Comparison::between_reports(&before.report, &after.report)
+        }
+    }
+
+    /// Compare the totals and file types of two reports, like a report saved by an earlier
+    /// run and the current one. Without the results of each file, no files are listed.
+    pub fn between_reports(before: &Report, after: &Report) -> Self {
+        let mut types: BTreeMap<String, Delta> = BTreeMap::new();
+        for (name, stats) in &before.types {
+            types.entry(name.clone()).or_default().before = *stats;
+        }
+        for (name, stats) in &after.types {
+            types.entry(name.clone()).or_default().after = *stats;
+        }
+
+        Comparison {
+            total: Delta {
+                before: before.total,
+                after: after.total,
+            },
+            types: types.into_iter().collect(),
+            files: Vec::new(),
+        }
+    }
+}
+
+/// Match up the files of two scans by path, and files that were only removed or added by
+/// their contents, to find how each file changed.
+fn file_changes(before: &[ScannedFile], after: &[ScannedFile]) -> Vec<FileChange> {
+    let after_paths = after.iter().map(|f| &f.path).collect::<HashSet<_>>();
+    let before_by_path = before
+        .iter()
+        .map(|f| (&f.path, f))
+        .collect::<HashMap<_, _>>();
+
+    // files that are only in the earlier scan, by contents, in path order so each added
+    // file is paired with the first removed file that has the same contents
+    let mut removed: HashMap<u64, Vec<&ScannedFile>> = HashMap::new();
+    let mut unmatched = Vec::new();
+    for f in before.iter().rev() {
+        if !after_paths.contains(&f.path) {
+            match f.rename_key() {
+                Some(key) => removed.entry(key).or_default().push(f),
+                None => unmatched.push(f),
+            }
+        }
+    }
+
+    let mut changes = Vec::new();
+    for f in after {
+        match before_by_path.get(&f.path) {
+            Some(b) if b.differs_from(f) => changes.push(FileChange::Modified {
+                before: (*b).clone(),
+                after: f.clone(),
+            }),
+            Some(_) => (),
+            None => match f
+                .rename_key()
+                .and_then(|key| removed.get_mut(&key))
+                .and_then(|r| r.pop())
+            {
+                Some(b) => changes.push(FileChange::Renamed {
+                    before: b.clone(),
+                    after: f.clone(),
+                }),
+                None => changes.push(FileChange::Added(f.clone())),
+            },
+        }
+    }
+    changes.extend(
+        removed
+            .into_values()
+            .flatten()
+            .chain(unmatched)
+            .map(|f| FileChange::Removed(f.clone())),
+    );
+
+    changes.sort_by(|a, b| a.path().cmp(b.path()));
+    changes
+}
@@ -0,0 +1,437 @@
+use clap::{App, ArgMatches};
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the project-local configuration file, looked up in the scanned directory.
+pub const PROJECT_FILE: &str = ".cloc.toml";
+
+/// A value in a configuration file.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Str(String),
+    /// A number, kept as written so it is parsed by the flag it is passed to.
+    Num(String),
+    Bool(bool),
+    Array(Vec<Value>),
+}
+
+/// Flags that a project's `.cloc.toml` may set. The file comes with the tree being counted,
+/// which may not be trusted, so it can only change what is counted and how the results are
+/// printed: never run programs, read or write files elsewhere, reach the network, or use
+/// more threads than whoever runs the scan chose.
+const PROJECT_FLAGS: &[&str] = &[
+    "always-count-ext",
+    "api-surface",
+    "attribute-to",
+    "binary-policy",
+    "by-dir",
+    "by-ext",
+    "by-file",
+    "by-lang",
+    "by-root",
+    "cfg-stats",
+    "classification-version",
+    "cocomo",
+    "cocomo-mode",
+    "cocomo-rate",
+    "colocation",
+    "commented-code",
+    "concentration",
+    "count-binary",
+    "depth",
+    "eol-rule",
+    "exclude",
+    "fail-if-lang-share",
+    "family",
+    "fan-out",
+    "files-only",
+    "group-by",
+    "include",
+    "indent-stats",
+    "literal-density",
+    "max-depth",
+    "max-file-lines",
+    "max-line-width",
+    "max-lines",
+    "max-percent-blank",
+    "near-duplicates",
+    "no-header",
+    "outlier-bucket",
+    "perm-stats",
+    "sniff-bytes",
+    "sort",
+    "strict",
+    "strip-path-prefix",
+    "tab-width",
+    "top-dirs",
+    "top-dirs-direct",
+    "unreferenced",
+    "weight",
+    "with-mime",
+];
+
+/// Settings read from a configuration file, in the order they were written.
+#[derive(Debug, Default)]
+pub struct Config {
+    pub path: PathBuf,
+    pub settings: Vec<(String, Value)>,
+    /// Languages defined in `[languages.ID]` tables, each named after the language's ID.
+    pub languages: Vec<Table>,
+    /// Whether this is the `.cloc.toml` of the scanned directory, which may only set
+    /// some flags.
+    pub project: bool,
+}
+
+impl Config {
+    /// Read a configuration file, with an error message if it cannot be read or is invalid.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let src = fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read config file {}: {}", path.display(), e))?;
+        Config::parse(&src)
+            .map(|config| Config {
+                path: path.to_owned(),
+                ..config
+            })
+            .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))
+    }
+
+    /// Parse the subset of TOML used for configuration: `key = value` lines with strings,
+    /// numbers, booleans and arrays of those, and `#` comments. Arrays can span lines.
+    /// Languages are defined in `[languages.ID]` tables, like the tables of a languages file.
+    pub fn parse(src: &str) -> Result<Config, String> {
+        let mut tables = parse_tables(src)?.into_iter();
+        let settings = tables.next().unwrap_or_default().settings;
+        let languages = tables
+            .map(|table| match table.name.strip_prefix("languages.") {
+                Some(id) if !id.is_empty() => Ok(Table {
+                    name: id.to_owned(),
+                    ..table
+                }),
+                _ => Err(format!(
+                    "line {}: only `[languages.ID]` tables are supported",
+                    table.line
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Config {
+            settings,
+            languages,
+            ..Config::default()
+        })
+    }
+}
+
+/// Settings under a `[name]` table header.
+#[derive(Debug, Default)]
+pub struct Table {
+    pub name: String,
+    /// Line of the table header.
+    pub line: usize,
+    pub settings: Vec<(String, Value)>,
+}
+
+/// Parse the same subset of TOML as [`Config::parse`], with `[name]` table headers.
+///
+/// The settings before the first header are returned as a table with an empty name,
+/// followed by each table in the order they were written.
+pub fn parse_tables(src: &str) -> Result<Vec<Table>, String> {
+    let mut tables = vec![Table::default()];
+    let mut pending = String::new();
+    let mut start_line = 0;
+
+    for (i, line) in src.lines().enumerate() {
+        if pending.is_empty() {
+            start_line = i + 1;
+        }
+        pending.push_str(strip_comment(line));
+        pending.push('\n');
+
+        // keep reading lines until every array is closed
+        if bracket_depth(&pending) > 0 {
+            continue;
+        }
+
+        let entry = pending.trim().to_owned();
+        pending.clear();
+        if entry.is_empty() {
+            continue;
+        }
+        if let Some(name) = entry.strip_prefix('[') {
+            let name = name
+                .strip_suffix(']')
+                .map(|n| n.trim().trim_matches('"'))
+                .filter(|n| !n.is_empty() && !n.contains(['[', ']']))
+                .ok_or_else(|| format!("line {}: invalid table header", start_line))?;
+            tables.push(Table {
+                name: name.to_owned(),
+                line: start_line,
+                settings: Vec::new(),
+            });
+            continue;
+        }
+
+        let (key, value) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected `key = value`", start_line))?;
+        let value = parse_value(value.trim()).map_err(|e| format!("line {}: {}", start_line, e))?;
+        tables
+            .last_mut()
+            .unwrap()
+            .settings
+            .push((key.trim().trim_matches('"').to_owned(), value));
+    }
+
+    if !pending.trim().is_empty() {
+        return Err(format!("line {}: unclosed array", start_line));
+    }
+    Ok(tables)
+}
+
+/// Remove a `#` comment from a line, unless the `#` is inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '#') => return &line[..i],
+            _ => (),
+        }
+        escaped = false;
+    }
+    line
+}
+
+/// Count how many arrays are still open at the end of some text, ignoring brackets in
+/// strings.
+fn bracket_depth(s: &str) -> i32 {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for c in s.chars() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '[') => depth += 1,
+            (None, ']') => depth -= 1,
+            _ => (),
+        }
+        escaped = false;
+    }
+    depth
+}
+
+fn parse_value(s: &str) -> Result<Value, String> {
+    if let Some(inner) = s.strip_prefix('[') {
+        let inner = inner
+            .strip_suffix(']')
+            .ok_or_else(|| format!("invalid array `{}`", s))?;
+        return split_items(inner)
+            .into_iter()
+            .map(|item| parse_value(&item))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array);
+    }
+    if let Some(inner) = s.strip_prefix('\'') {
+        return inner
+            .strip_suffix('\'')
+            .map(|v| Value::Str(v.to_owned()))
+            .ok_or_else(|| format!("unterminated string `{}`", s));
+    }
+    if let Some(inner) = s.strip_prefix('"') {
+        let inner = inner
+            .strip_suffix('"')
+            .ok_or_else(|| format!("unterminated string `{}`", s))?;
+        return unescape(inner).map(Value::Str);
+    }
+
+    match s {
+        "true" => Ok(Value::Bool(true)),
+        "false" => Ok(Value::Bool(false)),
+        _ if s.replace('_', "").parse::<f64>().is_ok() => Ok(Value::Num(s.replace('_', ""))),
+        _ => Err(format!("invalid value `{}`", s)),
+    }
+}
+
+/// Split the items of an array at top-level commas, allowing a trailing comma.
+fn split_items(s: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut item = String::new();
+    for c in s.chars() {
+        if c == ',' && bracket_depth(&item) == 0 && quote_closed(&item) {
+            items.push(item.trim().to_owned());
+            item.clear();
+        } else {
+            item.push(c);
+        }
+    }
+    if !item.trim().is_empty() {
+        items.push(item.trim().to_owned());
+    }
+    items
+}
+
+/// Check that every string in some text is closed.
+fn quote_closed(s: &str) -> bool {
+    strip_comment(&format!("{}#", s)).len() == s.len()
+}
+
+/// Resolve the escape sequences of a basic TOML string.
+fn unescape(s: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('"') => out.push('"'),
+            Some('\\') => out.push('\\'),
+            other => return Err(format!("invalid escape `\\{}`", other.unwrap_or(' '))),
+        }
+    }
+    Ok(out)
+}
+
+/// Get the global configuration file, `$XDG_CONFIG_HOME/rust-cloc/config.toml` or
+/// `~/.config/rust-cloc/config.toml`.
+pub fn global_path() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(dir.join("rust-cloc").join("config.toml"))
+}
+
+/// Read the configuration files that apply to a scan, most specific first: the file given
+/// with `--config` alone if there is one, otherwise `.cloc.toml` in the scanned directory
+/// followed by the global configuration file. Without a directory, as with `--sandbox`,
+/// only the global file is read.
+pub fn find_files(
+    explicit: Option<&Path>,
+    directory: Option<&Path>,
+) -> Result<Vec<Config>, String> {
+    if let Some(path) = explicit {
+        return Ok(vec![Config::read(path)?]);
+    }
+
+    let project = directory
+        .map(|d| d.join(PROJECT_FILE))
+        .filter(|p| p.is_file())
+        .map(|p| {
+            Config::read(&p).map(|config| Config {
+                project: true,
+                ..config
+            })
+        });
+    let global = global_path()
+        .filter(|p| p.is_file())
+        .map(|p| Config::read(&p));
+    project.into_iter().chain(global).collect()
+}
+
+/// Turn configuration settings into command line arguments that come before the ones that
+/// were actually given, so that flags given on the command line override the configuration.
+///
+/// Each setting is named after a long flag, like `threads = 4` for `--threads 4` or
+/// `exclude = ["vendor/**"]` for `--exclude vendor/**`. A setting is skipped if its flag was
+/// given on the command line or by a more specific configuration file. Unknown settings and
+/// flags a project may not set are errors.
+pub fn merge_args(
+    app: &App,
+    cli_matches: &ArgMatches,
+    configs: &[Config],
+) -> Result<Vec<OsString>, String> {
+    let mut set = Vec::new();
+    let mut args = Vec::new();
+
+    for config in configs {
+        for (key, value) in &config.settings {
+            let long = key.replace('_', "-");
+            let arg = app
+                .get_arguments()
+                .find(|a| {
+                    a.get_long_and_visible_aliases()
+                        .map(|names| names.contains(&long.as_str()))
+                        .unwrap_or(false)
+                })
+                .filter(|_| !["config", "no-config"].contains(&long.as_str()));
+            let arg = match arg {
+                Some(arg) => arg,
+                None => {
+                    return Err(format!(
+                        "Unknown setting `{}` in {}",
+                        key,
+                        config.path.display()
+                    ))
+                }
+            };
+
+            // formats are only allowed without a path, so they are printed to stdout
+            let writes_file = match value {
+                Value::Array(values) => values
+                    .iter()
+                    .any(|v| matches!(v, Value::Str(s) if s.contains('='))),
+                Value::Str(s) => s.contains('='),
+                _ => false,
+            };
+            let allowed = match arg.get_long() {
+                Some("format") => !writes_file,
+                Some(long) => PROJECT_FLAGS.contains(&long),
+                None => false,
+            };
+            if config.project && !allowed {
+                return Err(format!(
+                    "`{}` cannot be set in {}, only on the command line or in the global \
+                     config file",
+                    key,
+                    config.path.display()
+                ));
+            }
+
+            if cli_matches.occurrences_of(arg.get_name()) > 0 || set.contains(&arg.get_name()) {
+                continue;
+            }
+            set.push(arg.get_name());
+
+            let values = match value {
+                Value::Array(values) => values.clone(),
+                v => vec![v.clone()],
+            };
+            for v in values {
+                match v {
+                    Value::Bool(true) => args.push(OsString::from(format!("--{}", long))),
+                    Value::Bool(false) => (),
+                    Value::Str(s) | Value::Num(s) => {
+                        args.push(OsString::from(format!("--{}={}", long, s)))
+                    }
+                    Value::Array(_) => {
+                        return Err(format!(
+                            "Nested array for `{}` in {}",
+                            key,
+                            config.path.display()
+                        ))
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(args)
+}
@@ -0,0 +1,282 @@
+use rayon::prelude::*;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::binary::BinaryPolicy;
+use crate::compare::{Scan, ScannedFile};
+use crate::glob::Glob;
+use crate::presets;
+use crate::report::Report;
+use crate::tally::Tally;
+use crate::walk::{self, AttributeTo, WalkOptions};
+use crate::{
+    add_ext_results, count_bytes_by_ext, count_lines_by_ext, count_lines_per_file,
+    count_while_walking, get_ext, Results,
+};
+
+/// Counts lines in a directory tree, configured with builder methods.
+///
+/// With more than one thread, the walk and the counting run on the current rayon thread
+/// pool, so callers that need a specific number of threads should run the counter inside
+/// their own pool.
+#[derive(Clone)]
+pub struct Counter {
+    threads: usize,
+    walk: WalkOptions,
+    by_language: bool,
+    /// Tally shared by every scan, if given, instead of a new one for each scan.
+    tally: Option<Arc<Tally>>,
+}
+
+impl Default for Counter {
+    fn default() -> Self {
+        Counter::new()
+    }
+}
+
+impl Counter {
+    /// Create a counter that visits every file except binary files on a single thread and
+    /// reports results by file extension.
+    pub fn new() -> Self {
+        Counter {
+            threads: 1,
+            walk: WalkOptions::default(),
+            by_language: false,
+            tally: None,
+        }
+    }
+
+    /// Walk and count in parallel if `threads` is more than one.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Do not visit files or directories matching a glob, relative to the counted directory.
+    pub fn exclude(mut self, glob: Glob) -> Self {
+        self.walk.exclude.push(glob);
+        self
+    }
+
+    /// Only count files matching one of the globs given with this method.
+    pub fn include(mut self, glob: Glob) -> Self {
+        self.walk.include.push(glob);
+        self
+    }
+
+    /// Do not descend more than `depth` directories below the counted directory.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.walk.max_depth = Some(depth);
+        self
+    }
+
+    /// Skip symlinks instead of following them.
+    pub fn skip_symlinks(mut self, skip: bool) -> Self {
+        self.walk.skip_symlinks = skip;
+        self
+    }
+
+    /// Follow symlinks to files and directories instead of leaving them out. Files reached
+    /// through several paths are counted once, and links back to a directory above are not
+    /// followed.
+    pub fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.walk.follow_symlinks = follow;
+        self
+    }
+
+    /// Report files reached through symlinks under the path of the link or of the file it
+    /// leads to, when following symlinks, instead of the path it was found at.
+    pub fn attribute_to(mut self, attribute_to: AttributeTo) -> Self {
+        self.walk.attribute_to = Some(attribute_to);
+        self
+    }
+
+    /// Count binary files like images and executables instead of skipping them.
+    pub fn count_binary(mut self, count: bool) -> Self {
+        if count {
+            self.walk.binary.policy = BinaryPolicy::None;
+        }
+        self
+    }
+
+    /// Choose how binary files are recognized.
+    pub fn binary_policy(mut self, policy: BinaryPolicy) -> Self {
+        self.walk.binary.policy = policy;
+        self
+    }
+
+    /// Check this many bytes at the start of each file for NUL bytes.
+    pub fn sniff_bytes(mut self, bytes: u64) -> Self {
+        self.walk.binary.sniff_bytes = bytes;
+        self
+    }
+
+    /// Always count files with an extension, even if they look binary.
+    pub fn always_count_ext(mut self, ext: &str) -> Self {
+        self.walk.binary.always_count.push(ext.to_owned());
+        self
+    }
+
+    /// Count the contents of Git LFS pointer files when they have been downloaded, instead
+    /// of skipping the pointers.
+    pub fn resolve_lfs(mut self, resolve: bool) -> Self {
+        self.walk.resolve_lfs = resolve;
+        self
+    }
+
+    /// Find files without ever opening them, like when only the files are counted: binary
+    /// files are only recognized by their extension, and Git LFS pointers are not
+    /// recognized at all.
+    pub fn without_reading(mut self, without: bool) -> Self {
+        if without {
+            self.walk.binary.policy = BinaryPolicy::Extension;
+            self.walk.resolve_lfs = false;
+            self.walk.skip_lfs_check = true;
+        }
+        self
+    }
+
+    /// Break down reports by detected language instead of by file extension.
+    pub fn by_language(mut self, by_language: bool) -> Self {
+        self.by_language = by_language;
+        self
+    }
+
+    /// Record what every scan skips and how far it got in `tally`, like one the caller
+    /// shows progress from, instead of in a new tally for each scan.
+    pub fn tally(mut self, tally: Arc<Tally>) -> Self {
+        self.tally = Some(tally);
+        self
+    }
+
+    /// Get the options used to walk the tree at `root`, with a new tally unless one was
+    /// given with [`tally`](Self::tally).
+    pub fn walk_options(&self, root: &Path) -> WalkOptions {
+        WalkOptions {
+            // scanning a whole filesystem skips virtual and system directories like `/proc`
+            skip_dirs: presets::system_excludes(root),
+            tally: self.tally.clone().unwrap_or_default(),
+            ..self.walk.clone()
+        }
+    }
+
+    /// List the files that would be counted below `root`.
+    pub fn find_files(&self, root: &Path) -> Vec<PathBuf> {
+        self.find_files_with(root, &self.walk_options(root))
+    }
+
+    /// List the files that would be counted below `root`, walking with `opts`.
+    fn find_files_with(&self, root: &Path, opts: &WalkOptions) -> Vec<PathBuf> {
+        let files = if self.threads > 1 {
+            walk::find_all_files_par(root, opts)
+        } else {
+            let mut files = Vec::new();
+            walk::find_all_files(root, opts, &mut files);
+            files
+        };
+        if !self.walk.follow_symlinks {
+            return files;
+        }
+
+        let units = vec![(); files.len()];
+        walk::dedup_links(root, files, units, self.walk.attribute_to).0
+    }
+
+    /// Count the lines in every file below `root`.
+    ///
+    /// With more than one thread, files are counted while the tree is still being walked.
+    pub fn count(&self, root: impl AsRef<Path>) -> Report {
+        let root = root.as_ref();
+        let opts = self.walk_options(root);
+        if self.threads <= 1 {
+            return self.count_files_with(&self.find_files_with(root, &opts), &opts);
+        }
+
+        let (files, per_file) = count_while_walking(root, &opts, None);
+        let (files, per_file) = if self.walk.follow_symlinks {
+            walk::dedup_links(root, files, per_file, self.walk.attribute_to)
+        } else {
+            (files, per_file)
+        };
+        let report = if self.by_language {
+            Report::by_language(&files, &files, &per_file, &opts)
+        } else {
+            let res_map = files
+                .iter()
+                .zip(per_file)
+                .map(|(f, res)| (get_ext(f), res))
+                .fold(HashMap::new(), add_ext_results);
+            Report::new(
+                &files,
+                &res_map,
+                &count_bytes_by_ext(&files, &files, self.threads, &opts),
+            )
+        };
+        Report {
+            skipped: opts.tally.skipped(),
+            ..report
+        }
+    }
+
+    /// Count the lines in every file below `root`, keeping the results of each file so the
+    /// scan can be compared with another one.
+    pub fn scan(&self, root: impl AsRef<Path>) -> Scan {
+        let root = root.as_ref();
+        let opts = self.walk_options(root);
+        let mut files = self.find_files_with(root, &opts);
+        files.sort();
+        let per_file = count_lines_per_file(&files, self.threads, &opts);
+
+        let report = if self.by_language {
+            Report::by_language(&files, &files, &per_file, &opts)
+        } else {
+            let res_map = files
+                .iter()
+                .zip(&per_file)
+                .map(|(f, res)| (get_ext(f), *res))
+                .fold(HashMap::new(), add_ext_results);
+            Report::new(
+                &files,
+                &res_map,
+                &count_bytes_by_ext(&files, &files, self.threads, &opts),
+            )
+        };
+        let report = Report {
+            skipped: opts.tally.skipped(),
+            ..report
+        };
+        let scanned = |(f, res): (&PathBuf, &Results)| ScannedFile::new(root, f, *res, &opts);
+        let files = if self.threads > 1 {
+            files.par_iter().zip(&per_file).map(scanned).collect()
+        } else {
+            files.iter().zip(&per_file).map(scanned).collect()
+        };
+
+        Scan { report, files }
+    }
+
+    /// Count the lines in a list of files.
+    pub fn count_files(&self, files: &[PathBuf]) -> Report {
+        self.count_files_with(files, &self.walk_options(Path::new("")))
+    }
+
+    /// Count the lines in a list of files, recording what is skipped in the tally of `opts`.
+    fn count_files_with(&self, files: &[PathBuf], opts: &WalkOptions) -> Report {
+        let report = if self.by_language {
+            let per_file = count_lines_per_file(files, self.threads, opts);
+            Report::by_language(files, files, &per_file, opts)
+        } else {
+            Report::new(
+                files,
+                &count_lines_by_ext(files, self.threads, opts),
+                &count_bytes_by_ext(files, files, self.threads, opts),
+            )
+        };
+        Report {
+            skipped: opts.tally.skipped(),
+            ..report
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Source file extensions that CMake projects list explicitly. Headers are left out since
+/// they are usually found through include directories instead.
+const CMAKE_SOURCE_EXTS: &[&str] = &["c", "cc", "cpp", "cxx", "c++", "cu"];
+
+/// Resolve `.` and `..` in a path without touching the filesystem, so paths built from
+/// build manifests can be compared with the paths found by walking the tree.
+fn normalize(path: &Path) -> PathBuf {
+    let mut res = PathBuf::new();
+    for c in path.components() {
+        match c {
+            Component::CurDir => (),
+            Component::ParentDir if res.file_name().is_some() => {
+                res.pop();
+            }
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+fn is_named(path: &Path, name: &str) -> bool {
+    path.file_name().map(|n| n == name).unwrap_or(false)
+}
+
+/// Get the nearest of a set of build manifest directories that contains a path.
+pub fn manifest_dir<'a>(path: &Path, manifests: &'a HashSet<PathBuf>) -> Option<&'a Path> {
+    path.ancestors()
+        .skip(1)
+        .find_map(|dir| manifests.get(dir))
+        .map(PathBuf::as_path)
+}
+
+/// Get the child modules declared with `mod foo;` in a Rust file, along with the path from
+/// a `#[path = "..."]` attribute right before the declaration if there is one.
+fn mod_decls(src: &str) -> Vec<(String, Option<String>)> {
+    let mut decls = Vec::new();
+    let mut path_attr = None;
+
+    for line in src.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("#[path") {
+            path_attr = rest.split('"').nth(1).map(str::to_owned);
+            continue;
+        }
+
+        let mut tokens = line.split_whitespace();
+        if tokens.any(|t| t == "mod") {
+            if let Some(name) = tokens.next().and_then(|t| t.strip_suffix(';')) {
+                decls.push((name.to_owned(), path_attr.take()));
+                continue;
+            }
+        }
+        if !line.starts_with("#[") {
+            path_attr = None;
+        }
+    }
+
+    decls
+}
+
+/// Find every Rust file reachable through `mod` declarations from a crate root.
+fn reach_modules(root_file: &Path, files: &HashSet<&Path>, reached: &mut HashSet<PathBuf>) {
+    let mut todo = vec![root_file.to_owned()];
+
+    while let Some(file) = todo.pop() {
+        if !files.contains(file.as_path()) || !reached.insert(file.clone()) {
+            continue;
+        }
+        let src = match fs::read_to_string(&file) {
+            Ok(src) => src,
+            Err(_) => continue,
+        };
+
+        let dir = file.parent().unwrap_or_else(|| Path::new(""));
+        // modules of `foo.rs` live in `foo/`, while those of `mod.rs`, `lib.rs` and
+        // other crate roots live next to them
+        let child_dir = if is_named(&file, "mod.rs") || file == root_file {
+            dir.to_owned()
+        } else {
+            dir.join(file.file_stem().unwrap_or_default())
+        };
+
+        for (name, path) in mod_decls(&src) {
+            match path {
+                Some(path) => todo.push(normalize(&dir.join(path))),
+                None => {
+                    todo.push(child_dir.join(format!("{}.rs", name)));
+                    todo.push(child_dir.join(&name).join("mod.rs"));
+                }
+            }
+        }
+    }
+}
+
+/// Find the crate roots of a Cargo package: the conventional target locations and any
+/// `path = "..."` given in the manifest.
+fn crate_roots(pkg: &Path, files: &HashSet<&Path>) -> Vec<PathBuf> {
+    let mut roots = vec![
+        pkg.join("build.rs"),
+        pkg.join("src").join("lib.rs"),
+        pkg.join("src").join("main.rs"),
+    ];
+
+    // every file directly in these directories is a target, as is `<dir>/<name>/main.rs`
+    let auto_dirs = [
+        pkg.join("src").join("bin"),
+        pkg.join("examples"),
+        pkg.join("tests"),
+        pkg.join("benches"),
+    ];
+    for f in files {
+        let parent = f.parent();
+        let grandparent = parent.and_then(Path::parent);
+        if auto_dirs.iter().any(|d| Some(d.as_path()) == parent)
+            || (is_named(f, "main.rs")
+                && auto_dirs.iter().any(|d| Some(d.as_path()) == grandparent))
+        {
+            roots.push(f.to_path_buf());
+        }
+    }
+
+    if let Ok(manifest) = fs::read_to_string(pkg.join("Cargo.toml")) {
+        for line in manifest.lines() {
+            if let Some((key, value)) = line.split_once('=') {
+                if key.trim() == "path" {
+                    if let Some(path) = value.split('"').nth(1) {
+                        roots.push(normalize(&pkg.join(path)));
+                    }
+                }
+            }
+        }
+    }
+
+    roots
+}
+
+/// Find Rust files in Cargo packages that no target reaches through `mod` declarations.
+fn unreferenced_rust(files: &[PathBuf]) -> Vec<PathBuf> {
+    let packages = files
+        .iter()
+        .filter(|f| is_named(f, "Cargo.toml"))
+        .filter_map(|f| f.parent().map(Path::to_owned))
+        .collect::<HashSet<_>>();
+    let rs_files = files
+        .iter()
+        .filter(|f| f.extension().map(|e| e == "rs").unwrap_or(false))
+        .map(PathBuf::as_path)
+        .collect::<HashSet<_>>();
+
+    let mut reached = HashSet::new();
+    for pkg in &packages {
+        for root in crate_roots(pkg, &rs_files) {
+            reach_modules(&root, &rs_files, &mut reached);
+        }
+    }
+
+    rs_files
+        .into_iter()
+        .filter(|f| manifest_dir(f, &packages).is_some() && !reached.contains(*f))
+        .map(Path::to_owned)
+        .collect()
+}
+
+/// Find C and C++ sources under a `CMakeLists.txt` that are not named in any of them.
+///
+/// Sources are matched by their path relative to the `CMakeLists.txt` that names them.
+/// Directories whose `CMakeLists.txt` collects sources with `file(GLOB ...)` count as fully
+/// referenced, since the glob cannot be reliably evaluated here.
+fn unreferenced_cmake(files: &[PathBuf]) -> Vec<PathBuf> {
+    let mut projects = HashSet::new();
+    let mut globbed = HashSet::new();
+    let mut listed = HashSet::new();
+
+    for f in files.iter().filter(|f| is_named(f, "CMakeLists.txt")) {
+        let dir = f.parent().unwrap_or_else(|| Path::new("")).to_owned();
+        let src = fs::read_to_string(f).unwrap_or_default();
+
+        if src.contains("GLOB") {
+            globbed.insert(dir.clone());
+        }
+        for token in src.split(|c: char| c.is_whitespace() || "()\"".contains(c)) {
+            let token = token.trim_start_matches("${CMAKE_CURRENT_SOURCE_DIR}/");
+            listed.insert(normalize(&dir.join(token)));
+        }
+        projects.insert(dir);
+    }
+
+    files
+        .iter()
+        .filter(|f| {
+            f.extension()
+                .and_then(|e| e.to_str())
+                .map(|e| CMAKE_SOURCE_EXTS.contains(&e))
+                .unwrap_or(false)
+        })
+        .filter(|f| {
+            manifest_dir(f, &projects).is_some()
+                && !f.ancestors().any(|d| globbed.contains(d))
+                && !listed.contains(&normalize(f))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Print source files that are not referenced by any build configuration: Rust files that
+/// no Cargo target reaches through `mod` declarations, and C/C++ sources that no
+/// `CMakeLists.txt` lists. These are strong candidates for deletion.
+pub fn print_unreferenced(files: &[PathBuf]) {
+    let mut dead = unreferenced_rust(files);
+    dead.extend(unreferenced_cmake(files));
+    dead.sort();
+
+    println!(
+        "{} files are not referenced by any build configuration.",
+        dead.len()
+    );
+    for f in dead {
+        println!("  {}", f.display());
+    }
+}
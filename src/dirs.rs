@@ -0,0 +1,90 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::output::csv_field;
+use crate::{get_ext, Results};
+
+/// Sum lines of code for each directory under `root`.
+///
+/// With `direct`, a directory only gets the lines of the files directly inside it. Otherwise
+/// lines are also added to every ancestor directory up to (but excluding) the root itself,
+/// which would trivially contain everything.
+pub fn code_by_dir<'a>(
+    root: &Path,
+    files: &'a [PathBuf],
+    per_file: &[Results],
+    direct: bool,
+) -> HashMap<&'a Path, usize> {
+    let mut dirs = HashMap::new();
+
+    for (f, res) in files.iter().zip(per_file) {
+        for dir in f.ancestors().skip(1) {
+            if dir == root || !dir.starts_with(root) {
+                break;
+            }
+
+            *dirs.entry(dir).or_insert(0) += res.lines_of_code;
+            if direct {
+                break;
+            }
+        }
+    }
+
+    dirs
+}
+
+/// Print the `n` directories with the most lines of code.
+pub fn print_top_dirs(
+    root: &Path,
+    files: &[PathBuf],
+    per_file: &[Results],
+    n: usize,
+    direct: bool,
+) {
+    let mut dirs = code_by_dir(root, files, per_file, direct)
+        .into_iter()
+        .collect::<Vec<_>>();
+    dirs.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    println!("Directories with the most lines of code:");
+    for (dir, code) in dirs.into_iter().take(n) {
+        println!("{:>10}  {}", code, dir.display());
+    }
+}
+
+/// Print a CSV matrix where each cell counts the directories that directly contain files
+/// of both the row's and the column's file type. The diagonal is the number of directories
+/// that contain each file type at all.
+pub fn print_colocation(files: &[PathBuf]) {
+    let mut dir_exts: HashMap<&Path, BTreeSet<String>> = HashMap::new();
+    for f in files {
+        if let Some(dir) = f.parent() {
+            dir_exts.entry(dir).or_default().insert(get_ext(f));
+        }
+    }
+
+    let mut pairs: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    for exts in dir_exts.values() {
+        for a in exts {
+            for b in exts {
+                *pairs.entry((a, b)).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let exts = dir_exts
+        .values()
+        .flatten()
+        .map(String::as_str)
+        .collect::<BTreeSet<_>>();
+
+    let header = exts.iter().map(|e| csv_field(e)).collect::<Vec<_>>();
+    println!(",{}", header.join(","));
+    for a in &exts {
+        let row = exts
+            .iter()
+            .map(|b| pairs.get(&(a, b)).copied().unwrap_or(0).to_string())
+            .collect::<Vec<_>>();
+        println!("{},{}", csv_field(a), row.join(","));
+    }
+}
@@ -0,0 +1,132 @@
+use std::io::{BufRead, Error};
+
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+const UTF16LE_BOM: &[u8] = b"\xff\xfe";
+const UTF16BE_BOM: &[u8] = b"\xfe\xff";
+
+/// Check whether some bytes start with a UTF-16 byte order mark.
+pub fn is_utf16(head: &[u8]) -> bool {
+    head.starts_with(UTF16LE_BOM) || head.starts_with(UTF16BE_BOM)
+}
+
+/// Decode UTF-16 bytes without their byte order mark, replacing invalid characters.
+fn decode_utf16(bytes: &[u8], big_endian: bool) -> String {
+    let units = bytes.chunks_exact(2).map(|b| {
+        if big_endian {
+            u16::from_be_bytes([b[0], b[1]])
+        } else {
+            u16::from_le_bytes([b[0], b[1]])
+        }
+    });
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// Decode bytes as Latin-1, where every byte is the character with the same code point.
+/// Legacy 8-bit encodings like Windows-1252 only differ in characters that never change
+/// whether a line is empty, a comment or code.
+fn decode_latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+enum Source<R> {
+    Bytes(R),
+    Decoded(std::vec::IntoIter<String>),
+}
+
+/// Iterator over the lines of text read in any encoding, decoded to UTF-8 strings, like
+/// `BufRead::lines` without failing on text that is not UTF-8.
+///
+/// A byte order mark picks UTF-8 or UTF-16. Otherwise, each line is read as UTF-8 if it is
+/// valid and as Latin-1 if it is not.
+pub struct Lines<R> {
+    source: Source<R>,
+    buf: Vec<u8>,
+    started: bool,
+    done: bool,
+    /// Whether any text so far was decoded from an encoding other than UTF-8.
+    pub transcoded: bool,
+}
+
+/// Read the lines of text from a reader in any encoding.
+pub fn lines<R: BufRead>(reader: R) -> Lines<R> {
+    Lines {
+        source: Source::Bytes(reader),
+        buf: Vec::new(),
+        started: false,
+        done: false,
+        transcoded: false,
+    }
+}
+
+impl<R: BufRead> Lines<R> {
+    /// Look for a byte order mark at the start, decoding all of the text at once if it is
+    /// UTF-16.
+    fn start(&mut self) -> Result<(), Error> {
+        self.started = true;
+        let reader = match &mut self.source {
+            Source::Bytes(reader) => reader,
+            Source::Decoded(_) => return Ok(()),
+        };
+
+        let head = reader.fill_buf()?;
+        if head.starts_with(UTF8_BOM) {
+            reader.consume(UTF8_BOM.len());
+        } else if is_utf16(head) {
+            let big_endian = head.starts_with(UTF16BE_BOM);
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes)?;
+            let text = decode_utf16(&bytes[2..], big_endian);
+            let lines = text.lines().map(str::to_owned).collect::<Vec<_>>();
+            self.source = Source::Decoded(lines.into_iter());
+            self.transcoded = true;
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Iterator for Lines<R> {
+    type Item = Result<String, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if !self.started {
+            if let Err(e) = self.start() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+
+        let reader = match &mut self.source {
+            Source::Decoded(lines) => return lines.next().map(Ok),
+            Source::Bytes(reader) => reader,
+        };
+
+        self.buf.clear();
+        match reader.read_until(b'\n', &mut self.buf) {
+            Ok(0) => return None,
+            Ok(_) => (),
+            Err(e) => {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+        if self.buf.ends_with(b"\n") {
+            self.buf.pop();
+            if self.buf.ends_with(b"\r") {
+                self.buf.pop();
+            }
+        }
+
+        match std::str::from_utf8(&self.buf) {
+            Ok(line) => Some(Ok(line.to_owned())),
+            Err(_) => {
+                self.transcoded = true;
+                Some(Ok(decode_latin1(&self.buf)))
+            }
+        }
+    }
+}
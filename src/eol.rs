@@ -0,0 +1,117 @@
+use rayon::prelude::*;
+
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::get_ext;
+
+/// A line ending convention.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Eol {
+    /// `\n` only.
+    Lf,
+    /// `\r\n` only.
+    Crlf,
+}
+
+impl fmt::Display for Eol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Eol::Lf => write!(f, "LF"),
+            Eol::Crlf => write!(f, "CRLF"),
+        }
+    }
+}
+
+/// A rule like `rs=lf` that fails when a file of some type has a line ending other than
+/// the one it requires.
+#[derive(Debug, Clone)]
+pub struct EolRule {
+    ext: String,
+    eol: Eol,
+}
+
+impl FromStr for EolRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (ext, eol) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected EXT=lf or EXT=crlf but got \"{}\"", s))?;
+        let eol = match eol.trim().to_ascii_lowercase().as_str() {
+            "lf" => Eol::Lf,
+            "crlf" => Eol::Crlf,
+            _ => return Err(format!("invalid line ending in \"{}\"", s)),
+        };
+
+        Ok(EolRule {
+            ext: ext.trim().trim_start_matches('.').to_owned(),
+            eol,
+        })
+    }
+}
+
+/// Count the `\n` and `\r\n` line endings in some bytes.
+fn count_eols(bytes: &[u8]) -> (usize, usize) {
+    let mut lf = 0;
+    let mut crlf = 0;
+    for (i, &b) in bytes.iter().enumerate() {
+        if b == b'\n' {
+            if i > 0 && bytes[i - 1] == b'\r' {
+                crlf += 1;
+            } else {
+                lf += 1;
+            }
+        }
+    }
+    (lf, crlf)
+}
+
+/// Check the line endings of every file with a rule, returning a message for each file
+/// that breaks its rule. `paths` are the paths to report for `files`.
+///
+/// The last rule given for a file type wins. Files that cannot be read are skipped.
+pub fn check_eols(rules: &[EolRule], files: &[PathBuf], paths: &[PathBuf]) -> Vec<String> {
+    if rules.is_empty() {
+        return Vec::new();
+    }
+
+    let mut violations = files
+        .par_iter()
+        .zip(paths)
+        .filter_map(|(f, p)| {
+            let ext = get_ext(f);
+            let rule = rules.iter().rev().find(|r| r.ext == ext)?;
+            let (lf, crlf) = count_eols(&fs::read(f).ok()?);
+            let wrong = match rule.eol {
+                Eol::Lf => crlf,
+                Eol::Crlf => lf,
+            };
+            if wrong == 0 {
+                return None;
+            }
+            let other = match rule.eol {
+                Eol::Lf => Eol::Crlf,
+                Eol::Crlf => Eol::Lf,
+            };
+            Some((p.clone(), wrong, other, rule))
+        })
+        .collect::<Vec<_>>();
+    violations.sort_by(|a, b| a.0.cmp(&b.0));
+
+    violations
+        .into_iter()
+        .map(|(path, wrong, other, rule)| {
+            format!(
+                "Line ending check failed: {} has {} {} line endings (\"{}\" files must use {})",
+                path.display(),
+                wrong,
+                other,
+                rule.ext,
+                rule.eol
+            )
+        })
+        .collect()
+}
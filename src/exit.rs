@@ -0,0 +1,27 @@
+use crate::tally::Tally;
+
+/// The scan finished and every file was counted.
+pub const SUCCESS: i32 = 0;
+/// A check like `--fail-if-lang-share` failed.
+pub const THRESHOLD_VIOLATED: i32 = 1;
+/// The arguments, configuration or an input like a template or git revision are invalid.
+/// Argument parsing errors reported by clap use the same code.
+pub const USAGE: i32 = 2;
+/// The results are incomplete because some paths were skipped or only partially counted.
+pub const PARTIAL: i32 = 3;
+/// No files were found to count.
+pub const NOTHING_COUNTED: i32 = 4;
+
+/// Get the exit status of a finished scan, given what it recorded in `tally`. A failed check
+/// takes precedence over an empty scan, which takes precedence over partial results.
+pub fn status(violated: bool, files: usize, tally: &Tally) -> i32 {
+    if violated {
+        THRESHOLD_VIOLATED
+    } else if files == 0 {
+        NOTHING_COUNTED
+    } else if !tally.skipped().is_empty() {
+        PARTIAL
+    } else {
+        SUCCESS
+    }
+}
@@ -0,0 +1,135 @@
+use clap::ArgEnum;
+
+use std::fs;
+use std::path::Path;
+
+use crate::languages;
+use crate::walk::WalkOptions;
+use crate::whitespace::BlankRule;
+use crate::{count_lines_in_file, get_ext, NO_EXT};
+
+/// Print every decision that goes into counting one file: whether it is skipped,
+/// which file type it is reported under and why, how lines are classified, and the
+/// resulting counts.
+pub fn explain(root: &Path, path: &Path, opts: &WalkOptions) {
+    println!("File: {}", path.display());
+    println!("Scan root: {}", root.display());
+
+    let meta = match fs::metadata(path) {
+        Ok(m) => m,
+        Err(e) => {
+            println!("Result: not counted, the file cannot be read ({})", e);
+            return;
+        }
+    };
+    if meta.is_dir() {
+        println!("Result: not counted, this is a directory");
+        return;
+    }
+
+    // the walker only looks below the scan root and never enters skipped directories
+    let canonical = |p: &Path| fs::canonicalize(p).unwrap_or_else(|_| p.to_owned());
+    let file = canonical(path);
+    if !file.starts_with(canonical(root)) {
+        println!("Ignore rules: the file is outside the scan root, so it is never visited");
+        println!("Result: not counted");
+        return;
+    }
+    if let Some(dir) = opts
+        .skip_dirs
+        .iter()
+        .find(|d| file.starts_with(canonical(d)))
+    {
+        println!(
+            "Ignore rules: inside {}, which is skipped when scanning a filesystem root",
+            dir.display()
+        );
+        println!("Result: not counted");
+        return;
+    }
+    // glob patterns are matched against the path relative to the scan root
+    let rel = file.strip_prefix(canonical(root)).unwrap_or(&file);
+    if let Some((p, glob)) = opts.excluded_ancestor(rel) {
+        println!(
+            "Ignore rules: {} is excluded by --exclude {}",
+            p.display(),
+            glob
+        );
+        println!("Result: not counted");
+        return;
+    }
+    if !opts.included(Path::new(""), rel) {
+        println!("Ignore rules: the file does not match any --include pattern");
+        println!("Result: not counted");
+        return;
+    }
+    if opts.binary.is_binary(path) {
+        println!(
+            "Ignore rules: the file is binary under --binary-policy {} (use --count-binary or \
+             --always-count-ext to count it)",
+            opts.binary
+                .policy
+                .to_possible_value()
+                .map(|v| v.get_name())
+                .unwrap_or_default()
+        );
+        println!("Result: not counted");
+        return;
+    }
+    println!("Ignore rules: none matched");
+
+    let ext = get_ext(path);
+    if ext == NO_EXT {
+        println!("File type: {} (the file name has no extension)", NO_EXT);
+    } else {
+        println!("File type: \"{}\" (from the file extension)", ext);
+    }
+
+    match BlankRule::for_path(path) {
+        BlankRule::Trim => println!("Empty lines: lines that only contain whitespace"),
+        BlankRule::TabIsCode => println!(
+            "Empty lines: lines that only contain whitespace, except lines starting with a tab \
+             (Makefile recipe lines count as code)"
+        ),
+    }
+
+    let bytes = fs::read(path).unwrap_or_default();
+    if std::str::from_utf8(&bytes).is_ok() {
+        println!("Encoding: valid UTF-8");
+    } else {
+        println!("Encoding: not valid UTF-8, so no lines can be counted in this file");
+    }
+    match languages::detect(path) {
+        Some(lang) => {
+            let markers = lang
+                .line_comments
+                .iter()
+                .map(|m| m.to_string())
+                .chain(
+                    lang.block_comments
+                        .iter()
+                        .map(|(s, e)| format!("{} {}", s, e)),
+                )
+                .collect::<Vec<_>>();
+            println!(
+                "Classification: {} comment syntax ({}{}), lines with only comments are comments \
+                 and every other line that is not empty is code",
+                lang.name,
+                markers.join(", "),
+                if lang.docstrings { ", docstrings" } else { "" }
+            );
+        }
+        None => println!(
+            "Classification: no known comment syntax, every line that is not empty is code"
+        ),
+    }
+
+    let res = count_lines_in_file(path, opts);
+    println!(
+        "Result: {} lines of code, {} comment lines, {} empty lines, {} bytes",
+        res.lines_of_code,
+        res.comment_lines,
+        res.empty_lines,
+        meta.len()
+    );
+}
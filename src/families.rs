@@ -0,0 +1,91 @@
+use clap::ArgEnum;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Built-in language families and the file extensions that belong to each of them.
+const DEFAULT_FAMILIES: &[(&str, &[&str])] = &[
+    (
+        "C/C++",
+        &[
+            "c", "h", "cc", "cpp", "cxx", "c++", "hh", "hpp", "hxx", "h++", "inl", "ipp",
+        ],
+    ),
+    (
+        "JS/TS",
+        &["js", "jsx", "mjs", "cjs", "ts", "tsx", "mts", "cts"],
+    ),
+    ("Shell", &["sh", "bash", "zsh", "ksh", "fish"]),
+    ("Python", &["py", "pyi", "pyx"]),
+    ("JVM", &["java", "kt", "kts", "scala", "groovy", "clj"]),
+    (".NET", &["cs", "fs", "fsx", "vb"]),
+    ("Ruby", &["rb", "erb", "rake"]),
+    ("CSS", &["css", "scss", "sass", "less"]),
+    ("HTML", &["html", "htm", "xhtml"]),
+];
+
+/// How results are grouped in the per-type breakdown.
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum GroupBy {
+    /// One row per file extension
+    Ext,
+    /// One row per language family, like "C/C++" or "JS/TS"
+    Family,
+}
+
+/// A user-defined family like `Config=json,yaml,yml,toml`, which takes its extensions
+/// away from any built-in family.
+#[derive(Clone, Debug)]
+pub struct FamilySpec {
+    name: String,
+    exts: Vec<String>,
+}
+
+impl FromStr for FamilySpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, exts) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected NAME=EXT,EXT,... but got \"{}\"", s))?;
+
+        Ok(FamilySpec {
+            name: name.trim().to_owned(),
+            exts: exts
+                .split(',')
+                .map(|e| e.trim().trim_start_matches('.').to_owned())
+                .filter(|e| !e.is_empty())
+                .collect(),
+        })
+    }
+}
+
+/// Mapping from file extensions to the language family they belong to.
+pub struct Families {
+    map: HashMap<String, String>,
+}
+
+impl Families {
+    /// Build the family hierarchy from the built-in families and user-defined ones,
+    /// where later definitions win.
+    pub fn new(specs: &[FamilySpec]) -> Self {
+        let mut map = HashMap::new();
+        for (name, exts) in DEFAULT_FAMILIES {
+            for ext in exts.iter() {
+                map.insert(ext.to_string(), name.to_string());
+            }
+        }
+        for spec in specs {
+            for ext in &spec.exts {
+                map.insert(ext.clone(), spec.name.clone());
+            }
+        }
+
+        Families { map }
+    }
+
+    /// Get the family of a file type. Types that are not in any family form their own.
+    pub fn family_of<'a>(&'a self, ext: &'a str) -> &'a str {
+        self.map.get(ext).map(String::as_str).unwrap_or(ext)
+    }
+}
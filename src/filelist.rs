@@ -0,0 +1,45 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use crate::exit;
+
+/// Read a list of files to count, one per line or separated by NUL bytes, from a file or
+/// from stdin if the path is `-`. Empty entries are ignored, and with newlines a trailing
+/// carriage return is removed.
+pub fn read(source: &Path, nul_separated: bool) -> Vec<PathBuf> {
+    let mut bytes = Vec::new();
+    let read = if source == Path::new("-") {
+        io::stdin().read_to_end(&mut bytes).map(|_| ())
+    } else {
+        fs::read(source).map(|b| bytes = b)
+    };
+    if let Err(e) = read {
+        eprintln!("Unable to read file list {}: {}", source.display(), e);
+        process::exit(exit::USAGE);
+    }
+
+    let sep = if nul_separated { b'\0' } else { b'\n' };
+    bytes
+        .split(|&b| b == sep)
+        .map(|entry| match (nul_separated, entry.strip_suffix(b"\r")) {
+            (false, Some(entry)) => entry,
+            _ => entry,
+        })
+        .filter(|entry| !entry.is_empty())
+        .map(path_from_bytes)
+        .collect()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
@@ -0,0 +1,386 @@
+use rayon::prelude::*;
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use crate::exit;
+use crate::languages;
+use crate::walk::WalkOptions;
+use crate::whitespace::BlankRule;
+use crate::{add_ext_results, count_lines_in_reader, get_ext, Results};
+
+/// Lines added and removed by a single commit.
+pub struct CommitSize {
+    pub hash: String,
+    pub author: String,
+    pub subject: String,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl CommitSize {
+    /// The size of a commit is the total number of lines it touched.
+    pub fn size(&self) -> usize {
+        self.added + self.removed
+    }
+}
+
+/// Exit with an error message when git cannot be run or talked to, like when it is not
+/// installed.
+fn git_failed(what: &str, e: io::Error) -> ! {
+    eprintln!("Unable to {} git: {}", what, e);
+    process::exit(exit::USAGE);
+}
+
+/// Run a git command in the repository at `repo` and return its stdout.
+///
+/// Exits the process with git's error message if the command fails.
+pub fn run_git(repo: &Path, args: &[&str]) -> Vec<u8> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()
+        .unwrap_or_else(|e| git_failed("run", e));
+
+    if !output.status.success() {
+        eprintln!(
+            "git {}: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        process::exit(exit::USAGE);
+    }
+
+    output.stdout
+}
+
+/// Get the lines added and removed for each commit in a revision range.
+pub fn commit_sizes(repo: &Path, range: &str) -> Vec<CommitSize> {
+    // each commit header starts with a NUL byte so it cannot be confused with a numstat line
+    let log = run_git(
+        repo,
+        &[
+            "log",
+            "--no-merges",
+            "--numstat",
+            "--format=%x00%H%x09%an%x09%s",
+            range,
+        ],
+    );
+    let log = String::from_utf8_lossy(&log);
+    let mut commits: Vec<CommitSize> = Vec::new();
+
+    for line in log.lines() {
+        if let Some(header) = line.strip_prefix('\0') {
+            let mut fields = header.splitn(3, '\t');
+            commits.push(CommitSize {
+                hash: fields.next().unwrap_or("").to_owned(),
+                author: fields.next().unwrap_or("").to_owned(),
+                subject: fields.next().unwrap_or("").to_owned(),
+                added: 0,
+                removed: 0,
+            });
+            continue;
+        }
+
+        // numstat lines are "added<TAB>removed<TAB>path", with "-" for binary files
+        let mut fields = line.split('\t');
+        let (added, removed) = match (fields.next(), fields.next()) {
+            (Some(a), Some(r)) => (a.parse::<usize>(), r.parse::<usize>()),
+            _ => continue,
+        };
+
+        if let (Some(commit), Ok(added), Ok(removed)) = (commits.last_mut(), added, removed) {
+            commit.added += added;
+            commit.removed += removed;
+        }
+    }
+
+    commits
+}
+
+/// Summary statistics over the sizes of a set of commits.
+pub struct SizeStats {
+    pub commits: usize,
+    pub added: usize,
+    pub removed: usize,
+    pub median: usize,
+    pub p90: usize,
+    pub max: usize,
+}
+
+impl SizeStats {
+    /// Compute statistics for a set of commits.
+    pub fn new(commits: &[&CommitSize]) -> Self {
+        let mut sizes = commits.iter().map(|c| c.size()).collect::<Vec<_>>();
+        sizes.sort_unstable();
+
+        SizeStats {
+            commits: commits.len(),
+            added: commits.iter().map(|c| c.added).sum(),
+            removed: commits.iter().map(|c| c.removed).sum(),
+            median: percentile(&sizes, 50),
+            p90: percentile(&sizes, 90),
+            max: sizes.last().copied().unwrap_or(0),
+        }
+    }
+}
+
+/// Get the `p`-th percentile of sorted values using the nearest-rank method.
+fn percentile(sorted: &[usize], p: usize) -> usize {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (p * sorted.len()).div_ceil(100);
+    sorted[rank.max(1) - 1]
+}
+
+/// Print commit size statistics for a revision range, optionally broken down by author.
+pub fn print_commit_stats(repo: &Path, range: &str, by_author: bool, top: usize) {
+    let commits = commit_sizes(repo, range);
+    let all = commits.iter().collect::<Vec<_>>();
+
+    print_size_stats("all authors", &SizeStats::new(&all));
+
+    if by_author {
+        let mut authors: HashMap<&str, Vec<&CommitSize>> = HashMap::new();
+        for c in &commits {
+            authors.entry(&c.author).or_default().push(c);
+        }
+
+        let mut authors = authors.into_iter().collect::<Vec<_>>();
+        authors.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(b.0)));
+        for (author, author_commits) in authors {
+            print_size_stats(author, &SizeStats::new(&author_commits));
+        }
+    }
+
+    // list the largest commits, which are usually vendoring, renames, or generated code
+    let mut largest = all;
+    largest.sort_by(|a, b| b.size().cmp(&a.size()).then(a.hash.cmp(&b.hash)));
+    if top > 0 && !largest.is_empty() {
+        println!("Largest commits:");
+        for c in largest.iter().take(top) {
+            println!(
+                "  {} +{} -{} {} ({})",
+                &c.hash[..c.hash.len().min(10)],
+                c.added,
+                c.removed,
+                c.subject,
+                c.author
+            );
+        }
+    }
+}
+
+fn print_size_stats(label: &str, stats: &SizeStats) {
+    println!(
+        "{}: {} commits, +{} -{} lines, median size {}, p90 size {}, largest {}",
+        label, stats.commits, stats.added, stats.removed, stats.median, stats.p90, stats.max
+    );
+}
+
+/// A file in a git tree.
+struct TreeEntry {
+    object: String,
+    path: PathBuf,
+}
+
+/// List the regular files in the tree of a revision without checking anything out.
+///
+/// With `full_tree`, every file in the repository is listed with its path from the top
+/// of the repository. Otherwise only the files below `repo` are listed, with paths
+/// relative to it.
+fn list_tree(repo: &Path, rev: &str, full_tree: bool) -> Vec<TreeEntry> {
+    let mut args = vec!["ls-tree", "-r", "-z"];
+    if full_tree {
+        args.push("--full-tree");
+    }
+    args.push(rev);
+    let listing = run_git(repo, &args);
+
+    // entries are "<mode> <type> <object>\t<path>" separated by NUL bytes
+    listing
+        .split(|&b| b == b'\0')
+        .filter_map(|entry| {
+            let entry = String::from_utf8_lossy(entry);
+            let (meta, path) = entry.split_once('\t')?;
+            let mut fields = meta.split(' ');
+            let mode = fields.next()?;
+            let kind = fields.next()?;
+            let object = fields.next()?;
+
+            // skip submodules and symlinks, which have no content of their own in this tree
+            if kind != "blob" || mode == "120000" {
+                return None;
+            }
+
+            Some(TreeEntry {
+                object: object.to_owned(),
+                path: PathBuf::from(path),
+            })
+        })
+        .collect()
+}
+
+/// Reads objects from a repository through a single `git cat-file --batch` process, so
+/// reading many files does not start a process for each one.
+///
+/// Only the objects that are actually read are needed, so in a partial clone with a blob
+/// filter git lazily fetches just those objects.
+pub struct BlobReader {
+    cat_file: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+    header: String,
+}
+
+impl BlobReader {
+    /// Start reading objects from the repository at `repo`.
+    pub fn new(repo: &Path) -> Self {
+        let mut cat_file = Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(["cat-file", "--batch"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap_or_else(|e| git_failed("run", e));
+        let stdin = cat_file.stdin.take();
+        let stdout = BufReader::new(cat_file.stdout.take().unwrap());
+        BlobReader {
+            cat_file,
+            stdin,
+            stdout,
+            header: String::new(),
+        }
+    }
+
+    /// Read an object, given by its name or as `REV:PATH` with a path relative to the
+    /// repository directory written as `./PATH`, into `blob`. Returns whether it exists.
+    pub fn read(&mut self, spec: &str, blob: &mut Vec<u8>) -> bool {
+        let stdin = self.stdin.as_mut().unwrap();
+        writeln!(stdin, "{}", spec)
+            .and_then(|_| stdin.flush())
+            .unwrap_or_else(|e| git_failed("write to", e));
+
+        // the response is "<object> blob <size>\n" followed by the contents and a newline,
+        // or "<spec> missing\n"
+        self.header.clear();
+        self.stdout
+            .read_line(&mut self.header)
+            .unwrap_or_else(|e| git_failed("read from", e));
+        blob.clear();
+        let size = match self.header.trim_end().rsplit(' ').next().map(str::parse) {
+            Some(Ok(size)) => size,
+            _ => return false,
+        };
+
+        blob.resize(size + 1, 0);
+        self.stdout
+            .read_exact(blob)
+            .unwrap_or_else(|e| git_failed("read from", e));
+        blob.truncate(size);
+        true
+    }
+}
+
+impl Drop for BlobReader {
+    fn drop(&mut self) {
+        // closing stdin lets git exit
+        self.stdin.take();
+        let _ = self.cat_file.wait();
+    }
+}
+
+/// Count lines in a set of blobs, streaming them one at a time through a [`BlobReader`].
+/// Binary blobs are not counted and have no results, as binary files are skipped when
+/// walking a directory with `opts`.
+fn count_blobs(repo: &Path, entries: &[TreeEntry], opts: &WalkOptions) -> Vec<Option<Results>> {
+    let mut blobs = BlobReader::new(repo);
+    let mut blob = Vec::new();
+
+    entries
+        .iter()
+        .map(|entry| {
+            if !blobs.read(&entry.object, &mut blob) {
+                return Some(Results::new());
+            }
+            if opts.binary.is_binary_contents(&entry.path, &blob) {
+                return None;
+            }
+            Some(
+                count_lines_in_reader(
+                    &blob[..],
+                    BlankRule::for_path(&entry.path),
+                    languages::for_path(&entry.path),
+                    &opts.tally,
+                )
+                .0,
+            )
+        })
+        .collect()
+}
+
+/// Count lines in each of a set of tree entries, in the same order, with no results for
+/// binary blobs.
+fn count_entries(
+    repo: &Path,
+    entries: &[TreeEntry],
+    threads: usize,
+    opts: &WalkOptions,
+) -> Vec<Option<Results>> {
+    if threads > 1 {
+        // each chunk gets its own `git cat-file` process so blobs are read in parallel
+        let chunk_size = entries.len().div_ceil(threads).max(1);
+        entries
+            .par_chunks(chunk_size)
+            .map(|chunk| count_blobs(repo, chunk, opts))
+            .collect::<Vec<_>>()
+            .concat()
+    } else {
+        count_blobs(repo, entries, opts)
+    }
+}
+
+/// Count lines for each file type in the tree of a git revision. Binary files are skipped
+/// as they are when walking a directory.
+///
+/// The tree is enumerated and blobs are read directly from the object database, so no
+/// checkout is materialized and memory use is bounded by the largest single file.
+pub fn count_rev_by_ext(repo: &Path, rev: &str, threads: usize) -> HashMap<String, Results> {
+    let entries = list_tree(repo, rev, true);
+    let counts = count_entries(repo, &entries, threads, &WalkOptions::default());
+
+    entries
+        .iter()
+        .zip(counts)
+        .filter_map(|(entry, res)| Some((get_ext(&entry.path), res?)))
+        .fold(HashMap::new(), add_ext_results)
+}
+
+/// Count lines in each file below `dir` in the tree of a git revision, with paths relative
+/// to `dir`. Files that `keep` rejects are not read at all, and files that are binary by the
+/// settings of `opts` are left out.
+pub fn count_rev_files(
+    dir: &Path,
+    rev: &str,
+    threads: usize,
+    opts: &WalkOptions,
+    keep: impl Fn(&Path) -> bool,
+) -> Vec<(PathBuf, Results)> {
+    let entries = list_tree(dir, rev, false)
+        .into_iter()
+        .filter(|entry| keep(&entry.path))
+        .collect::<Vec<_>>();
+    let counts = count_entries(dir, &entries, threads, opts);
+
+    entries
+        .into_iter()
+        .zip(counts)
+        .filter_map(|(entry, res)| Some((entry.path, res?)))
+        .collect()
+}
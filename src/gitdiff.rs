@@ -0,0 +1,326 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::git::{run_git, BlobReader};
+use crate::languages::{self, Classifier, LineKind};
+use crate::walk::WalkOptions;
+use crate::whitespace::BlankRule;
+use crate::{get_ext, Results};
+
+/// One side of a diff: a git revision, or the working tree.
+enum Side<'a> {
+    Rev(&'a str),
+    WorkTree,
+}
+
+impl Side<'_> {
+    /// Read a file, with a path relative to `root`, as it is on this side of the diff.
+    /// Revisions are read through `blobs`, which reads from the repository at `root`.
+    fn read(&self, root: &Path, path: &Path, blobs: &mut BlobReader) -> Vec<u8> {
+        match self {
+            Side::Rev(rev) => {
+                let mut blob = Vec::new();
+                blobs.read(&format!("{}:./{}", rev, path.display()), &mut blob);
+                blob
+            }
+            Side::WorkTree => fs::read(root.join(path)).unwrap_or_default(),
+        }
+    }
+}
+
+/// Lines removed from and added to one file, by line number (starting at 1) on each side.
+///
+/// Each hunk is kept separately, since a removed line is only paired with an added line
+/// from the same hunk as a modification.
+struct FileDiff {
+    old_path: Option<PathBuf>,
+    new_path: Option<PathBuf>,
+    hunks: Vec<(Vec<usize>, Vec<usize>)>,
+}
+
+/// Line counts for files that were added, removed and modified.
+#[derive(Default)]
+struct DiffStats {
+    added: Results,
+    removed: Results,
+    modified: Results,
+    added_files: usize,
+    removed_files: usize,
+    modified_files: usize,
+}
+
+/// Get the kind of each line of a file, with `None` for empty lines.
+fn line_kinds(path: &Path, text: &str) -> Vec<Option<LineKind>> {
+    let blank_rule = BlankRule::for_path(path);
+    let mut classifier = Classifier::new(languages::for_path(path));
+    text.lines()
+        .map(|line| {
+            if blank_rule.is_empty(line) {
+                None
+            } else {
+                Some(classifier.classify(line))
+            }
+        })
+        .collect()
+}
+
+/// Count a line of a given kind.
+fn add_line(res: &mut Results, kind: Option<LineKind>) {
+    match kind {
+        None => res.empty_lines += 1,
+        Some(LineKind::Comment) => res.comment_lines += 1,
+        Some(LineKind::Code) => res.lines_of_code += 1,
+    }
+}
+
+/// Parse a path from a `--- a/path` or `+++ b/path` line, which is `/dev/null` for a file
+/// that does not exist on that side.
+fn diff_path(s: &str) -> Option<PathBuf> {
+    if s == "/dev/null" {
+        return None;
+    }
+    let s = s.trim_end_matches('\t');
+    Some(PathBuf::from(
+        s.strip_prefix("a/")
+            .or_else(|| s.strip_prefix("b/"))
+            .unwrap_or(s),
+    ))
+}
+
+/// Parse a range like `12,3` or `12` from a hunk header into the line numbers it covers.
+fn hunk_lines(range: &str) -> Vec<usize> {
+    let (start, len) = match range.split_once(',') {
+        Some((start, len)) => (start.parse().unwrap_or(0), len.parse().unwrap_or(0)),
+        None => (range.parse().unwrap_or(0), 1),
+    };
+    (start..start + len).collect()
+}
+
+/// Parse the output of `git diff -U0` into the lines changed in each file.
+fn parse_diff(diff: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    let mut old_path = None;
+    // changed lines can look like headers, so headers are only parsed before the first hunk
+    let mut in_header = false;
+
+    for line in diff.lines() {
+        if line.starts_with("diff --git ") {
+            old_path = None;
+            in_header = true;
+        } else if let (true, Some(path)) = (in_header, line.strip_prefix("--- ")) {
+            old_path = diff_path(path);
+        } else if let (true, Some(path)) = (in_header, line.strip_prefix("+++ ")) {
+            files.push(FileDiff {
+                old_path: old_path.take(),
+                new_path: diff_path(path),
+                hunks: Vec::new(),
+            });
+        } else if let Some(header) = line.strip_prefix("@@ -") {
+            in_header = false;
+            // hunk headers look like "@@ -12,3 +12,4 @@ context"
+            let mut ranges = header.split(' ');
+            let old = ranges.next().unwrap_or("");
+            let new = ranges.next().unwrap_or("").trim_start_matches('+');
+            if let Some(file) = files.last_mut() {
+                file.hunks.push((hunk_lines(old), hunk_lines(new)));
+            }
+        }
+    }
+
+    files
+}
+
+/// Count the lines added, removed and modified between two revisions, or between a revision
+/// and the working tree, for each language, and print them like `cloc --diff`.
+///
+/// `range` is either `REV1..REV2` or a single revision, which is compared with the working
+/// tree (where, like with `git diff`, untracked files are left out). Lines are classified
+/// using the whole file on each side, so lines inside block comments are recognized even if
+/// the comment starts outside the changed lines. Binary files are left out, like when
+/// walking a directory.
+pub fn print_diff(root: &Path, range: &str, opts: &WalkOptions) {
+    let (old, new) = match range.split_once("..") {
+        Some((a, b)) => (
+            Side::Rev(a),
+            Side::Rev(if b.is_empty() { "HEAD" } else { b }),
+        ),
+        None => (Side::Rev(range), Side::WorkTree),
+    };
+
+    let mut args = vec![
+        "-c",
+        "core.quotePath=false",
+        "diff",
+        "--no-color",
+        "--no-ext-diff",
+        "--no-renames",
+        "--relative",
+        "-U0",
+    ];
+    match (&old, &new) {
+        (Side::Rev(a), Side::Rev(b)) => args.extend([*a, *b]),
+        (Side::Rev(a), Side::WorkTree) => args.push(a),
+        _ => unreachable!("the old side is always a revision"),
+    }
+    args.push("--");
+    let diff = run_git(root, &args);
+
+    let mut blobs = BlobReader::new(root);
+    let mut stats: BTreeMap<String, DiffStats> = BTreeMap::new();
+    for file in parse_diff(&String::from_utf8_lossy(&diff)) {
+        let path = match file.new_path.as_ref().or(file.old_path.as_ref()) {
+            Some(path) => path,
+            None => continue,
+        };
+        if opts.excluded_ancestor(path).is_some() || !opts.included(Path::new(""), path) {
+            continue;
+        }
+
+        let old_text = file
+            .old_path
+            .as_ref()
+            .map(|p| (p, old.read(root, p, &mut blobs)));
+        let new_text = file
+            .new_path
+            .as_ref()
+            .map(|p| (p, new.read(root, p, &mut blobs)));
+        if old_text
+            .iter()
+            .chain(&new_text)
+            .any(|(p, text)| opts.binary.is_binary_contents(p, text))
+        {
+            continue;
+        }
+        let kinds = |side: Option<(&PathBuf, Vec<u8>)>| match side {
+            Some((p, text)) => line_kinds(p, &String::from_utf8_lossy(&text)),
+            None => Vec::new(),
+        };
+        let old_kinds = kinds(old_text);
+        let new_kinds = kinds(new_text);
+
+        let key = match languages::for_path(path) {
+            Some(lang) => lang.name.to_owned(),
+            None => get_ext(path),
+        };
+        let entry = stats.entry(key).or_default();
+        match (&file.old_path, &file.new_path) {
+            (None, Some(_)) => entry.added_files += 1,
+            (Some(_), None) => entry.removed_files += 1,
+            _ => entry.modified_files += 1,
+        }
+
+        let kind = |kinds: &[Option<LineKind>], n: usize| kinds.get(n - 1).copied().flatten();
+        for (removed, added) in &file.hunks {
+            // pair up removed and added lines in each hunk as modifications
+            let paired = removed.len().min(added.len());
+            for &n in &added[..paired] {
+                add_line(&mut entry.modified, kind(&new_kinds, n));
+            }
+            for &n in &added[paired..] {
+                add_line(&mut entry.added, kind(&new_kinds, n));
+            }
+            for &n in &removed[paired..] {
+                add_line(&mut entry.removed, kind(&old_kinds, n));
+            }
+        }
+    }
+
+    let mut total = DiffStats::default();
+    println!(
+        "{:<16} {:>8} {:>10} {:>10} {:>10}",
+        "Language", "Files", "Blank", "Comment", "Code"
+    );
+    for (lang, s) in &stats {
+        println!("{}", lang);
+        print_diff_rows(s);
+        total.added = total.added + s.added;
+        total.removed = total.removed + s.removed;
+        total.modified = total.modified + s.modified;
+        total.added_files += s.added_files;
+        total.removed_files += s.removed_files;
+        total.modified_files += s.modified_files;
+    }
+    println!("{}", "-".repeat(58));
+    println!("Total");
+    print_diff_rows(&total);
+}
+
+fn print_diff_rows(s: &DiffStats) {
+    for (label, files, res) in [
+        ("added", s.added_files, &s.added),
+        ("removed", s.removed_files, &s.removed),
+        ("modified", s.modified_files, &s.modified),
+    ] {
+        println!(
+            "  {:<14} {:>8} {:>10} {:>10} {:>10}",
+            label, files, res.empty_lines, res.comment_lines, res.lines_of_code
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multiple_hunks_and_files() {
+        let diff = "\
+diff --git a/src/a.rs b/src/a.rs
+index 1111111..2222222 100644
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -3 +3,2 @@ fn a() {
+-    old();
++    new();
++    more();
+@@ -10,2 +11,0 @@ fn b() {
+-// gone
+-gone();
+@@ -20,0 +19 @@
++added();
+diff --git a/new.py b/new.py
+new file mode 100644
+--- /dev/null
++++ b/new.py
+@@ -0,0 +1,2 @@
++++ not a header
++x = 1
+diff --git a/old.lua b/old.lua
+deleted file mode 100644
+--- a/old.lua
++++ /dev/null
+@@ -1 +0,0 @@
+--- not a header
+";
+        let files = parse_diff(diff);
+        assert_eq!(files.len(), 3);
+
+        assert_eq!(files[0].old_path, Some(PathBuf::from("src/a.rs")));
+        assert_eq!(files[0].new_path, Some(PathBuf::from("src/a.rs")));
+        assert_eq!(
+            files[0].hunks,
+            [
+                (vec![3], vec![3, 4]),
+                (vec![10, 11], vec![]),
+                (vec![], vec![19]),
+            ]
+        );
+
+        // changed lines that look like headers are not parsed as headers
+        assert_eq!(files[1].old_path, None);
+        assert_eq!(files[1].new_path, Some(PathBuf::from("new.py")));
+        assert_eq!(files[1].hunks, [(vec![], vec![1, 2])]);
+
+        assert_eq!(files[2].old_path, Some(PathBuf::from("old.lua")));
+        assert_eq!(files[2].new_path, None);
+        assert_eq!(files[2].hunks, [(vec![1], vec![])]);
+    }
+
+    #[test]
+    fn hunk_ranges() {
+        assert_eq!(hunk_lines("12"), [12]);
+        assert_eq!(hunk_lines("12,3"), [12, 13, 14]);
+        assert!(hunk_lines("12,0").is_empty());
+    }
+}
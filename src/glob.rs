@@ -0,0 +1,179 @@
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A glob pattern for paths relative to the scanned directory, like `vendor/**`, `*.rs` or
+/// `src/**/test_*.py`.
+///
+/// `*` matches any characters within one path segment, `?` matches one character and a
+/// `**` segment matches any number of segments. A pattern without a `/` matches the name of
+/// a file or directory anywhere in the tree.
+#[derive(Clone, Debug)]
+pub struct Glob {
+    pattern: String,
+    segments: Vec<String>,
+    name_only: bool,
+}
+
+impl FromStr for Glob {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim_start_matches("./").trim_start_matches('/');
+        if trimmed.is_empty() {
+            return Err("empty glob pattern".to_owned());
+        }
+
+        Ok(Glob {
+            pattern: s.to_owned(),
+            segments: trimmed
+                .split('/')
+                .filter(|s| !s.is_empty())
+                .map(str::to_owned)
+                .collect(),
+            name_only: !trimmed.contains('/'),
+        })
+    }
+}
+
+impl fmt::Display for Glob {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+impl Glob {
+    /// Check whether a path relative to the scanned directory matches the pattern.
+    pub fn matches(&self, rel: &Path) -> bool {
+        let segments = rel
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>();
+        let segments = segments.iter().map(|s| s.as_ref()).collect::<Vec<&str>>();
+
+        if self.name_only {
+            return segments
+                .last()
+                .map(|name| match_segment(&self.segments[0], name))
+                .unwrap_or(false);
+        }
+        match_segments(&self.segments, &segments)
+    }
+
+    /// Check whether everything inside a directory matches the pattern, like `vendor/` for
+    /// `vendor/**`, so the directory does not need to be visited at all.
+    pub fn matches_dir(&self, rel: &Path) -> bool {
+        if self.matches(rel) {
+            return true;
+        }
+
+        match self.segments.split_last() {
+            Some((last, init)) if last == "**" && !init.is_empty() => Glob {
+                pattern: String::new(),
+                segments: init.to_vec(),
+                name_only: false,
+            }
+            .matches(rel),
+            _ => false,
+        }
+    }
+}
+
+fn match_segments(pattern: &[String], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((p, rest)) if p == "**" => {
+            (0..=path.len()).any(|skip| match_segments(rest, &path[skip..]))
+        }
+        Some((p, rest)) => match path.split_first() {
+            Some((s, path_rest)) => match_segment(p, s) && match_segments(rest, path_rest),
+            None => false,
+        },
+    }
+}
+
+/// Match a single path segment against a pattern segment with `*` and `?` wildcards.
+fn match_segment(pattern: &str, s: &str) -> bool {
+    let p = pattern.chars().collect::<Vec<_>>();
+    let s = s.chars().collect::<Vec<_>>();
+    let (mut pi, mut si) = (0, 0);
+    // position of the last `*` in the pattern and of the text it started matching at
+    let mut star = None;
+
+    while si < s.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == s[si]) {
+            pi += 1;
+            si += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, si));
+            pi += 1;
+        } else if let Some((star_pi, star_si)) = star {
+            // let the last `*` match one more character and try again
+            pi = star_pi + 1;
+            si = star_si + 1;
+            star = Some((star_pi, star_si + 1));
+        } else {
+            return false;
+        }
+    }
+
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        pattern.parse::<Glob>().unwrap().matches(Path::new(path))
+    }
+
+    #[test]
+    fn names_match_anywhere() {
+        assert!(matches("*.rs", "main.rs"));
+        assert!(matches("*.rs", "src/deep/lib.rs"));
+        assert!(matches("test_?.py", "tests/test_a.py"));
+        assert!(!matches("*.rs", "main.rs.bak"));
+        assert!(!matches("test_?.py", "test_ab.py"));
+    }
+
+    #[test]
+    fn double_star_matches_any_number_of_segments() {
+        assert!(matches("vendor/**", "vendor/a.c"));
+        assert!(matches("vendor/**", "vendor/x/y/z.c"));
+        assert!(matches("src/**/test_*.py", "src/test_a.py"));
+        assert!(matches("src/**/test_*.py", "src/a/b/test_a.py"));
+        assert!(matches("**/gen/*.rs", "gen/a.rs"));
+        assert!(matches("**/gen/*.rs", "a/b/gen/a.rs"));
+    }
+
+    #[test]
+    fn non_matching_paths() {
+        // `*` and `?` never match across a `/`
+        assert!(!matches("src/*.rs", "src/a/b.rs"));
+        assert!(!matches("src/?", "src/a/b"));
+        // patterns with a `/` are anchored at the scanned directory
+        assert!(!matches("vendor/**", "third_party/vendor/a.c"));
+        assert!(!matches("src/**/test_*.py", "lib/src/test_a.py"));
+        assert!(!matches("**/gen/*.rs", "a/gen/b/c.rs"));
+    }
+
+    #[test]
+    fn whole_directories_match() {
+        let glob = "vendor/**".parse::<Glob>().unwrap();
+        assert!(glob.matches_dir(Path::new("vendor")));
+        assert!(!glob.matches_dir(Path::new("vendored")));
+        assert!(!"vendor/*.c"
+            .parse::<Glob>()
+            .unwrap()
+            .matches_dir(Path::new("vendor")));
+    }
+
+    #[test]
+    fn leading_slashes_are_ignored_and_empty_patterns_are_errors() {
+        assert!(matches("./src/*.rs", "src/a.rs"));
+        assert!(matches("/src/*.rs", "src/a.rs"));
+        assert!("".parse::<Glob>().is_err());
+        assert!("./".parse::<Glob>().is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use crate::{encoding, get_ext};
+
+/// Number of import statements in the files of one file type.
+#[derive(Default)]
+struct FanOut {
+    files: usize,
+    imports: usize,
+    max: usize,
+}
+
+/// Check whether a trimmed line is an import statement in a file type. Returns `None` for
+/// file types whose imports are not recognized.
+fn is_import(ext: &str, line: &str) -> Option<bool> {
+    let starts = |prefixes: &[&str]| prefixes.iter().any(|p| line.starts_with(p));
+
+    let res = match ext {
+        "c" | "h" | "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" | "m" | "mm" => {
+            line.starts_with('#') && line[1..].trim_start().starts_with("include")
+        }
+        "rs" => starts(&["use ", "pub use ", "pub(crate) use ", "extern crate "]),
+        "py" | "pyi" => starts(&["import ", "from "]) && line.contains("import"),
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" => {
+            starts(&["import ", "import{", "export * from", "export {"]) && line.contains("from")
+                || line.starts_with("import \"")
+                || line.starts_with("import '")
+                || line.contains("require(")
+        }
+        "java" | "kt" | "kts" | "scala" | "groovy" | "swift" => starts(&["import "]),
+        "cs" => line.starts_with("using ") && line.ends_with(';') && !line.contains('('),
+        "rb" => starts(&[
+            "require ",
+            "require_relative ",
+            "require(",
+            "require_relative(",
+        ]),
+        "php" => starts(&["use ", "require", "include"]),
+        _ => return None,
+    };
+    Some(res)
+}
+
+/// Count the import statements in the lines of a Go file, where `import ( ... )` blocks
+/// import one package per line.
+fn count_go_imports(lines: impl Iterator<Item = String>) -> usize {
+    let mut count = 0;
+    let mut in_block = false;
+
+    for line in lines {
+        let line = line.trim();
+        if in_block {
+            if line.starts_with(')') {
+                in_block = false;
+            } else if !line.is_empty() && !line.starts_with("//") {
+                count += 1;
+            }
+        } else if line.starts_with("import (") {
+            in_block = true;
+        } else if line.starts_with("import ") {
+            count += 1;
+        }
+    }
+
+    count
+}
+
+/// Print the number of import, include and use statements for each file type with known
+/// import syntax, and the average and maximum per file, as a cheap coupling signal.
+pub fn print_fan_out(files: &[PathBuf]) {
+    let mut stats: BTreeMap<String, FanOut> = BTreeMap::new();
+
+    for f in files {
+        let ext = get_ext(f);
+        // checking an empty line tells whether imports of this file type are recognized
+        if ext != "go" && is_import(&ext, "").is_none() {
+            continue;
+        }
+        let file = match File::open(f) {
+            Ok(file) => file,
+            Err(_) => continue,
+        };
+
+        let lines = encoding::lines(BufReader::new(file)).map_while(Result::ok);
+        let imports = if ext == "go" {
+            count_go_imports(lines)
+        } else {
+            lines
+                .filter(|l| is_import(&ext, l.trim()) == Some(true))
+                .count()
+        };
+
+        let s = stats.entry(ext).or_default();
+        s.files += 1;
+        s.imports += imports;
+        s.max = s.max.max(imports);
+    }
+
+    for (ext, s) in stats {
+        let mean = if s.files == 0 {
+            0.0
+        } else {
+            (s.imports as f64) / (s.files as f64)
+        };
+        println!(
+            "In {} \"{}\" files, there are {} imports, {:.2} per file on average and at most {} \
+             in one file.",
+            s.files, ext, s.imports, mean, s.max
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// A JSON value, with the members of objects in the order they were written.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Num(f64),
+    Str(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Get a member of an object.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Get a whole number that is not negative.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Num(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as u64),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a JSON document.
+pub fn parse(src: &str) -> Result<Value, String> {
+    let mut chars = src.chars().peekable();
+    let value = parse_value(&mut chars)?;
+    skip_whitespace(&mut chars);
+    match chars.next() {
+        None => Ok(value),
+        Some(c) => Err(format!("unexpected `{}` after the end of the document", c)),
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+        chars.next();
+    }
+}
+
+/// Consume an expected character, after any whitespace.
+fn expect(chars: &mut Peekable<Chars>, expected: char) -> Result<(), String> {
+    skip_whitespace(chars);
+    match chars.next() {
+        Some(c) if c == expected => Ok(()),
+        Some(c) => Err(format!("expected `{}` but got `{}`", expected, c)),
+        None => Err(format!(
+            "expected `{}` but got the end of the document",
+            expected
+        )),
+    }
+}
+
+fn parse_value(chars: &mut Peekable<Chars>) -> Result<Value, String> {
+    skip_whitespace(chars);
+    match chars.peek().copied() {
+        Some('{') => parse_object(chars),
+        Some('[') => parse_array(chars),
+        Some('"') => parse_string(chars).map(Value::Str),
+        Some(c) if c == '-' || c.is_ascii_digit() => parse_number(chars),
+        Some(c) if c.is_ascii_alphabetic() => {
+            let mut word = String::new();
+            while let Some(c) = chars.peek().copied().filter(|c| c.is_ascii_alphabetic()) {
+                word.push(c);
+                chars.next();
+            }
+            match word.as_str() {
+                "null" => Ok(Value::Null),
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                _ => Err(format!("invalid value `{}`", word)),
+            }
+        }
+        Some(c) => Err(format!("unexpected `{}`", c)),
+        None => Err("unexpected end of the document".to_owned()),
+    }
+}
+
+fn parse_object(chars: &mut Peekable<Chars>) -> Result<Value, String> {
+    expect(chars, '{')?;
+    let mut members = Vec::new();
+    skip_whitespace(chars);
+    if chars.peek() == Some(&'}') {
+        chars.next();
+        return Ok(Value::Object(members));
+    }
+
+    loop {
+        skip_whitespace(chars);
+        let key = parse_string(chars)?;
+        expect(chars, ':')?;
+        members.push((key, parse_value(chars)?));
+        skip_whitespace(chars);
+        match chars.next() {
+            Some(',') => (),
+            Some('}') => return Ok(Value::Object(members)),
+            _ => return Err("expected `,` or `}` in an object".to_owned()),
+        }
+    }
+}
+
+fn parse_array(chars: &mut Peekable<Chars>) -> Result<Value, String> {
+    expect(chars, '[')?;
+    let mut values = Vec::new();
+    skip_whitespace(chars);
+    if chars.peek() == Some(&']') {
+        chars.next();
+        return Ok(Value::Array(values));
+    }
+
+    loop {
+        values.push(parse_value(chars)?);
+        skip_whitespace(chars);
+        match chars.next() {
+            Some(',') => (),
+            Some(']') => return Ok(Value::Array(values)),
+            _ => return Err("expected `,` or `]` in an array".to_owned()),
+        }
+    }
+}
+
+fn parse_number(chars: &mut Peekable<Chars>) -> Result<Value, String> {
+    let mut num = String::new();
+    while let Some(c) = chars
+        .peek()
+        .copied()
+        .filter(|c| c.is_ascii_digit() || "+-.eE".contains(*c))
+    {
+        num.push(c);
+        chars.next();
+    }
+    num.parse::<f64>()
+        .map(Value::Num)
+        .map_err(|_| format!("invalid number `{}`", num))
+}
+
+/// Read the four hex digits of a `\u` escape.
+fn parse_hex4(chars: &mut Peekable<Chars>) -> Result<u32, String> {
+    let hex = chars.take(4).collect::<String>();
+    u32::from_str_radix(&hex, 16).map_err(|_| format!("invalid escape `\\u{}`", hex))
+}
+
+fn parse_string(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    expect(chars, '"')?;
+    let mut s = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(s),
+            Some('\\') => match chars.next() {
+                Some('"') => s.push('"'),
+                Some('\\') => s.push('\\'),
+                Some('/') => s.push('/'),
+                Some('b') => s.push('\u{8}'),
+                Some('f') => s.push('\u{c}'),
+                Some('n') => s.push('\n'),
+                Some('r') => s.push('\r'),
+                Some('t') => s.push('\t'),
+                Some('u') => {
+                    let mut code = parse_hex4(chars)?;
+                    // characters outside the basic plane are escaped as a surrogate pair
+                    if (0xd800..0xdc00).contains(&code) {
+                        if chars.next() != Some('\\') || chars.next() != Some('u') {
+                            return Err("unpaired surrogate in a string".to_owned());
+                        }
+                        let low = parse_hex4(chars)?;
+                        code =
+                            0x10000 + ((code - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+                    }
+                    s.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                }
+                other => return Err(format!("invalid escape `\\{}`", other.unwrap_or(' '))),
+            },
+            Some(c) => s.push(c),
+            None => return Err("unterminated string".to_owned()),
+        }
+    }
+}
@@ -0,0 +1,1240 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+use crate::config;
+
+/// Comment syntax of a language.
+pub struct Language {
+    /// Name shown to people, which can be changed to read better.
+    pub name: &'static str,
+    /// Stable snake_case identifier used in machine-readable output, which never changes
+    /// once a language is added.
+    pub id: &'static str,
+    /// Markers that start a comment running to the end of the line.
+    pub line_comments: &'static [&'static str],
+    /// Start and end markers of comments that can span several lines.
+    pub block_comments: &'static [(&'static str, &'static str)],
+    /// Whether a triple-quoted string that starts a line is a docstring, as in Python.
+    pub docstrings: bool,
+    /// Characters that start and end a string, in which comment markers are ignored.
+    pub string_quotes: &'static [char],
+}
+
+const C_LINE: &[&str] = &["//"];
+const C_BLOCK: &[(&str, &str)] = &[("/*", "*/")];
+const HASH: &[&str] = &["#"];
+const HTML_BLOCK: &[(&str, &str)] = &[("<!--", "-->")];
+const DOUBLE_QUOTE: &[char] = &['"'];
+
+/// Languages with their file extensions.
+const LANGUAGES: &[(&[&str], Language)] = &[
+    (
+        &["c", "h"],
+        Language {
+            name: "C",
+            id: "c",
+            line_comments: C_LINE,
+            block_comments: C_BLOCK,
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &[
+            "cc", "cpp", "cxx", "c++", "hh", "hpp", "hxx", "h++", "inl", "ipp",
+        ],
+        Language {
+            name: "C++",
+            id: "cpp",
+            line_comments: C_LINE,
+            block_comments: C_BLOCK,
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["rs"],
+        Language {
+            name: "Rust",
+            id: "rust",
+            line_comments: C_LINE,
+            block_comments: C_BLOCK,
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["go"],
+        Language {
+            name: "Go",
+            id: "go",
+            line_comments: C_LINE,
+            block_comments: C_BLOCK,
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["java"],
+        Language {
+            name: "Java",
+            id: "java",
+            line_comments: C_LINE,
+            block_comments: C_BLOCK,
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["kt", "kts"],
+        Language {
+            name: "Kotlin",
+            id: "kotlin",
+            line_comments: C_LINE,
+            block_comments: C_BLOCK,
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["scala"],
+        Language {
+            name: "Scala",
+            id: "scala",
+            line_comments: C_LINE,
+            block_comments: C_BLOCK,
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["swift"],
+        Language {
+            name: "Swift",
+            id: "swift",
+            line_comments: C_LINE,
+            block_comments: C_BLOCK,
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["cs"],
+        Language {
+            name: "C#",
+            id: "csharp",
+            line_comments: C_LINE,
+            block_comments: C_BLOCK,
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["js", "jsx", "mjs", "cjs"],
+        Language {
+            name: "JavaScript",
+            id: "javascript",
+            line_comments: C_LINE,
+            block_comments: C_BLOCK,
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["ts", "tsx", "mts", "cts"],
+        Language {
+            name: "TypeScript",
+            id: "typescript",
+            line_comments: C_LINE,
+            block_comments: C_BLOCK,
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["php"],
+        Language {
+            name: "PHP",
+            id: "php",
+            line_comments: &["//", "#"],
+            block_comments: C_BLOCK,
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["css"],
+        Language {
+            name: "CSS",
+            id: "css",
+            line_comments: &[],
+            block_comments: C_BLOCK,
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["scss", "less"],
+        Language {
+            name: "SCSS",
+            id: "scss",
+            line_comments: C_LINE,
+            block_comments: C_BLOCK,
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["py", "pyw", "pyi"],
+        Language {
+            name: "Python",
+            id: "python",
+            line_comments: HASH,
+            block_comments: &[],
+            docstrings: true,
+            string_quotes: &['"', '\''],
+        },
+    ),
+    (
+        &["rb", "rake"],
+        Language {
+            name: "Ruby",
+            id: "ruby",
+            line_comments: HASH,
+            block_comments: &[("=begin", "=end")],
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["sh", "bash", "zsh", "ksh"],
+        Language {
+            name: "Shell",
+            id: "shell",
+            line_comments: HASH,
+            block_comments: &[],
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["pl", "pm"],
+        Language {
+            name: "Perl",
+            id: "perl",
+            line_comments: HASH,
+            block_comments: &[],
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["r", "R"],
+        Language {
+            name: "R",
+            id: "r",
+            line_comments: HASH,
+            block_comments: &[],
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["yaml", "yml"],
+        Language {
+            name: "YAML",
+            id: "yaml",
+            line_comments: HASH,
+            block_comments: &[],
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["toml"],
+        Language {
+            name: "TOML",
+            id: "toml",
+            line_comments: HASH,
+            block_comments: &[],
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["mk", "cmake"],
+        Language {
+            name: "Make",
+            id: "make",
+            line_comments: HASH,
+            block_comments: &[],
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["sql"],
+        Language {
+            name: "SQL",
+            id: "sql",
+            line_comments: &["--"],
+            block_comments: C_BLOCK,
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["lua"],
+        Language {
+            name: "Lua",
+            id: "lua",
+            line_comments: &["--"],
+            block_comments: &[("--[[", "]]")],
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["hs"],
+        Language {
+            name: "Haskell",
+            id: "haskell",
+            line_comments: &["--"],
+            block_comments: &[("{-", "-}")],
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["html", "htm", "xhtml", "xml", "svg", "vue"],
+        Language {
+            name: "HTML",
+            id: "html",
+            line_comments: &[],
+            block_comments: HTML_BLOCK,
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["md", "markdown"],
+        Language {
+            name: "Markdown",
+            id: "markdown",
+            line_comments: &[],
+            block_comments: HTML_BLOCK,
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["tex"],
+        Language {
+            name: "TeX",
+            id: "tex",
+            line_comments: &["%"],
+            block_comments: &[],
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["mm"],
+        Language {
+            name: "Objective-C",
+            id: "objective_c",
+            line_comments: C_LINE,
+            block_comments: C_BLOCK,
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &[],
+        Language {
+            name: "MATLAB",
+            id: "matlab",
+            line_comments: &["%"],
+            block_comments: &[("%{", "%}")],
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+    (
+        &["lisp", "el", "clj", "scm"],
+        Language {
+            name: "Lisp",
+            id: "lisp",
+            line_comments: &[";"],
+            block_comments: &[],
+            docstrings: false,
+            string_quotes: DOUBLE_QUOTE,
+        },
+    ),
+];
+
+/// Comment syntax for files without an extension that are recognized by name.
+const MAKEFILE: Language = Language {
+    name: "Make",
+    id: "make",
+    line_comments: HASH,
+    block_comments: &[],
+    docstrings: false,
+    string_quotes: DOUBLE_QUOTE,
+};
+const DOCKERFILE: Language = Language {
+    name: "Dockerfile",
+    id: "dockerfile",
+    line_comments: HASH,
+    block_comments: &[],
+    docstrings: false,
+    string_quotes: DOUBLE_QUOTE,
+};
+
+/// Languages of files recognized by their whole name.
+const FILE_NAMES: &[(&[&str], &Language)] = &[
+    (
+        &["Makefile", "makefile", "GNUmakefile", "CMakeLists.txt"],
+        &MAKEFILE,
+    ),
+    (&["Dockerfile"], &DOCKERFILE),
+];
+
+/// A language defined with `--languages`, with the file extensions and file names it is
+/// recognized by.
+struct Custom {
+    exts: Vec<String>,
+    names: Vec<String>,
+    lang: &'static Language,
+}
+
+/// Languages loaded from files, which take precedence over the built-in languages.
+static CUSTOM: RwLock<Vec<Custom>> = RwLock::new(Vec::new());
+
+/// Whether the built-in languages are recognized, which a languages file can turn off to use
+/// only its own languages.
+static BUILTINS: AtomicBool = AtomicBool::new(true);
+
+/// Get the language of a file from its extension or name, if it is known.
+pub fn for_path(path: &Path) -> Option<&'static Language> {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let ext = path.extension().and_then(|e| e.to_str());
+
+    let custom = CUSTOM.read().unwrap();
+    let found = custom
+        .iter()
+        .find(|c| c.names.iter().any(|n| n == name))
+        .or_else(|| {
+            custom
+                .iter()
+                .find(|c| ext.map(|e| c.exts.iter().any(|x| x == e)).unwrap_or(false))
+        });
+    if let Some(c) = found {
+        return Some(c.lang);
+    }
+    if !BUILTINS.load(Ordering::Relaxed) {
+        return None;
+    }
+    // a built-in language that was redefined is only recognized by its new definition
+    let overridden = |lang: &Language| custom.iter().any(|c| c.lang.id == lang.id);
+
+    if let Some((_, lang)) = FILE_NAMES.iter().find(|(names, _)| names.contains(&name)) {
+        return Some(*lang).filter(|l| !overridden(l));
+    }
+
+    let ext = ext?;
+    LANGUAGES
+        .iter()
+        .find(|(exts, _)| exts.contains(&ext))
+        .map(|(_, lang)| lang)
+        .filter(|l| !overridden(l))
+}
+
+/// Look up a language by its name or a common alias (like an interpreter or an editor
+/// file type), ignoring case.
+pub fn by_name(name: &str) -> Option<&'static Language> {
+    let name = name.to_ascii_lowercase();
+    let alias = match name.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.') {
+        "python" | "py" => "python",
+        "sh" | "bash" | "zsh" | "ksh" | "dash" => "shell",
+        "node" | "nodejs" | "js" => "javascript",
+        "deno" | "ts-node" | "ts" => "typescript",
+        "rscript" => "r",
+        "cpp" | "c++" => "c++",
+        "objc" => "objective-c",
+        "make" | "makefile" => "make",
+        n => n,
+    };
+
+    all().find(|lang| lang.name.to_ascii_lowercase() == alias || lang.id == alias)
+}
+
+/// Look up a language by its exact display name.
+pub fn by_display_name(name: &str) -> Option<&'static Language> {
+    all().find(|lang| lang.name == name)
+}
+
+/// Get every language with the file patterns (`*.ext` or a whole file name) it is recognized
+/// by, with the languages loaded from files first and the built-in languages they redefine
+/// left out. A language can appear more than once.
+fn table() -> Vec<(Vec<String>, &'static Language)> {
+    let custom = CUSTOM.read().unwrap();
+    let mut table = custom
+        .iter()
+        .map(|c| {
+            let exts = c.exts.iter().map(|e| format!("*.{}", e));
+            (exts.chain(c.names.iter().cloned()).collect(), c.lang)
+        })
+        .collect::<Vec<_>>();
+
+    let exts = LANGUAGES
+        .iter()
+        .map(|(exts, lang)| (exts.iter().map(|e| format!("*.{}", e)).collect(), lang));
+    let names = FILE_NAMES
+        .iter()
+        .map(|(names, lang)| (names.iter().map(|n| n.to_string()).collect(), *lang));
+    if BUILTINS.load(Ordering::Relaxed) {
+        table.extend(
+            exts.chain(names)
+                .filter(|(_, lang)| !custom.iter().any(|c| c.lang.id == lang.id)),
+        );
+    }
+    table
+}
+
+/// Get every known language, with the file extensions and file names it is recognized by.
+pub fn all() -> impl Iterator<Item = &'static Language> {
+    let mut langs: Vec<&'static Language> = Vec::new();
+    for (_, lang) in table() {
+        if !langs.iter().any(|l| l.id == lang.id) {
+            langs.push(lang);
+        }
+    }
+    langs.into_iter()
+}
+
+/// Print the stable identifier, display name and file extensions and names of every
+/// language, which is the mapping between the identifiers in machine-readable output and
+/// the names shown to people.
+pub fn print_languages() {
+    let table = table();
+    let mut langs = all().collect::<Vec<_>>();
+    langs.sort_by_key(|lang| lang.id);
+
+    println!("{:<14} {:<14} Files", "ID", "Name");
+    for lang in langs {
+        let patterns = table
+            .iter()
+            .filter(|(_, l)| l.id == lang.id)
+            .flat_map(|(patterns, _)| patterns.iter().cloned());
+        println!(
+            "{:<14} {:<14} {}",
+            lang.id,
+            lang.name,
+            patterns.collect::<Vec<_>>().join(" ")
+        );
+    }
+}
+
+/// Get a hash of the language table: the file patterns, names, IDs and comment syntax of
+/// every language, including the ones loaded from files. It changes whenever any of them
+/// does, so tools can tell whether two builds classify files the same way.
+///
+/// This uses FNV-1a rather than the standard library's hasher, whose output may change
+/// between Rust versions.
+pub fn table_hash() -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut write = |s: &str| {
+        // a separator after each string keeps e.g. ["ab", "c"] and ["a", "bc"] apart
+        for b in s.bytes().chain([0]) {
+            hash ^= b as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    };
+
+    for (patterns, lang) in table() {
+        patterns.iter().for_each(|p| write(p));
+        write(lang.id);
+        write(lang.name);
+        lang.line_comments.iter().for_each(|c| write(c));
+        for (start, end) in lang.block_comments {
+            write(start);
+            write(end);
+        }
+        write(if lang.docstrings { "docstrings" } else { "" });
+        write(&lang.string_quotes.iter().collect::<String>());
+    }
+
+    hash
+}
+
+/// Quote a string for a TOML file.
+fn toml_string(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+        .replace('\r', "\\r");
+    format!("\"{}\"", escaped)
+}
+
+/// Quote strings as a TOML array.
+fn toml_array<'a>(items: impl IntoIterator<Item = &'a str>) -> String {
+    let items = items.into_iter().map(toml_string).collect::<Vec<_>>();
+    format!("[{}]", items.join(", "))
+}
+
+/// Write the language table in use, including languages loaded from a file, as a languages
+/// file that [`load`] reads back. Every setting of every language is spelled out and the
+/// built-in languages are turned off, so a project that pins its languages with it
+/// classifies files the same way after upgrades that add or change built-in languages.
+pub fn export() -> String {
+    let table = table();
+    let mut out = String::from("builtins = false\n");
+
+    for lang in all() {
+        let mut exts = Vec::new();
+        let mut names = Vec::new();
+        for (patterns, _) in table.iter().filter(|(_, l)| l.id == lang.id) {
+            for p in patterns {
+                match p.strip_prefix("*.") {
+                    Some(ext) => exts.push(ext),
+                    None => names.push(p.as_str()),
+                }
+            }
+        }
+        let bare = lang
+            .id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        let blocks = lang
+            .block_comments
+            .iter()
+            .map(|(start, end)| toml_array([*start, *end]))
+            .collect::<Vec<_>>();
+        let quotes = lang
+            .string_quotes
+            .iter()
+            .map(|q| q.to_string())
+            .collect::<Vec<_>>();
+
+        out.push('\n');
+        if bare {
+            out.push_str(&format!("[{}]\n", lang.id));
+        } else {
+            out.push_str(&format!("[{}]\n", toml_string(lang.id)));
+        }
+        out.push_str(&format!("name = {}\n", toml_string(lang.name)));
+        out.push_str(&format!("extensions = {}\n", toml_array(exts)));
+        out.push_str(&format!("filenames = {}\n", toml_array(names)));
+        out.push_str(&format!(
+            "line_comments = {}\n",
+            toml_array(lang.line_comments.iter().copied())
+        ));
+        out.push_str(&format!("block_comments = [{}]\n", blocks.join(", ")));
+        out.push_str(&format!(
+            "string_quotes = {}\n",
+            toml_array(quotes.iter().map(String::as_str))
+        ));
+        out.push_str(&format!("docstrings = {}\n", lang.docstrings));
+    }
+
+    out
+}
+
+/// Keys allowed in a table of a languages file.
+const LANGUAGE_KEYS: &[&str] = &[
+    "name",
+    "extensions",
+    "filenames",
+    "line_comments",
+    "block_comments",
+    "string_quotes",
+    "docstrings",
+];
+
+/// Load languages from a file with a table for each language, named after its identifier:
+///
+/// ```toml
+/// [my_dsl]
+/// name = "My DSL"
+/// extensions = ["dsl"]
+/// filenames = ["Dslfile"]
+/// line_comments = ["--"]
+/// block_comments = [["{-", "-}"]]
+/// string_quotes = ["\"", "`"]
+/// ```
+///
+/// A table named after a built-in language redefines it, and settings left out keep their
+/// built-in values. The languages are recognized before the built-in ones, so they can also
+/// take over extensions of other languages. With `builtins = false` before the first table,
+/// only the languages in the file are recognized.
+pub fn load(path: &Path) -> Result<(), String> {
+    let src = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    load_str(&src)
+}
+
+fn load_str(src: &str) -> Result<(), String> {
+    let tables = config::parse_tables(src)?;
+    for (key, value) in &tables[0].settings {
+        match (key.as_str(), value) {
+            ("builtins", config::Value::Bool(b)) => BUILTINS.store(*b, Ordering::Relaxed),
+            ("builtins", _) => return Err("`builtins` must be true or false".to_owned()),
+            _ => return Err(format!("`{}` is not in a language table", key)),
+        }
+    }
+
+    load_tables(&tables[1..])
+}
+
+/// Load languages from tables like the ones of a languages file, such as the
+/// `[languages.ID]` tables of a configuration file, each named after a language's ID.
+pub fn load_tables(tables: &[config::Table]) -> Result<(), String> {
+    let mut custom = CUSTOM.write().unwrap();
+    for table in tables {
+        let c = parse_custom(table).map_err(|e| format!("line {}: {}", table.line, e))?;
+        custom.retain(|other| other.lang.id != c.lang.id);
+        custom.push(c);
+    }
+    Ok(())
+}
+
+/// Snapshots of the built-in languages as languages files, one for each classification
+/// version starting from 1. A snapshot never changes once it is released; changes to the
+/// built-in languages are released as a new version.
+const CLASSIFICATIONS: &[&str] = &[include_str!("classifications/v1.toml")];
+
+/// The classification version of the built-in languages.
+pub const CLASSIFICATION_VERSION: u32 = CLASSIFICATIONS.len() as u32;
+
+/// Classify files with the built-in languages as they were in an earlier classification
+/// version, so counts do not change when a later release adds or changes languages.
+/// Languages loaded from files afterwards still redefine languages of the snapshot.
+pub fn pin_classification(version: u32) -> Result<(), String> {
+    let snapshot = version
+        .checked_sub(1)
+        .and_then(|i| CLASSIFICATIONS.get(i as usize))
+        .ok_or_else(|| {
+            format!(
+                "unknown classification version {} (this build has 1 to {})",
+                version, CLASSIFICATION_VERSION
+            )
+        })?;
+    load_str(snapshot)
+}
+
+/// Turn a table of a languages file into a language, filling in settings that are left out
+/// from the built-in language with the same identifier.
+fn parse_custom(table: &config::Table) -> Result<Custom, String> {
+    let id = table.name.as_str();
+    let builtin_exts = LANGUAGES.iter().filter(|(_, l)| l.id == id);
+    let builtin_names = FILE_NAMES.iter().filter(|(_, l)| l.id == id);
+    let builtin = builtin_exts
+        .clone()
+        .map(|(_, l)| l)
+        .chain(builtin_names.clone().map(|(_, l)| *l))
+        .next();
+
+    let get = |key: &str| {
+        table
+            .settings
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    };
+    if let Some((key, _)) = table
+        .settings
+        .iter()
+        .find(|(k, _)| !LANGUAGE_KEYS.contains(&k.as_str()))
+    {
+        return Err(format!("unknown setting `{}`", key));
+    }
+
+    let exts = match get("extensions") {
+        Some(v) => strings(v)?
+            .into_iter()
+            .map(|e| e.trim_start_matches('.').to_owned())
+            .collect(),
+        None => builtin_exts
+            .flat_map(|(exts, _)| exts.iter().map(|e| e.to_string()))
+            .collect::<Vec<_>>(),
+    };
+    let names = match get("filenames") {
+        Some(v) => strings(v)?,
+        None => builtin_names
+            .flat_map(|(names, _)| names.iter().map(|n| n.to_string()))
+            .collect(),
+    };
+    // built-in languages like MATLAB can be recognized by their contents alone
+    if exts.is_empty() && names.is_empty() && builtin.is_none() {
+        return Err(format!("language `{}` has no extensions or filenames", id));
+    }
+
+    let name = match get("name") {
+        Some(v) => leak_str(string(v)?),
+        None => builtin
+            .map(|l| l.name)
+            .unwrap_or_else(|| leak_str(id.to_owned())),
+    };
+    let line_comments = match get("line_comments") {
+        Some(v) => leak(strings(v)?.into_iter().map(leak_str).collect()),
+        None => builtin.map(|l| l.line_comments).unwrap_or(&[]),
+    };
+    let block_comments = match get("block_comments") {
+        Some(v) => leak(block_pairs(v)?),
+        None => builtin.map(|l| l.block_comments).unwrap_or(&[]),
+    };
+    let string_quotes = match get("string_quotes") {
+        Some(v) => leak(
+            strings(v)?
+                .iter()
+                .map(|q| {
+                    let mut chars = q.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => Ok(c),
+                        _ => Err(format!("string quote `{}` is not a single character", q)),
+                    }
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        None => builtin.map(|l| l.string_quotes).unwrap_or(DOUBLE_QUOTE),
+    };
+    let docstrings = match get("docstrings") {
+        Some(config::Value::Bool(b)) => *b,
+        Some(_) => return Err("`docstrings` must be true or false".to_owned()),
+        None => builtin.map(|l| l.docstrings).unwrap_or(false),
+    };
+
+    let lang = Box::leak(Box::new(Language {
+        name,
+        id: builtin
+            .map(|l| l.id)
+            .unwrap_or_else(|| leak_str(id.to_owned())),
+        line_comments,
+        block_comments,
+        docstrings,
+        string_quotes,
+    }));
+    Ok(Custom { exts, names, lang })
+}
+
+fn string(value: &config::Value) -> Result<String, String> {
+    match value {
+        config::Value::Str(s) => Ok(s.clone()),
+        v => Err(format!("expected a string but got {:?}", v)),
+    }
+}
+
+/// Get a string or an array of strings.
+fn strings(value: &config::Value) -> Result<Vec<String>, String> {
+    match value {
+        config::Value::Array(values) => values.iter().map(string).collect(),
+        v => Ok(vec![string(v)?]),
+    }
+}
+
+/// Get an array of `[start, end]` pairs of block comment markers.
+fn block_pairs(value: &config::Value) -> Result<Vec<(&'static str, &'static str)>, String> {
+    let pairs = match value {
+        config::Value::Array(pairs) => pairs,
+        v => return Err(format!("expected an array of pairs but got {:?}", v)),
+    };
+    pairs
+        .iter()
+        .map(|pair| match strings(pair)?.as_slice() {
+            [start, end] => Ok((leak_str(start.clone()), leak_str(end.clone()))),
+            _ => Err(format!("expected a [start, end] pair but got {:?}", pair)),
+        })
+        .collect()
+}
+
+/// Loaded languages live until the program exits, like the built-in ones.
+fn leak<T>(v: Vec<T>) -> &'static [T] {
+    Box::leak(v.into_boxed_slice())
+}
+
+fn leak_str(s: String) -> &'static str {
+    Box::leak(s.into_boxed_str())
+}
+
+/// Number of bytes at the start of a file that are read to detect its language.
+const HEAD_BYTES: u64 = 8192;
+
+/// Get the interpreter named by a shebang line like `#!/usr/bin/env python3`.
+fn shebang_interpreter(first_line: &str) -> Option<&str> {
+    let mut words = first_line.strip_prefix("#!")?.split_whitespace();
+    let program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        // skip options like `env -S`
+        words.find(|w| !w.starts_with('-'))
+    } else {
+        Some(program)
+    }
+}
+
+/// Get the file type set by a Vim (`vim: set ft=python:`) or Emacs (`-*- mode: ruby -*-`)
+/// modeline.
+fn modeline_type(line: &str) -> Option<&str> {
+    if let Some(i) = line.find("-*-") {
+        let inner = line[i + 3..].split("-*-").next()?.trim();
+        let mode = inner
+            .split(';')
+            .find_map(|v| v.trim().strip_prefix("mode:"))
+            .unwrap_or(inner);
+        return Some(mode.trim());
+    }
+
+    let i = ["vim:", "vi:", "ex:"].iter().find_map(|m| line.find(m))?;
+    line[i..]
+        .split(|c: char| c.is_whitespace() || c == ':')
+        .find_map(|w| {
+            w.strip_prefix("ft=")
+                .or_else(|| w.strip_prefix("filetype="))
+        })
+}
+
+/// Detect the language of a file, refining what its name says with its contents.
+///
+/// Files without a known extension are recognized by a shebang line or an editor
+/// modeline, and extensions shared by several languages (`.h` for C, C++ and
+/// Objective-C, `.m` for Objective-C and MATLAB) are told apart by characteristic syntax.
+pub fn detect(path: &Path) -> Option<&'static Language> {
+    let ext = path.extension().and_then(|e| e.to_str());
+    let known = for_path(path);
+    if known.is_some() && !matches!(ext, Some("h") | Some("m")) {
+        return known;
+    }
+
+    let mut head = Vec::new();
+    if let Ok(f) = File::open(path) {
+        let _ = f.take(HEAD_BYTES).read_to_end(&mut head);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let objc = ["@interface", "@implementation", "@end", "#import"]
+        .iter()
+        .any(|m| head.contains(m));
+
+    match ext {
+        Some("h") if objc => by_name("objective-c"),
+        Some("h")
+            if [
+                "class ",
+                "namespace ",
+                "template<",
+                "template <",
+                "std::",
+                "public:",
+            ]
+            .iter()
+            .any(|m| head.contains(m)) =>
+        {
+            by_name("c++")
+        }
+        Some("h") => known,
+        Some("m") if objc => by_name("objective-c"),
+        Some("m") => by_name("matlab"),
+        _ => head
+            .lines()
+            .next()
+            .and_then(shebang_interpreter)
+            .and_then(by_name)
+            .or_else(|| {
+                head.lines()
+                    .take(5)
+                    .find_map(modeline_type)
+                    .and_then(by_name)
+            }),
+    }
+}
+
+/// A multi-line construct that a line can end inside of.
+#[derive(Clone, Copy)]
+enum Open {
+    /// A block comment or docstring, with its end marker.
+    Comment(&'static str),
+    /// A triple-quoted string that is not a docstring, with its end marker.
+    String(&'static str),
+}
+
+/// Whether a non-empty line holds code or only comments.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineKind {
+    Code,
+    Comment,
+}
+
+/// Classifies the lines of a file one at a time, keeping track of block comments and
+/// strings that span several lines.
+pub struct Classifier {
+    lang: Option<&'static Language>,
+    open: Option<Open>,
+}
+
+impl Classifier {
+    /// Create a classifier for a language. Without a language every line is code.
+    pub fn new(lang: Option<&'static Language>) -> Self {
+        Classifier { lang, open: None }
+    }
+
+    /// Classify a non-empty line. A line with any code outside of comments is code.
+    pub fn classify(&mut self, line: &str) -> LineKind {
+        let lang = match self.lang {
+            Some(lang) => lang,
+            None => return LineKind::Code,
+        };
+
+        let mut rest = line;
+        let mut code = false;
+
+        loop {
+            match self.open {
+                Some(Open::Comment(end)) | Some(Open::String(end)) => {
+                    if let Some(Open::String(_)) = self.open {
+                        code = true;
+                    }
+                    match rest.find(end) {
+                        Some(i) => {
+                            rest = &rest[i + end.len()..];
+                            self.open = None;
+                        }
+                        None => break,
+                    }
+                }
+                None => {
+                    rest = rest.trim_start();
+                    if rest.is_empty() {
+                        break;
+                    }
+
+                    // docstrings are triple-quoted strings that start a line
+                    if lang.docstrings && !code {
+                        if let Some(q) = ["\"\"\"", "'''"].iter().find(|q| rest.starts_with(*q)) {
+                            rest = &rest[q.len()..];
+                            self.open = Some(Open::Comment(q));
+                            continue;
+                        }
+                    }
+                    // block comments first, since `--[[` in Lua also starts with `--`
+                    if let Some((start, end)) = lang
+                        .block_comments
+                        .iter()
+                        .find(|(s, _)| rest.starts_with(s))
+                    {
+                        rest = &rest[start.len()..];
+                        self.open = Some(Open::Comment(end));
+                        continue;
+                    }
+                    if lang.line_comments.iter().any(|c| rest.starts_with(c)) {
+                        break;
+                    }
+
+                    code = true;
+                    rest = self.skip_code(lang, rest);
+                }
+            }
+        }
+
+        if code {
+            LineKind::Code
+        } else {
+            LineKind::Comment
+        }
+    }
+
+    /// Skip over code up to the next comment marker, ignoring markers inside strings.
+    fn skip_code<'a>(&mut self, lang: &Language, s: &'a str) -> &'a str {
+        let mut chars = s.char_indices();
+
+        while let Some((i, c)) = chars.next() {
+            let rest = &s[i..];
+            if lang.line_comments.iter().any(|m| rest.starts_with(m))
+                || lang.block_comments.iter().any(|(m, _)| rest.starts_with(m))
+            {
+                return rest;
+            }
+
+            if lang.docstrings {
+                if let Some(q) = ["\"\"\"", "'''"].iter().find(|q| rest.starts_with(*q)) {
+                    // a triple-quoted string in the middle of code is a string, not a docstring
+                    let after = &rest[q.len()..];
+                    match after.find(q) {
+                        Some(j) => return &after[j + q.len()..],
+                        None => {
+                            self.open = Some(Open::String(q));
+                            return "";
+                        }
+                    }
+                }
+            }
+
+            if rest.starts_with("'\"'") {
+                // a character literal of a double quote does not start a string
+                chars.nth(1);
+            } else if lang.string_quotes.contains(&c) {
+                // skip the string, including escaped quotes
+                let mut escaped = false;
+                for (_, d) in chars.by_ref() {
+                    match d {
+                        '\\' if !escaped => escaped = true,
+                        d if d == c && !escaped => break,
+                        _ => escaped = false,
+                    }
+                }
+            }
+        }
+
+        ""
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use LineKind::{Code, Comment};
+
+    /// Classify the non-empty lines of a snippet in a language.
+    fn classify(lang: &str, text: &str) -> Vec<LineKind> {
+        let mut classifier = Classifier::new(by_name(lang));
+        text.lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| classifier.classify(l))
+            .collect()
+    }
+
+    #[test]
+    fn comment_markers_in_strings_are_code() {
+        assert_eq!(
+            classify(
+                "rust",
+                "let url = \"http://example.com\";\nlet s = \"/* not a comment\";\n\
+                 x(); // trailing comment\n// comment\n"
+            ),
+            [Code, Code, Code, Comment]
+        );
+        assert_eq!(
+            classify("c", "char *s = \"a\\\"// b\";\nchar q = '\"'; /* c */\n"),
+            [Code, Code]
+        );
+    }
+
+    #[test]
+    fn block_comments_span_lines() {
+        assert_eq!(
+            classify(
+                "c",
+                "/* start\n * middle\n end */\nint x; /* a */ int y;\n/* a */ /* b */\n\
+                 /* a */ int z;\n"
+            ),
+            [Comment, Comment, Comment, Code, Comment, Code]
+        );
+        // code after the end of a block comment on the same line counts
+        assert_eq!(classify("c", "/*\n*/ int x;\n"), [Comment, Code]);
+    }
+
+    #[test]
+    fn nested_comment_markers() {
+        // C block comments do not nest, so the first `*/` ends the comment
+        assert_eq!(
+            classify("c", "/* outer /* inner */\nint x;\n"),
+            [Comment, Code]
+        );
+        // a line comment marker inside a block comment does not hide its end
+        assert_eq!(classify("c", "/* // */ int x;\n"), [Code]);
+        // Lua block comments start with the line comment marker
+        assert_eq!(
+            classify("lua", "--[[\nlocal x = 1\n]]\n-- done\nlocal y = 2\n"),
+            [Comment, Comment, Comment, Comment, Code]
+        );
+    }
+
+    #[test]
+    fn docstrings_and_triple_quoted_strings() {
+        assert_eq!(
+            classify(
+                "python",
+                "\"\"\"Module\ndocs.\n\"\"\"\nx = \"\"\"a\n# not a comment\n\"\"\"\n# comment\n"
+            ),
+            [Comment, Comment, Comment, Code, Code, Code, Comment]
+        );
+    }
+
+    #[test]
+    fn without_a_language_everything_is_code() {
+        let mut classifier = Classifier::new(None);
+        assert_eq!(classifier.classify("// comment"), Code);
+    }
+
+    /// Write a file with some contents to a directory of its own and detect its language.
+    fn detect_file(name: &str, contents: &str) -> Option<&'static str> {
+        let dir = std::env::temp_dir().join(format!(
+            "rust-cloc-detect-{}-{}",
+            name.replace('.', "_"),
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        let lang = detect(&path).map(|l| l.id);
+        fs::remove_dir_all(&dir).unwrap();
+        lang
+    }
+
+    #[test]
+    fn detect_by_name_and_extension() {
+        assert_eq!(
+            detect(&PathBuf::from("src/main.rs")).map(|l| l.id),
+            Some("rust")
+        );
+        assert_eq!(
+            detect(&PathBuf::from("Makefile")).map(|l| l.id),
+            Some("make")
+        );
+        assert_eq!(detect_file("data.unknown", "just text\n"), None);
+    }
+
+    #[test]
+    fn detect_ambiguous_extensions_by_contents() {
+        assert_eq!(detect_file("a.h", "int f(void);\n"), Some("c"));
+        assert_eq!(
+            detect_file("b.h", "namespace x {\nclass A {};\n}\n"),
+            Some("cpp")
+        );
+        assert_eq!(
+            detect_file(
+                "c.h",
+                "#import <Foundation/Foundation.h>\n@interface A\n@end\n"
+            ),
+            Some("objective_c")
+        );
+        assert_eq!(
+            detect_file("d.m", "@implementation A\n@end\n"),
+            Some("objective_c")
+        );
+        assert_eq!(detect_file("e.m", "x = zeros(3);\n"), Some("matlab"));
+    }
+
+    #[test]
+    fn detect_shebangs_and_modelines() {
+        assert_eq!(
+            detect_file("script", "#!/usr/bin/env -S python3 -u\nprint(1)\n"),
+            Some("python")
+        );
+        assert_eq!(detect_file("run", "#!/bin/bash\necho hi\n"), Some("shell"));
+        assert_eq!(
+            detect_file("tool", "# vim: set ft=ruby:\nputs 1\n"),
+            Some("ruby")
+        );
+        assert_eq!(
+            detect_file("conf", "# -*- mode: perl; indent-tabs-mode: nil -*-\n"),
+            Some("perl")
+        );
+    }
+}
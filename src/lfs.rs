@@ -0,0 +1,75 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// First line of every Git LFS pointer file.
+const POINTER_VERSION: &str = "version https://git-lfs.github.com/spec/v1\n";
+
+/// Pointer files are tiny, so larger files are never read to check.
+const MAX_POINTER_BYTES: u64 = 1024;
+
+/// Get the SHA-256 object ID of a Git LFS pointer file, if the file is one.
+fn pointer_oid(path: &Path) -> Option<String> {
+    if fs::metadata(path).ok()?.len() > MAX_POINTER_BYTES {
+        return None;
+    }
+    let mut text = String::new();
+    File::open(path).ok()?.read_to_string(&mut text).ok()?;
+
+    let oid = text
+        .strip_prefix(POINTER_VERSION)?
+        .lines()
+        .find_map(|l| l.strip_prefix("oid sha256:"))?;
+    Some(oid.to_owned()).filter(|o| o.len() == 64 && o.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// Find the git directory of the repository a file is in, following the `gitdir:` link of
+/// worktrees and submodules.
+fn git_dir(path: &Path) -> Option<PathBuf> {
+    let path = fs::canonicalize(path).ok()?;
+    path.ancestors().skip(1).find_map(|dir| {
+        let git = dir.join(".git");
+        if git.is_dir() {
+            return Some(git);
+        }
+        let link = fs::read_to_string(&git).ok()?;
+        let target = link.strip_prefix("gitdir:")?.trim();
+        Some(dir.join(target))
+    })
+}
+
+/// Get where the local LFS object store keeps the contents of an object, if they have
+/// been downloaded.
+fn object_path(path: &Path, oid: &str) -> Option<PathBuf> {
+    let git_dir = git_dir(path)?;
+    // worktrees share the object store of the main repository
+    let common = fs::read_to_string(git_dir.join("commondir"))
+        .map(|c| git_dir.join(c.trim()))
+        .unwrap_or(git_dir);
+    let object = common
+        .join("lfs/objects")
+        .join(&oid[..2])
+        .join(&oid[2..4])
+        .join(oid);
+    Some(object).filter(|o| o.is_file())
+}
+
+/// Get the file to read for a file that is counted: the downloaded LFS object for an LFS
+/// pointer when resolving them, or the file itself.
+pub fn contents_path(path: &Path, resolve: bool) -> PathBuf {
+    if resolve {
+        if let Some(object) = pointer_oid(path).and_then(|oid| object_path(path, &oid)) {
+            return object;
+        }
+    }
+    path.to_owned()
+}
+
+/// Check whether a file is an LFS pointer that should be skipped, because its contents are
+/// not resolved or not available locally.
+pub fn skips_pointer(path: &Path, resolve: bool) -> bool {
+    match pointer_oid(path) {
+        Some(oid) => !resolve || object_path(path, &oid).is_none(),
+        None => false,
+    }
+}
@@ -0,0 +1,375 @@
+//! Count lines of code, comments and empty lines in a directory tree.
+//!
+//! [`Counter`] walks a directory and counts every file in it, producing a [`Report`] with
+//! the totals and a [`LanguageStats`] for each file type or language:
+//!
+//! ```no_run
+//! use rust_cloc::Counter;
+//!
+//! let report = Counter::new()
+//!     .threads(4)
+//!     .exclude("vendor/**".parse().unwrap())
+//!     .by_language(true)
+//!     .count("path/to/project");
+//!
+//! for (language, stats) in &report.types {
+//!     println!("{}: {} lines of code", language, stats.res.lines_of_code);
+//! }
+//! ```
+//!
+//! Two scans made with [`Counter::scan`], like before and after a change, can be compared
+//! with [`Comparison::between`] to get what changed for each language and each file.
+//!
+//! What could not be counted is listed in [`Report::skipped`]. The modules below hold the
+//! building blocks used by the `rust-cloc` command line tool, for callers that need finer
+//! control.
+
+use rayon::prelude::*;
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+pub mod binary;
+pub mod cache;
+mod compare;
+pub mod config;
+mod counter;
+pub mod encoding;
+pub mod families;
+pub mod glob;
+pub mod json;
+pub mod languages;
+pub mod lfs;
+pub mod presets;
+pub mod report;
+pub mod tally;
+pub mod throttle;
+pub mod walk;
+pub mod whitespace;
+
+pub use compare::{Comparison, Delta, FileChange, Scan, ScannedFile};
+pub use counter::Counter;
+pub use report::{LanguageStats, Report};
+
+use languages::{Classifier, LineKind};
+use tally::Tally;
+use walk::WalkOptions;
+use whitespace::BlankRule;
+
+/// Sum the sizes of files for each file type, using only file metadata. Sizes are read
+/// from `files`, found with `opts`, and grouped by the file type of the matching reported
+/// path in `paths`.
+pub fn count_bytes_by_ext(
+    files: &[PathBuf],
+    paths: &[PathBuf],
+    threads: usize,
+    opts: &WalkOptions,
+) -> HashMap<String, u64> {
+    let size = |(f, p): (&PathBuf, &PathBuf)| (get_ext(p), file_size(f, opts));
+    let reduce_fn = |mut map: HashMap<String, u64>, (ext, bytes): (String, u64)| {
+        *map.entry(ext).or_insert(0) += bytes;
+        map
+    };
+
+    if threads > 1 {
+        files
+            .par_iter()
+            .zip(paths)
+            .map(size)
+            .fold(HashMap::new, reduce_fn)
+            .reduce(HashMap::new, |mut a, b| {
+                for e in b.into_iter() {
+                    a = reduce_fn(a, e);
+                }
+                a
+            })
+    } else {
+        files
+            .iter()
+            .zip(paths)
+            .map(size)
+            .fold(HashMap::new(), reduce_fn)
+    }
+}
+
+/// Get the size of a file found with `opts`, or of the contents of an LFS pointer when
+/// resolving them, from its metadata alone.
+pub fn file_size(path: &Path, opts: &WalkOptions) -> u64 {
+    fs::metadata(opts.contents_path(path))
+        .map(|m| m.len())
+        .unwrap_or(0)
+}
+
+/// Count the number of empty and non-empty lines in a file found with `opts`.
+///
+/// Files can change while a live tree is being scanned. A file that cannot be opened, like
+/// one that disappeared after it was found, is skipped, and a file that fails partway
+/// through reading keeps the lines that were read; both are recorded in the tally of the
+/// options. Once a strict scan has stopped, files are no longer read.
+pub fn count_lines_in_file(path: &Path, opts: &WalkOptions) -> Results {
+    count_lines_in_file_checked(path, opts).0
+}
+
+/// Count lines in a file like [`count_lines_in_file`], also returning whether all of it was
+/// read, so results that were only partially counted are not saved for later runs.
+pub fn count_lines_in_file_checked(path: &Path, opts: &WalkOptions) -> (Results, bool) {
+    let tally = &opts.tally;
+    if tally.stopped() {
+        return (Results::new(), false);
+    }
+    throttle::throttled(|| {
+        let file = match File::open(opts.contents_path(path)) {
+            Ok(f) => f,
+            Err(e) => {
+                let reason = match e.kind() {
+                    ErrorKind::NotFound if fs::symlink_metadata(path).is_ok() => {
+                        "broken symlink".to_owned()
+                    }
+                    ErrorKind::NotFound => "disappeared before it could be read".to_owned(),
+                    _ => format!("unable to open ({})", e),
+                };
+                tally.record_error(path, reason);
+                return (Results::new(), false);
+            }
+        };
+        let bytes = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+        let (res, err) = count_lines_in_reader(
+            BufReader::new(file),
+            BlankRule::for_path(path),
+            languages::detect(path),
+            tally,
+        );
+        let complete = err.is_none();
+        if let Some(e) = err {
+            tally.record_error(
+                path,
+                format!("reading failed after {} lines ({})", res.total_lines(), e),
+            );
+        }
+        tally.record_counted(bytes, res.total_lines());
+        (res, complete)
+    })
+}
+
+/// Count the number of code, comment and empty lines from any buffered reader, so that
+/// contents that are not on disk (like git blobs) can be counted the same way as files.
+/// `blank_rule` decides which lines are empty for the type of file being read, and `lang`
+/// gives its comment syntax. Without a language, every line that is not empty is code.
+///
+/// Text that is not UTF-8 is decoded first (see [`encoding::lines`]) and recorded in
+/// `tally`. If reading fails partway through, the lines read so far are returned with the
+/// error.
+pub fn count_lines_in_reader<R: BufRead>(
+    r: R,
+    blank_rule: BlankRule,
+    lang: Option<&'static languages::Language>,
+    tally: &Tally,
+) -> (Results, Option<Error>) {
+    let mut res = Results::new();
+    let mut classifier = Classifier::new(lang);
+
+    let mut lines = encoding::lines(r);
+    let mut err = None;
+    for line in lines.by_ref() {
+        let line = match line {
+            Ok(l) => l,
+            Err(e) => {
+                err = Some(e);
+                break;
+            }
+        };
+
+        // a line is considered empty if it is actually empty or if it only contains whitespace,
+        // except where whitespace is significant for the file type
+        if blank_rule.is_empty(&line) {
+            res.empty_lines += 1;
+        } else {
+            match classifier.classify(&line) {
+                LineKind::Code => res.lines_of_code += 1,
+                LineKind::Comment => res.comment_lines += 1,
+            }
+        }
+    }
+
+    if lines.transcoded {
+        tally.record_transcoded();
+    }
+    (res, err)
+}
+
+/// Count lines for multiple files specified by their paths, keeping the results
+/// for each file in the same order as the paths.
+pub fn count_lines_per_file(files: &[PathBuf], threads: usize, opts: &WalkOptions) -> Vec<Results> {
+    if threads > 1 {
+        files
+            .par_iter()
+            .map(|f| count_lines_in_file(f, opts))
+            .collect()
+    } else {
+        files.iter().map(|f| count_lines_in_file(f, opts)).collect()
+    }
+}
+
+/// Walk a tree and count each file as soon as it is found, instead of listing every file
+/// before counting any. The walk runs on `walk_pool`, or on the current rayon thread pool if
+/// there is none, while files are counted on the current thread pool.
+///
+/// Files are returned sorted by path, with the results for each file in the same order.
+pub fn count_while_walking(
+    root: &Path,
+    opts: &WalkOptions,
+    walk_pool: Option<&rayon::ThreadPool>,
+) -> (Vec<PathBuf>, Vec<Results>) {
+    let (tx, rx) = mpsc::channel();
+
+    let mut counted = thread::scope(|s| {
+        s.spawn(move || {
+            let send = |f: PathBuf| {
+                // the receiver only goes away if counting panicked, which is reported there
+                let _ = tx.send(f);
+            };
+            match walk_pool {
+                Some(pool) => pool.install(|| walk::stream_files(root, opts, &send)),
+                None => walk::stream_files(root, opts, &send),
+            }
+        });
+
+        rx.into_iter()
+            .par_bridge()
+            .map(|f| {
+                let res = count_lines_in_file(&f, opts);
+                (f, res)
+            })
+            .collect::<Vec<_>>()
+    });
+
+    // files are found in a different order every time, so sort them to keep results stable
+    counted.sort_by(|a, b| a.0.cmp(&b.0));
+    counted.into_iter().unzip()
+}
+
+/// Count lines for multiple files specified by their paths, and aggregate
+/// the line counts across all files.
+pub fn count_lines(files: &[PathBuf], threads: usize, opts: &WalkOptions) -> Results {
+    // this function is used to aggregate the results of counting the lines of code
+    let reduce_fn = |a: Results, b: Results| a + b;
+
+    // map-reduce paradigm: count lines for each file separately, then combine the counted
+    // empty and non-empty lines together using a reduction operation
+    if threads > 1 {
+        files
+            .par_iter()
+            .map(|f| count_lines_in_file(f, opts))
+            .reduce(Results::new, reduce_fn)
+    } else {
+        files
+            .iter()
+            .map(|f| count_lines_in_file(f, opts))
+            .fold(Results::new(), reduce_fn)
+    }
+}
+
+/// Count lines and aggregate the counts for each file type.
+pub fn count_lines_by_ext(
+    files: &[PathBuf],
+    threads: usize,
+    opts: &WalkOptions,
+) -> HashMap<String, Results> {
+    let reduce_fn = add_ext_results;
+
+    // similar map-reduce paradigm to count_lines, but the reduction operation builds a HashMap
+    if threads > 1 {
+        files
+            .par_iter()
+            .map(|p| (get_ext(p), count_lines_in_file(p, opts)))
+            // generates multiple HashMaps in parallel by using reduce_fn
+            .fold(HashMap::new, reduce_fn)
+            // combine all the HashMap to get a single HashMap
+            .reduce(HashMap::new, |mut a, b| {
+                for e in b.into_iter() {
+                    a = reduce_fn(a, e);
+                }
+                a
+            })
+    } else {
+        files
+            .iter()
+            .map(|p| (get_ext(p), count_lines_in_file(p, opts)))
+            .fold(HashMap::new(), reduce_fn)
+    }
+}
+
+/// Add a new entry of file type and line counts to a running HashMap.
+/// If the HashMap contains the file type, then the line counts are summed. Otherwise a new
+/// entry is inserted.
+pub fn add_ext_results(
+    mut map: HashMap<String, Results>,
+    (new_ext, new_res): (String, Results),
+) -> HashMap<String, Results> {
+    map.entry(new_ext)
+        .and_modify(|e| *e = *e + new_res)
+        .or_insert(new_res);
+    map
+}
+
+/// File type reported for files without an extension, so they never end up under an
+/// empty key that tools consuming the output would choke on.
+pub const NO_EXT: &str = "(no extension)";
+
+/// Get the extension of a path.
+pub fn get_ext(path: &Path) -> String {
+    match path.extension() {
+        Some(p) => p.to_string_lossy().into_owned(),
+        None => NO_EXT.to_owned(),
+    }
+}
+
+/// Holds the number of code, comment and empty lines in a file.
+///
+/// Counts are kept as integers so that aggregating them gives exactly the same result no
+/// matter how many threads are used or in which order partial results are merged.
+/// Floating-point statistics like percentages are only derived from the final sums.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Results {
+    pub lines_of_code: usize,
+    pub comment_lines: usize,
+    pub empty_lines: usize,
+}
+
+impl Results {
+    /// Create a new zero-initialized Results instance.
+    pub fn new() -> Self {
+        Results {
+            lines_of_code: 0,
+            comment_lines: 0,
+            empty_lines: 0,
+        }
+    }
+
+    /// Get the number of lines, empty or not.
+    pub fn total_lines(&self) -> usize {
+        self.lines_of_code + self.comment_lines + self.empty_lines
+    }
+
+    /// Compute the percentage of empty lines.
+    pub fn percent_empty(&self) -> f64 {
+        (self.empty_lines as f64) / (self.total_lines() as f64) * 100.0f64
+    }
+}
+
+impl std::ops::Add for Results {
+    type Output = Results;
+
+    fn add(self, other: Results) -> Results {
+        Results {
+            lines_of_code: self.lines_of_code + other.lines_of_code,
+            comment_lines: self.comment_lines + other.comment_lines,
+            empty_lines: self.empty_lines + other.empty_lines,
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use crate::languages::{self, Classifier, LineKind};
+use crate::{encoding, get_ext};
+
+/// File types where single quotes delimit strings rather than characters or lifetimes.
+const SINGLE_QUOTE_STRINGS: &[&str] = &[
+    "py", "js", "jsx", "mjs", "cjs", "ts", "tsx", "rb", "php", "sh", "bash", "lua", "pl",
+];
+
+/// String and numeric literals in the files of one file type.
+#[derive(Default)]
+struct Literals {
+    code_lines: usize,
+    strings: usize,
+    /// Numeric literals other than 0 and 1, which are rarely magic.
+    numbers: usize,
+}
+
+impl Literals {
+    /// Count the literals in one line. Strings are assumed to end on the line they
+    /// start on.
+    fn add_line(&mut self, line: &str, single_quotes: bool) {
+        let chars = line.chars().collect::<Vec<_>>();
+        let mut i = 0;
+
+        while i < chars.len() {
+            let c = chars[i];
+            if c == '"' || (c == '\'' && single_quotes) {
+                self.strings += 1;
+                i += 1;
+                while i < chars.len() && chars[i] != c {
+                    // skip escaped characters, including escaped quotes
+                    i += if chars[i] == '\\' { 2 } else { 1 };
+                }
+            } else if c.is_ascii_digit()
+                && (i == 0 || !(chars[i - 1].is_alphanumeric() || chars[i - 1] == '_'))
+            {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || "_.".contains(chars[i])) {
+                    i += 1;
+                }
+                let number = chars[start..i].iter().collect::<String>();
+                if number != "0" && number != "1" {
+                    self.numbers += 1;
+                }
+                continue;
+            }
+            i += 1;
+        }
+    }
+}
+
+/// Number of items per thousand lines of code.
+fn per_kloc(count: usize, lines: usize) -> f64 {
+    if lines == 0 {
+        0.0
+    } else {
+        (count as f64) / (lines as f64) * 1000.0f64
+    }
+}
+
+/// Print the number of string literals and numeric literals (other than 0 and 1) per
+/// thousand lines of code for each file type, for finding hardcoded strings and magic
+/// numbers. Only lines of code are counted and scanned, so comments are left out.
+pub fn print_literal_density(files: &[PathBuf]) {
+    let mut stats: BTreeMap<String, Literals> = BTreeMap::new();
+
+    for f in files {
+        let file = match File::open(f) {
+            Ok(file) => file,
+            Err(_) => continue,
+        };
+        let ext = get_ext(f);
+        let single_quotes = SINGLE_QUOTE_STRINGS.contains(&ext.as_str());
+
+        let mut classifier = Classifier::new(languages::for_path(f));
+        let s = stats.entry(ext).or_default();
+        for line in encoding::lines(BufReader::new(file)).map_while(Result::ok) {
+            if !line.trim().is_empty() && classifier.classify(&line) == LineKind::Code {
+                s.code_lines += 1;
+                s.add_line(&line, single_quotes);
+            }
+        }
+    }
+
+    for (ext, s) in stats.iter().filter(|(_, s)| s.code_lines > 0) {
+        println!(
+            "In \"{}\" files, there are {:.2} string literals and {:.2} numeric literals per 1000 \
+             lines of code.",
+            ext,
+            per_kloc(s.strings, s.code_lines),
+            per_kloc(s.numbers, s.code_lines)
+        );
+    }
+}
@@ -1,193 +1,1445 @@
-use clap::Parser;
+use clap::{AppSettings, IntoApp, Parser, Subcommand};
 
-use rayon::prelude::*;
-
-use std::collections::HashMap;
-use std::fs::{self, *};
-use std::io::*;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
 use std::path::*;
+use std::process;
+use std::sync::Arc;
+
+mod api;
+mod bazel;
+mod cfg;
+mod checkpoint;
+mod checks;
+mod cocomo;
+mod commented;
+mod deadfiles;
+mod dirs;
+mod eol;
+mod exit;
+mod explain;
+mod filelist;
+mod git;
+mod gitdiff;
+mod imports;
+mod literals;
+mod migration;
+mod normalize;
+mod notify;
+mod outliers;
+mod output;
+mod perms;
+mod progress;
+mod projects;
+mod similar;
+mod skipped;
+mod stats;
+mod template;
+mod watch;
+mod weights;
+
+use output::{FileSort, Format, FormatSpec};
+use rust_cloc::families::{self, GroupBy};
+use rust_cloc::tally::Tally;
+use rust_cloc::{
+    add_ext_results, binary, cache, config, count_bytes_by_ext, count_lines, count_lines_by_ext,
+    count_lines_in_file, count_lines_in_file_checked, count_lines_in_reader, count_lines_per_file,
+    count_while_walking, encoding, get_ext, glob, languages, presets, report, tally, throttle,
+    walk, whitespace, Comparison, Counter, Delta, FileChange, Results, NO_EXT,
+};
+
+/// Directory depth limit used by `--sandbox` unless `--max-depth` is given.
+const SANDBOX_MAX_DEPTH: usize = 64;
 
 fn main() {
-    // use clap's convenient derive feature to parse arguments based on a struct we define
-    let args = Args::parse();
+    let (args, configs) = parse_args();
 
-    // recursively traverse the specified directory to get a list of all the files
-    let mut files = Vec::new();
-    find_all_files(Path::new(&args.directory), &mut files);
+    if let Some(version) = args.classification_version {
+        if let Err(e) = languages::pin_classification(version) {
+            eprintln!("Invalid --classification-version: {}", e);
+            process::exit(exit::USAGE);
+        }
+    }
+    // languages of more specific configuration files replace the ones of others
+    for config in configs.iter().rev() {
+        if let Err(e) = languages::load_tables(&config.languages) {
+            eprintln!("Invalid languages in {}: {}", config.path.display(), e);
+            process::exit(exit::USAGE);
+        }
+    }
+    if let Some(path) = &args.languages {
+        if let Err(e) = languages::load(path) {
+            eprintln!("Invalid languages file {}: {}", path.display(), e);
+            process::exit(exit::USAGE);
+        }
+    }
+
+    // counting untrusted input should never reach out to the network
+    if args.sandbox && args.notify_webhook.is_some() {
+        eprintln!("--notify-webhook cannot be used with --sandbox.");
+        process::exit(exit::USAGE);
+    }
+
+    if args.tab_width == 0 {
+        eprintln!("--tab-width must be at least 1.");
+        process::exit(exit::USAGE);
+    }
 
-    // set the number of threads to use in rayon
-    if args.threads > 1 {
+    // be a good neighbor when running as a background job
+    if let Some(increment) = args.nice {
+        throttle::renice(increment);
+    }
+    if let Some(percent) = args.max_cpu_percent {
+        throttle::set_max_cpu_percent(percent);
+    }
+
+    // everything the scan skips and how far it has come, shared by every walk and count
+    let tally = Arc::new(Tally::new().verbosity(args.verbose).strict(args.strict));
+
+    // directory walking and file counting can each be given their own number of threads,
+    // falling back to the shared `--threads` setting
+    let walk_threads = args.walk_threads.unwrap_or(args.threads);
+    let count_threads = args.count_threads.unwrap_or(args.threads);
+
+    let limits = checks::Limits {
+        max_lines: args.max_lines,
+        max_file_lines: args.max_file_lines,
+        max_percent_blank: args.max_percent_blank,
+    };
+
+    // set the number of threads to use in rayon for counting
+    if count_threads > 1 {
         rayon::ThreadPoolBuilder::new()
-            .num_threads(args.threads)
+            .num_threads(count_threads)
             .build_global()
             .unwrap();
     }
 
-    if args.by_ext {
-        // count lines for each file type and print results
-        let res_map = count_lines_by_ext(&files, args.threads);
-        res_map.iter().for_each(|(ext, res)| {
-            println!(
-                "There are {} lines of code in \"{}\" files.",
-                res.lines_of_code, ext
-            );
-            println!(
-                "There are {} empty lines in \"{}\" files.",
-                res.empty_lines, ext
-            );
-            println!(
-                "{:.2}% of the lines in \"{}\" files are empty.",
-                res.percent_empty(),
-                ext
+    match &args.command {
+        Some(Command::GitStats {
+            range,
+            repo,
+            by_author,
+            top,
+        }) => {
+            git::print_commit_stats(Path::new(repo), range, *by_author, *top);
+            return;
+        }
+        Some(Command::Diff { paths, matrix, .. }) if matrix.is_empty() => {
+            let mut counter = Counter::new()
+                .threads(count_threads)
+                .by_language(args.by_lang);
+            for glob in &args.exclude {
+                counter = counter.exclude(glob.clone());
+            }
+            for glob in &args.include {
+                counter = counter.include(glob.clone());
+            }
+            let cmp = Comparison::between(&counter.scan(&paths[0]), &counter.scan(&paths[1]));
+            print!("{}", output::delta_table(&cmp, args.by_lang));
+            return;
+        }
+        Some(Command::Diff { matrix, repo, .. }) => {
+            print_ref_matrix(Path::new(repo), matrix, count_threads);
+            return;
+        }
+        Some(Command::Languages { action: None }) => {
+            languages::print_languages();
+            return;
+        }
+        Some(Command::Languages {
+            action: Some(LanguagesCommand::Export),
+        }) => {
+            print!("{}", languages::export());
+            return;
+        }
+        Some(Command::Stats { all_projects, sort }) => {
+            if !all_projects {
+                eprintln!("stats needs --all-projects, the only kind of statistics so far.");
+                process::exit(exit::USAGE);
+            }
+            projects::print_all_projects(*sort);
+            return;
+        }
+        Some(Command::Migration {
+            from,
+            to,
+            directory,
+            since,
+        }) => {
+            migration::print_migration(Path::new(directory), from, to, since, count_threads);
+            return;
+        }
+        None => (),
+    }
+
+    // a missing local path is a typo, but a network share can be offline, so it is only
+    // skipped and the other paths are still counted
+    let mut inputs = Vec::new();
+    for input in &args.directory {
+        match fs::metadata(input) {
+            Ok(_) => inputs.push(input.clone()),
+            Err(e) if e.kind() == ErrorKind::NotFound && !presets::is_network_path(input) => {
+                eprintln!("{}: No such file or directory", input.display());
+                process::exit(exit::USAGE);
+            }
+            Err(e) => tally.record_error(input, format!("unable to access ({})", e)),
+        }
+    }
+    let inputs = &inputs;
+    // reported paths are relative to the scanned directory, or as given when there are
+    // several paths or a single file
+    let single_dir = inputs.len() == 1 && inputs[0].is_dir();
+    let root = if single_dir {
+        inputs[0].as_path()
+    } else {
+        Path::new("")
+    };
+    for (flag, used) in [
+        ("--diff", args.diff.is_some()),
+        ("--why", args.why.is_some()),
+        ("--watch", args.watch),
+        ("--bazel-query", args.bazel_query.is_some()),
+        ("--baseline", args.baseline.is_some()),
+    ] {
+        if used && !single_dir {
+            eprintln!("{} needs a single directory to scan.", flag);
+            process::exit(exit::USAGE);
+        }
+    }
+
+    let mut counter = Counter::new()
+        .threads(walk_threads)
+        .skip_symlinks(args.sandbox)
+        .follow_symlinks(args.follow_symlinks)
+        .binary_policy(args.binary_policy)
+        .sniff_bytes(args.sniff_bytes)
+        .count_binary(args.count_binary)
+        .resolve_lfs(args.resolve_lfs)
+        .without_reading(args.files_only)
+        .tally(Arc::clone(&tally));
+    for ext in &args.always_count_ext {
+        counter = counter.always_count_ext(ext.trim_start_matches('.'));
+    }
+    // untrusted trees get a depth limit by default so deep nesting cannot exhaust resources
+    if let Some(depth) = args.max_depth.or(if args.sandbox {
+        Some(SANDBOX_MAX_DEPTH)
+    } else {
+        None
+    }) {
+        counter = counter.max_depth(depth);
+    }
+    for glob in &args.exclude {
+        counter = counter.exclude(glob.clone());
+    }
+    for glob in &args.include {
+        counter = counter.include(glob.clone());
+    }
+    if let Some(attribute_to) = args.attribute_to {
+        counter = counter.attribute_to(attribute_to);
+    }
+    let walk_opts = counter.walk_options(root);
+
+    if let Some(range) = &args.diff {
+        gitdiff::print_diff(root, range, &walk_opts);
+        return;
+    }
+
+    if let Some(path) = &args.why {
+        explain::explain(root, path, &walk_opts);
+        return;
+    }
+
+    if args.watch {
+        watch::watch(root, &walk_opts, args.by_lang, args.sort);
+    }
+
+    // read the saved report up front too, so comparing with a broken one fails early
+    let saved = args.compare.as_ref().map(|path| {
+        let saved = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|src| report::Report::from_json(&src))
+            .unwrap_or_else(|e| {
+                eprintln!("Unable to read report {}: {}", path.display(), e);
+                process::exit(exit::USAGE);
+            });
+        if saved.by_language != args.by_lang {
+            eprintln!(
+                "{} has results by {}, so it can only be compared {} --by-lang.",
+                path.display(),
+                if saved.by_language {
+                    "language"
+                } else {
+                    "file type"
+                },
+                if saved.by_language { "with" } else { "without" }
             );
+            process::exit(exit::USAGE);
+        }
+        saved
+    });
+
+    // read the template up front so a broken template fails before a long scan
+    let template = args.template.as_ref().map(|path| {
+        let src = fs::read_to_string(path).unwrap_or_else(|e| {
+            eprintln!("Unable to read template {}: {}", path.display(), e);
+            process::exit(exit::USAGE);
         });
+        template::Template::parse(&src).unwrap_or_else(|e| {
+            eprintln!("Invalid template {}: {}", path.display(), e);
+            process::exit(exit::USAGE);
+        })
+    });
+
+    // report progress for wrappers that draw their own progress bars
+    let progress = args
+        .progress
+        .map(|format| progress::Reporter::start(format, Arc::clone(&tally)));
+
+    // with threads for both walking and counting, files are counted as soon as the walk finds
+    // them, unless the whole list is needed first or no file needs to be read at all
+    let file_list = match (&args.files_from, &args.files0_from) {
+        (Some(source), _) => Some((source, false)),
+        (_, Some(source)) => Some((source, true)),
+        _ => None,
+    };
+    let stream = walk_threads > 1
+        && count_threads > 1
+        && args.bazel_query.is_none()
+        && file_list.is_none()
+        && !args.resume
+        && args.cache.is_none()
+        && !args.colocation
+        && !args.unreferenced
+        && !args.files_only;
+
+    let (files, streamed) = if let Some(expr) = &args.bazel_query {
+        // count exactly the sources of the selected targets instead of walking the tree
+        let mut files = bazel::query_files(root, expr, &args.query_tool);
+        files.retain(|f| !walk_opts.skips_binary(f));
+        tally.record_discovered(files.len());
+        (files, None)
+    } else if let Some((source, nul_separated)) = file_list {
+        // count exactly the listed files, like the output of `git ls-files`, without walking
+        let mut files = filelist::read(source, nul_separated);
+        files.retain(|f| {
+            if !f.is_file() {
+                tally.record(f, "listed file is not a regular file".to_owned());
+                return false;
+            }
+            !walk_opts.skips_binary(f)
+        });
+        tally.record_discovered(files.len());
+        dedup_files(root, files, None, &walk_opts)
     } else {
-        // count and aggregate lines across all file types and print results
-        let res = count_lines(&files, args.threads);
-        println!("There are {} lines of code.", res.lines_of_code);
-        println!("There are {} empty lines.", res.empty_lines);
-        println!("{:.2}% of the lines are empty.", res.percent_empty());
+        // walk in a separate pool so its size is independent of the counting threads
+        let walk_pool = if walk_threads > 1 {
+            Some(
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(walk_threads)
+                    .build()
+                    .unwrap(),
+            )
+        } else {
+            None
+        };
+
+        // recursively traverse each directory to get a list of all the files, while files
+        // given directly are counted as they are
+        let mut files = Vec::new();
+        let mut streamed = if stream { Some(Vec::new()) } else { None };
+        for input in inputs {
+            let (found, per_file) = if !input.is_dir() {
+                if walk_opts.skips_binary(input) {
+                    continue;
+                }
+                tally.record_discovered(1);
+                let found = vec![input.clone()];
+                let per_file = if stream {
+                    Some(count_lines_per_file(&found, 1, &walk_opts))
+                } else {
+                    None
+                };
+                (found, per_file)
+            } else {
+                match &walk_pool {
+                    Some(pool) if stream => {
+                        let opts = counter.walk_options(input);
+                        let (found, per_file) = count_while_walking(input, &opts, Some(pool));
+                        (found, Some(per_file))
+                    }
+                    Some(pool) => (pool.install(|| counter.find_files(input)), None),
+                    None => (counter.find_files(input), None),
+                }
+            };
+            files.extend(found);
+            if let (Some(streamed), Some(per_file)) = (&mut streamed, per_file) {
+                streamed.extend(per_file);
+            }
+        }
+        dedup_files(root, files, streamed, &walk_opts)
+    };
+    tally.record_walk_done();
+    skipped::exit_if_stopped(&tally);
+
+    // outliers are still counted, so they do not make a scan empty
+    let found = files.len();
+
+    // paths used for grouping and reporting, which can differ from where files are read
+    let normalizer =
+        normalize::PathNormalizer::new(&args.strip_path_prefix, args.path_map.as_deref());
+    let normalized = if normalizer.is_empty() {
+        None
+    } else {
+        Some(
+            files
+                .iter()
+                .map(|f| normalizer.normalize(root, f))
+                .collect::<Vec<_>>(),
+        )
+    };
+    let paths = normalized.as_deref().unwrap_or(&files);
+
+    // file sizes only need metadata, so they are available even without reading any file
+    let bytes_map = count_bytes_by_ext(&files, paths, count_threads, &walk_opts);
+
+    if args.colocation {
+        dirs::print_colocation(paths);
+        return;
     }
-}
 
-/// Count the number of empty and non-empty lines in a file.
-fn count_lines_in_file(path: &PathBuf) -> Results {
-    let r = BufReader::new(File::open(path).expect("Unable to open file"));
-    let mut res = Results::new();
+    if args.unreferenced {
+        deadfiles::print_unreferenced(&files);
+        return;
+    }
 
-    for line in r.lines() {
-        // if there is an error in reading, just skip the file
-        let line = match line {
-            Ok(l) => l,
-            _ => return Results::new(),
+    if args.files_only {
+        print_file_counts(paths, &bytes_map, args.by_ext);
+        if args.by_dir {
+            // directories only need the number and size of their files, not their lines
+            let none = vec![Results::new(); files.len()];
+            let report = report::Report::new(paths, &HashMap::new(), &bytes_map).with_dirs(
+                root,
+                &files,
+                paths,
+                &none,
+                args.depth.unwrap_or(1),
+                &walk_opts,
+            );
+            for (dir, stats) in &report.dirs {
+                println!(
+                    "There are {} files with {} bytes in \"{}\".",
+                    stats.files, stats.bytes, dir
+                );
+            }
+        }
+        skipped::print_report(&tally, args.resolve_lfs);
+        drop(progress);
+        process::exit(exit::status(false, found, &tally));
+    }
+
+    // reports about individual files or directories need per-file results, which are
+    // otherwise reduced away while counting, and so does grouping by rewritten paths;
+    // counting while walking keeps them anyway
+    // binary detection decides which files are read, so a cache or checkpoint is only reused
+    // with the same settings for it
+    let settings = format!(
+        "binary {:?} {} {} {}",
+        args.binary_policy,
+        args.sniff_bytes,
+        args.count_binary,
+        args.always_count_ext.join(",")
+    );
+    let per_file = if streamed.is_some() {
+        streamed
+    } else if let Some(cache) = &args.cache {
+        Some(cache::count_lines_per_file_cached(
+            &files,
+            count_threads,
+            cache,
+            &settings,
+            &walk_opts,
+        ))
+    } else if args.resume {
+        let checkpoint = args
+            .checkpoint
+            .clone()
+            .or_else(|| checkpoint::default_path(root))
+            .unwrap_or_else(|| {
+                eprintln!("No cache directory for the checkpoint file, use --checkpoint");
+                process::exit(exit::USAGE);
+            });
+        Some(checkpoint::count_lines_per_file_resumable(
+            &files,
+            count_threads,
+            &checkpoint,
+            &settings,
+            &walk_opts,
+        ))
+    } else if args.top_dirs.is_some()
+        || limits.need_per_file()
+        || args.by_file
+        || args.by_root
+        || args.by_dir
+        || args.by_lang
+        || normalized.is_some()
+        || args.outlier_bucket.is_some()
+        || args.concentration
+        || args.format.iter().any(|s| {
+            matches!(
+                s.format,
+                Format::Report | Format::PrComment | Format::Statusline
+            )
+        })
+    {
+        Some(count_lines_per_file(&files, count_threads, &walk_opts))
+    } else {
+        None
+    };
+
+    // files matching `--outlier-bucket` are set aside and reported on their own, so that a
+    // few pathological files do not dominate the rest of the results
+    let is_outlier = match (&args.outlier_bucket, &per_file) {
+        (Some(rule), Some(per_file)) => files
+            .iter()
+            .zip(per_file)
+            .map(|(f, res)| rule.matches(f, res))
+            .collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+    let outliers = match &per_file {
+        Some(per_file) if is_outlier.contains(&true) => (0..files.len())
+            .filter(|&i| is_outlier[i])
+            .map(|i| (paths[i].clone(), per_file[i]))
+            .collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+    let (files, normalized, per_file, bytes_map) = if outliers.is_empty() {
+        (files, normalized, per_file, bytes_map)
+    } else {
+        let files = outliers::remove(files, &is_outlier);
+        let normalized = normalized.map(|n| outliers::remove(n, &is_outlier));
+        let bytes_map = count_bytes_by_ext(
+            &files,
+            normalized.as_deref().unwrap_or(&files),
+            count_threads,
+            &walk_opts,
+        );
+        let per_file = per_file.map(|p| outliers::remove(p, &is_outlier));
+        (files, normalized, per_file, bytes_map)
+    };
+    let paths = normalized.as_deref().unwrap_or(&files);
+
+    // grouping by family always prints the breakdown, since the total is the same either way
+    let by_type = args.by_ext || args.by_lang || args.group_by == GroupBy::Family;
+
+    let formats = if args.format.is_empty() {
+        vec![FormatSpec::stdout(Format::Table)]
+    } else {
+        args.format.clone()
+    };
+
+    // the results of each project are remembered for `stats --all-projects`
+    let record_project = single_dir && !args.sandbox && args.history;
+
+    // language share rules need the per-file-type breakdown even if it is not printed
+    let res_map = match &per_file {
+        Some(per_file) => Some(
+            paths
+                .iter()
+                .zip(per_file)
+                .map(|(f, res)| (get_ext(f), *res))
+                .fold(HashMap::new(), add_ext_results),
+        ),
+        None if by_type
+            || !args.fail_if_lang_share.is_empty()
+            || !limits.is_empty()
+            || !args.eol_rule.is_empty()
+            || args.max_line_width.is_some()
+            || !args.weight.is_empty()
+            || args.cocomo
+            || saved.is_some()
+            || record_project
+            || template.is_some()
+            || args.notify_webhook.is_some()
+            || formats.iter().any(|s| s.format != Format::Plain) =>
+        {
+            Some(count_lines_by_ext(&files, count_threads, &walk_opts))
+        }
+        None => None,
+    };
+    skipped::exit_if_stopped(&tally);
+
+    let report = res_map.as_ref().map(|res_map| {
+        let report = match (args.by_lang, &per_file) {
+            (true, Some(per_file)) => {
+                report::Report::by_language(&files, paths, per_file, &walk_opts)
+            }
+            _ => report::Report::new(paths, res_map, &bytes_map),
+        };
+        let report = match args.group_by {
+            GroupBy::Ext => report,
+            GroupBy::Family => report.group_by_family(&families::Families::new(&args.family)),
         };
+        let report = match (args.by_root, &per_file) {
+            (true, Some(per_file)) => report.with_roots(inputs, &files, per_file, &walk_opts),
+            _ => report,
+        };
+        match (args.by_dir, &per_file) {
+            (true, Some(per_file)) => report.with_dirs(
+                root,
+                &files,
+                paths,
+                per_file,
+                args.depth.unwrap_or(1),
+                &walk_opts,
+            ),
+            _ => report,
+        }
+    });
 
-        // a line is considered empty if it is actually empty or if it only contains whitespace
-        if line.trim().is_empty() {
-            res.empty_lines += 1;
-        } else {
-            res.lines_of_code += 1;
+    if let (true, Some(res_map)) = (record_project, &res_map) {
+        projects::record(root, &report::Report::new(paths, res_map, &bytes_map));
+    }
+
+    // pull request comments compare against the files in the baseline revision, filtered the
+    // same way as the files that were scanned
+    let baseline = if args.format.iter().any(|s| s.format == Format::PrComment) {
+        let rev = match &args.baseline {
+            Some(rev) => rev,
+            None => {
+                eprintln!("--format pr-comment needs a --baseline revision to compare against");
+                process::exit(exit::USAGE);
+            }
+        };
+        Some(git::count_rev_files(
+            root,
+            rev,
+            count_threads,
+            &walk_opts,
+            |rel| {
+                walk_opts.excluded_ancestor(rel).is_none() && walk_opts.included(Path::new(""), rel)
+            },
+        ))
+    } else {
+        None
+    };
+
+    let listed_files = match (args.by_file, &per_file) {
+        (true, Some(per_file)) => Some(output::sorted_files(paths, per_file, args.sort)),
+        _ => None,
+    };
+
+    // MIME types are sniffed from the real files, in the order they are listed
+    let mimes = match (args.with_mime, &listed_files) {
+        (true, Some(listed)) => {
+            let real = paths.iter().zip(&files).collect::<HashMap<_, _>>();
+            Some(
+                listed
+                    .iter()
+                    .map(|(p, _)| binary::mime_type(real[p]))
+                    .collect::<Vec<_>>(),
+            )
+        }
+        _ => None,
+    };
+
+    // render every requested output before writing any of them, so that a failure
+    // does not leave some outputs updated and others stale
+    let mut outputs = Vec::new();
+
+    if let (Some(template), Some(report)) = (&template, &report) {
+        // the user's template replaces whatever would have been printed to stdout
+        match template.render(report) {
+            Ok(out) => outputs.push((None, out)),
+            Err(e) => {
+                eprintln!("Unable to render template: {}", e);
+                process::exit(exit::USAGE);
+            }
+        }
+    }
+
+    for spec in &formats {
+        if template.is_some() && spec.path.is_none() {
+            continue;
+        }
+
+        let out = match (spec.format, &report, &per_file) {
+            (Format::Table, Some(report), _) => match &listed_files {
+                Some(listed) => output::file_table(root, listed, mimes.as_deref(), &report.total),
+                None => output::table(report, args.sort),
+            },
+            (Format::Table, None, _) => unreachable!("tables always count by file type"),
+            (Format::Plain, Some(report), _) => {
+                let mut out = match &listed_files {
+                    Some(listed) => {
+                        output::file_table(root, listed, mimes.as_deref(), &report.total)
+                    }
+                    None => output::plain(
+                        &report.total,
+                        if by_type { Some(&report.types) } else { None },
+                    ),
+                };
+                out.push_str(&output::plain_roots(&report.roots));
+                out.push_str(&output::plain_roots(&report.dirs));
+                out
+            }
+            (Format::Plain, None, _) => {
+                // count and aggregate lines across all file types
+                let total = report::LanguageStats {
+                    files: files.len(),
+                    res: count_lines(&files, count_threads, &walk_opts),
+                    bytes: bytes_map.values().sum(),
+                };
+                skipped::exit_if_stopped(&tally);
+                output::plain(&total, None)
+            }
+            (Format::Report, Some(report), Some(per_file)) => {
+                output::text_report(root, report, paths, per_file)
+            }
+            (Format::Report, _, _) => unreachable!("text reports always count per file"),
+            (Format::Json, Some(report), _) => {
+                output::json(root, report, listed_files.as_deref(), mimes.as_deref())
+            }
+            (Format::Json, None, _) => unreachable!("JSON output always counts by file type"),
+            (Format::Csv | Format::Tsv, Some(report), _) => output::delimited(
+                root,
+                report,
+                listed_files.as_deref(),
+                mimes.as_deref(),
+                spec.format == Format::Tsv,
+                !args.no_header,
+            ),
+            (Format::Csv | Format::Tsv, None, _) => {
+                unreachable!("CSV and TSV output always count by file type")
+            }
+            (Format::PrComment, Some(_), Some(per_file)) => {
+                let rev = args.baseline.as_deref().unwrap_or_default();
+                let baseline = baseline.as_deref().unwrap_or_default();
+                pr_comment(root, rev, baseline, paths, per_file, &args)
+            }
+            (Format::PrComment, _, _) => unreachable!("PR comments always count per file"),
+            (Format::Statusline, Some(report), Some(per_file)) => {
+                // the status line names languages even when the breakdown is by extension
+                if report.by_language {
+                    output::statusline(report)
+                } else {
+                    output::statusline(&report::Report::by_language(
+                        &files, paths, per_file, &walk_opts,
+                    ))
+                }
+            }
+            (Format::Statusline, _, _) => unreachable!("status lines always count per file"),
+            (Format::Markdown, Some(report), _) => output::markdown(
+                root,
+                report,
+                listed_files.as_deref(),
+                mimes.as_deref(),
+                args.sort,
+            ),
+            (Format::Html, Some(report), _) => output::html(
+                root,
+                report,
+                listed_files.as_deref(),
+                mimes.as_deref(),
+                args.sort,
+            ),
+            (Format::Markdown | Format::Html, None, _) => {
+                unreachable!("Markdown and HTML output always count by file type")
+            }
+        };
+        outputs.push((spec.path.clone(), out));
+    }
+
+    for (path, out) in outputs {
+        output::write_output(path.as_deref(), &out);
+    }
+
+    if !outliers.is_empty() {
+        outliers::print_outliers(&outliers);
+    }
+
+    if let (true, Some(report)) = (args.cocomo, &report) {
+        cocomo::print_estimate(
+            report.total.res.lines_of_code,
+            args.cocomo_mode,
+            args.cocomo_rate,
+        );
+    }
+
+    if let (Some(saved), Some(report)) = (&saved, &report) {
+        let path = args.compare.as_deref().unwrap_or_else(|| Path::new(""));
+        println!("\nChanges since {}:", path.display());
+        print!(
+            "{}",
+            output::delta_table(&Comparison::between_reports(saved, report), args.by_lang)
+        );
+    }
+
+    if let (false, Some(res_map)) = (args.weight.is_empty(), &res_map) {
+        println!(
+            "There are {:.1} weighted lines of code.",
+            weights::weighted_code(&args.weight, res_map)
+        );
+    }
+
+    if let (Some(n), Some(per_file)) = (args.top_dirs, &per_file) {
+        dirs::print_top_dirs(root, paths, per_file, n, args.top_dirs_direct);
+    }
+
+    if args.indent_stats {
+        whitespace::print_indent_stats(&files, args.tab_width);
+    }
+
+    if args.api_surface {
+        api::print_api_surface(&files);
+    }
+
+    if args.fan_out {
+        imports::print_fan_out(&files);
+    }
+
+    if args.literal_density {
+        literals::print_literal_density(&files);
+    }
+
+    if args.commented_code {
+        commented::print_commented_code(&files);
+    }
+
+    if args.cfg_stats {
+        cfg::print_cfg_stats(&files);
+    }
+
+    if args.perm_stats {
+        perms::print_perm_stats(&files);
+    }
+
+    if let (true, Some(per_file)) = (args.concentration, &per_file) {
+        stats::print_concentration(paths, per_file);
+    }
+
+    if let Some(percent) = args.near_duplicates {
+        similar::print_near_duplicates(&files, paths, percent);
+    }
+
+    let violations = match &res_map {
+        Some(res_map) => {
+            let mut violations = checks::check_shares(&args.fail_if_lang_share, res_map);
+            violations.extend(eol::check_eols(&args.eol_rule, &files, paths));
+            if let Some(max) = args.max_line_width {
+                violations.extend(whitespace::check_line_widths(
+                    &files,
+                    paths,
+                    max,
+                    args.tab_width,
+                ));
+            }
+            violations.extend(checks::check_limits(
+                &limits,
+                res_map,
+                per_file.as_deref().map(|p| (paths, p)),
+            ));
+            violations
         }
+        None => Vec::new(),
+    };
+    for v in &violations {
+        eprintln!("{}", v);
     }
 
-    res
+    if let (Some(url), Some(report)) = (&args.notify_webhook, &report) {
+        notify::post_summary(url, &args.notify_header, root, report, &violations);
+    }
+
+    skipped::print_report(&tally, args.resolve_lfs);
+
+    drop(progress);
+    process::exit(exit::status(!violations.is_empty(), found, &tally));
 }
 
-/// Count lines for multiple files specified by their paths, and aggregate
-/// the line counts across all files.
-fn count_lines(files: &[PathBuf], threads: usize) -> Results {
-    // this function is used to aggregate the results of counting the lines of code
-    let reduce_fn = |a: Results, b: Results| Results {
-        lines_of_code: a.lines_of_code + b.lines_of_code,
-        empty_lines: a.empty_lines + b.empty_lines,
-    };
-
-    // map-reduce paradigm: count lines for each file separately, then combine the counted
-    // empty and non-empty lines together using a reduction operation
-    if threads > 1 {
-        files
-            .par_iter()
-            .map(count_lines_in_file)
-            .reduce(|| Results::new(), reduce_fn)
+/// Remove files that were found more than once, like a file that was given on its own and
+/// inside a directory that was also given, keeping the first one. When following symlinks,
+/// files reached through a symlink and through another path are also found more than once,
+/// and are kept at the path they are attributed to.
+fn dedup_files(
+    root: &Path,
+    files: Vec<PathBuf>,
+    per_file: Option<Vec<Results>>,
+    walk_opts: &walk::WalkOptions,
+) -> (Vec<PathBuf>, Option<Vec<Results>>) {
+    let counted = per_file.is_some();
+    let per_file = match per_file {
+        Some(per_file) => per_file.into_iter().map(Some).collect(),
+        None => vec![None; files.len()],
+    };
+    let (files, per_file) = if walk_opts.follow_symlinks {
+        walk::dedup_links(root, files, per_file, walk_opts.attribute_to)
     } else {
-        files
-            .iter()
-            .map(count_lines_in_file)
-            .fold(Results::new(), reduce_fn)
-    }
+        (files, per_file)
+    };
+
+    let mut seen = HashSet::new();
+    let (files, per_file): (Vec<_>, Vec<_>) = files
+        .into_iter()
+        .zip(per_file)
+        .filter(|(f, _)| seen.insert(f.clone()))
+        .unzip();
+    let per_file = if counted {
+        Some(per_file.into_iter().flatten().collect())
+    } else {
+        None
+    };
+    (files, per_file)
 }
 
-/// Count lines and aggregate the counts for each file type.
-fn count_lines_by_ext(files: &[PathBuf], threads: usize) -> HashMap<String, Results> {
-    // This function is used to add a new entry of file type and line counts to a running HashMap.
-    // If the HashMap contains the file type, then the line counts are summed. Otherwise a new
-    // entry is inserted.
-    let reduce_fn = |mut map: HashMap<String, Results>, (new_ext, new_res): (String, Results)| {
-        map.entry(new_ext)
-            .and_modify(|e| {
-                e.lines_of_code += new_res.lines_of_code;
-                e.empty_lines += new_res.empty_lines;
-            })
-            .or_insert(new_res);
-        map
-    };
-
-    // similar map-reduce paradigm to count_lines, but the reduction operation builds a HashMap
-    if threads > 1 {
-        files
-            .par_iter()
-            .map(|p| (get_ext(p), count_lines_in_file(p)))
-            // generates multiple HashMaps in parallel by using reduce_fn
-            .fold(|| HashMap::new(), reduce_fn)
-            // combine all the HashMap to get a single HashMap
-            .reduce(
-                || HashMap::new(),
-                |mut a, b| {
-                    for e in b.into_iter() {
-                        a = reduce_fn(a, e);
-                    }
-                    a
-                },
-            )
+/// Print how many files there are, in total or for each file type, without reading any
+/// file contents.
+fn print_file_counts(files: &[PathBuf], bytes_map: &HashMap<String, u64>, by_ext: bool) {
+    if by_ext {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for f in files {
+            *counts.entry(get_ext(f)).or_insert(0) += 1;
+        }
+
+        let mut counts = counts.into_iter().collect::<Vec<_>>();
+        counts.sort();
+        for (ext, count) in counts {
+            println!(
+                "There are {} \"{}\" files with {} bytes.",
+                count,
+                ext,
+                bytes_map.get(&ext).copied().unwrap_or(0)
+            );
+        }
     } else {
-        files
-            .iter()
-            .map(|p| (get_ext(p), count_lines_in_file(p)))
-            .fold(HashMap::new(), reduce_fn)
+        println!(
+            "There are {} files with {} bytes.",
+            files.len(),
+            bytes_map.values().sum::<u64>()
+        );
     }
 }
 
-/// Recursively explore a directory to get a list of file paths.
-fn find_all_files(path: &Path, files: &mut Vec<PathBuf>) {
-    if path.is_dir() {
-        for entry in fs::read_dir(path).expect("Failed to read path") {
-            let entry = entry.unwrap();
-            let curr_path = entry.path();
+/// Render a pull request comment comparing the scanned files with the files in the
+/// `baseline` revision, given with paths relative to the root.
+fn pr_comment(
+    root: &Path,
+    rev: &str,
+    baseline: &[(PathBuf, Results)],
+    paths: &[PathBuf],
+    per_file: &[Results],
+    args: &Args,
+) -> String {
+    // git never tracks the files in `.git` itself, so they are not new files either
+    let (rel_paths, per_file) = paths
+        .iter()
+        .map(|p| p.strip_prefix(root).unwrap_or(p).to_owned())
+        .zip(per_file.iter().copied())
+        .filter(|(p, _)| !p.components().any(|c| c.as_os_str() == ".git"))
+        .unzip::<_, _, Vec<_>, Vec<_>>();
+    let per_file = &per_file[..];
+    let (baseline_paths, baseline_res) = baseline.iter().cloned().unzip::<_, _, Vec<_>, Vec<_>>();
 
-            if curr_path.is_dir() {
-                find_all_files(&curr_path, files);
-            } else {
-                files.push(curr_path);
-            }
+    // both sides are broken down by file type, since languages cannot be detected from
+    // files that are no longer on disk
+    let by_ext = |paths: &[PathBuf], per_file: &[Results]| {
+        let res_map = paths
+            .iter()
+            .zip(per_file)
+            .map(|(f, res)| (get_ext(f), *res))
+            .fold(HashMap::new(), add_ext_results);
+        let report = report::Report::new(paths, &res_map, &HashMap::new());
+        match args.group_by {
+            GroupBy::Ext => report,
+            GroupBy::Family => report.group_by_family(&families::Families::new(&args.family)),
         }
+    };
+
+    let existing = baseline_paths.iter().collect::<HashSet<_>>();
+    let (new_paths, new_res) = rel_paths
+        .iter()
+        .zip(per_file)
+        .filter(|(p, _)| !existing.contains(p))
+        .map(|(p, res)| (p.clone(), *res))
+        .unzip::<_, _, Vec<_>, Vec<_>>();
+
+    output::pr_comment(
+        rev,
+        &by_ext(&rel_paths, per_file),
+        &by_ext(&baseline_paths, &baseline_res),
+        &output::sorted_files(&new_paths, &new_res, FileSort::Code),
+    )
+}
+
+/// Count lines for each file type at several git revisions and print a matrix
+/// with one row per file type and one column per revision.
+fn print_ref_matrix(repo: &Path, revs: &[String], threads: usize) {
+    let counts = revs
+        .iter()
+        .map(|rev| git::count_rev_by_ext(repo, rev, threads))
+        .collect::<Vec<_>>();
+
+    let mut exts = counts
+        .iter()
+        .flat_map(|map| map.keys().cloned())
+        .collect::<Vec<_>>();
+    exts.sort();
+    exts.dedup();
+
+    let ext_width = exts.iter().map(|e| e.len()).max().unwrap_or(0).max(5);
+    let widths = revs.iter().map(|r| r.len().max(10)).collect::<Vec<_>>();
+
+    print!("{:<w$}", "", w = ext_width);
+    for (rev, w) in revs.iter().zip(&widths) {
+        print!("  {:>w$}", rev, w = w);
+    }
+    println!();
+
+    for ext in &exts {
+        print!("{:<w$}", ext, w = ext_width);
+        for (map, w) in counts.iter().zip(&widths) {
+            let code = map.get(ext).map(|r| r.lines_of_code).unwrap_or(0);
+            print!("  {:>w$}", code, w = w);
+        }
+        println!();
+    }
+
+    print!("{:<w$}", "total", w = ext_width);
+    for (map, w) in counts.iter().zip(&widths) {
+        let code = map.values().map(|r| r.lines_of_code).sum::<usize>();
+        print!("  {:>w$}", code, w = w);
+    }
+    println!();
+}
+
+/// Parse the command line arguments, filling in flags that were not given from the
+/// configuration files, which are returned for the languages they define.
+fn parse_args() -> (Args, Vec<config::Config>) {
+    // use clap's convenient derive feature to parse arguments based on a struct we define
+    let cli = env::args_os().collect::<Vec<_>>();
+    let app = Args::into_app();
+    let matches = app.clone().get_matches_from(&cli);
+    if matches.is_present("version") {
+        print_version(&matches);
+    }
+    if matches.is_present("no-config") {
+        return (Args::parse_from(cli), Vec::new());
     }
+
+    let explicit = matches.value_of("config").map(Path::new);
+    // a tree counted with --sandbox is not trusted to configure the scan
+    let directory = matches
+        .value_of("directory")
+        .map(Path::new)
+        .filter(|_| !matches.is_present("sandbox"));
+    let configs = config::find_files(explicit, directory).unwrap_or_else(|e| config_failed(e));
+    if configs.is_empty() {
+        return (Args::parse_from(cli), configs);
+    }
+
+    // configured flags go first, so the ones given on the command line can follow them
+    let mut argv = cli[..1].to_vec();
+    argv.extend(config::merge_args(&app, &matches, &configs).unwrap_or_else(|e| config_failed(e)));
+    argv.extend_from_slice(&cli[1..]);
+    (Args::parse_from(argv), configs)
 }
 
-/// Get the extension of a path.
-fn get_ext(path: &Path) -> String {
-    match path.extension() {
-        Some(p) => p.to_str().unwrap_or("").to_owned(),
-        None => "".to_owned(),
+/// Exit with an error message when a configuration file cannot be used.
+fn config_failed(message: String) -> ! {
+    eprintln!("{}", message);
+    process::exit(exit::USAGE);
+}
+
+/// Print the version, as JSON with `--format json`, and exit.
+fn print_version(matches: &clap::ArgMatches) -> ! {
+    let json = matches
+        .values_of("format")
+        .map(|mut formats| formats.any(|f| f == "json"))
+        .unwrap_or(false);
+    if json {
+        print!("{}", output::version_json());
+    } else {
+        println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
     }
+    process::exit(exit::SUCCESS);
 }
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about)]
+#[clap(setting = AppSettings::SubcommandsNegateReqs)]
+// `--version --format json` prints machine-readable version information
+#[clap(setting = AppSettings::NoAutoVersion)]
 struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
     #[clap(short = 'A', long)]
     by_ext: bool,
-    directory: String,
+    /// Break down the results by language, detected from file names, shebang lines, editor
+    /// modelines and content, instead of by file extension
+    #[clap(long)]
+    by_lang: bool,
+    /// Group the per-type breakdown by file extension or by language family
+    #[clap(long, arg_enum, default_value = "ext")]
+    group_by: GroupBy,
+    /// Define a language family as `NAME=EXT,EXT,...` for `--group-by family`, replacing
+    /// the built-in family of those extensions (can be repeated)
+    #[clap(long, value_name = "NAME=EXTS")]
+    family: Vec<families::FamilySpec>,
+    /// List every file with its line counts instead of only totals
+    #[clap(long)]
+    by_file: bool,
+    /// Add the MIME type of each file listed by `--by-file`, sniffed from its first bytes
+    #[clap(long, requires = "by-file")]
+    with_mime: bool,
+    /// Order of the rows of the table output and of the files listed by `--by-file`
+    #[clap(long, arg_enum, default_value = "lines")]
+    sort: FileSort,
+    /// Only count files without reading them, for a quick inventory of large trees. Binary
+    /// files are only recognized by extension and Git LFS pointers are counted as files
+    #[clap(long)]
+    files_only: bool,
+    /// Directories to scan and files to count
+    #[clap(
+        value_name = "PATH",
+        required_unless_present_any = &["files-from", "files0-from", "version"]
+    )]
+    directory: Vec<PathBuf>,
+    /// Count the files listed one per line in a file, or in stdin if FILE is `-`, instead of
+    /// walking directories, e.g. `git ls-files | rust-cloc --files-from -`
+    #[clap(long, value_name = "FILE", conflicts_with_all = &["directory", "files0-from"])]
+    files_from: Option<PathBuf>,
+    /// Like `--files-from`, with file names separated by NUL bytes, as printed by
+    /// `git ls-files -z` or `find -print0`
+    #[clap(long, value_name = "FILE", conflicts_with = "directory")]
+    files0_from: Option<PathBuf>,
+    /// Also break down the results by each path given on the command line
+    #[clap(long)]
+    by_root: bool,
+    /// Also break down the results by the top-level directories of the scanned directory, or
+    /// the directories `--depth` levels deep
+    #[clap(long)]
+    by_dir: bool,
+    /// How many levels of directories to break down the results by with `--by-dir`
+    #[clap(long, value_name = "N", requires = "by-dir")]
+    depth: Option<usize>,
     #[clap(short = 'j', long, default_value_t = 1)]
     threads: usize,
+    /// Count the source files of the build targets matched by a Bazel query expression like
+    /// `//src/...`, run in the scanned directory, instead of walking it
+    #[clap(long, value_name = "EXPR")]
+    bazel_query: Option<String>,
+    /// Build tool to run `--bazel-query` with, e.g. `buck2`
+    #[clap(long, value_name = "COMMAND", default_value = "bazel")]
+    query_tool: String,
+    /// Move files matching a rule like `lines>20000`, `code>5000` or `bytes>1000000` out of
+    /// the results into a separate outliers section
+    #[clap(long, value_name = "RULE")]
+    outlier_bucket: Option<outliers::OutlierRule>,
+    /// Remove a prefix (relative to the scanned directory) from reported paths, like a
+    /// build system's output directory (can be repeated)
+    #[clap(long, value_name = "PREFIX")]
+    strip_path_prefix: Vec<PathBuf>,
+    /// Report generated files under the paths of their sources, using a file with one
+    /// "generated<TAB>source" pair per line
+    #[clap(long, value_name = "FILE")]
+    path_map: Option<PathBuf>,
+    /// Output format, optionally with a file to write it to as `FORMAT=PATH` (can be repeated
+    /// to produce several outputs from one scan) [possible values: table, plain, report,
+    /// json, csv, tsv, pr-comment, statusline, markdown, html]
+    #[clap(long, visible_alias = "output", value_name = "FORMAT[=PATH]")]
+    format: Vec<FormatSpec>,
+    /// Leave out the header row of CSV and TSV output, e.g. to append to an existing table
+    #[clap(long)]
+    no_header: bool,
+    /// Count the lines added, removed and modified in each language between two git
+    /// revisions (`REV1..REV2`), or between a revision and the working tree
+    #[clap(long, value_name = "REVS")]
+    diff: Option<String>,
+    /// Git revision to compare against for `--format pr-comment`, e.g. the target branch
+    #[clap(long, value_name = "REV")]
+    baseline: Option<String>,
+    /// Show the change in each file type or language since a report saved earlier with
+    /// `--format json=FILE`
+    #[clap(long, value_name = "FILE")]
+    compare: Option<PathBuf>,
+    /// Number of threads for directory traversal (defaults to `--threads`)
+    #[clap(long)]
+    walk_threads: Option<usize>,
+    /// Number of threads for reading and counting files (defaults to `--threads`)
+    #[clap(long)]
+    count_threads: Option<usize>,
+    /// Lower the process priority by this amount, like `nice -n`
+    #[clap(long, value_name = "INCREMENT")]
+    nice: Option<i32>,
+    /// Keep each counting thread busy at most this percentage of the time
+    #[clap(long, value_name = "PERCENT")]
+    max_cpu_percent: Option<usize>,
+    /// Scale the lines of code in a file type by a factor like `sql=0.1` and report the
+    /// weighted total alongside the raw counts (can be repeated)
+    #[clap(long, value_name = "EXT=FACTOR")]
+    weight: Vec<weights::Weight>,
+    /// Fail if the share of code lines in some file types matches a rule like
+    /// `js>40%` or `ts<80% of js+ts` (can be repeated)
+    #[clap(long, value_name = "RULE")]
+    fail_if_lang_share: Vec<checks::ShareRule>,
+    /// Define new languages or redefine built-in ones with a TOML file, with a table for each
+    /// language giving its extensions, file names and comment syntax, like one written by
+    /// `languages export`
+    #[clap(long, alias = "languages-file", value_name = "FILE")]
+    languages: Option<PathBuf>,
+    /// Classify files with the built-in languages frozen as classification version N, so
+    /// results only change when the version is raised on purpose (this build has 1 to the
+    /// version listed by `--version --format json`)
+    #[clap(long, value_name = "N")]
+    classification_version: Option<u32>,
+    /// Fail if a file of some type has line endings other than the ones required by a rule
+    /// like `rs=lf` or `bat=crlf` (can be repeated)
+    #[clap(long, value_name = "EXT=EOL")]
+    eol_rule: Vec<eol::EolRule>,
+    /// Fail if there are more than this many lines of code in total
+    #[clap(long, value_name = "N")]
+    max_lines: Option<usize>,
+    /// Fail if any file has more than this many lines, including comments and blank lines
+    #[clap(long, value_name = "N")]
+    max_file_lines: Option<usize>,
+    /// Fail if more than this percentage of all lines are blank
+    #[clap(long, value_name = "PERCENT")]
+    max_percent_blank: Option<f64>,
+    /// Fail if any line is wider than this many columns, with tabs expanded to `--tab-width`
+    #[clap(long, value_name = "COLUMNS")]
+    max_line_width: Option<usize>,
+    /// Number of columns between tab stops when measuring line widths and indentation
+    #[clap(long, value_name = "COLUMNS", default_value_t = whitespace::DEFAULT_TAB_WIDTH)]
+    tab_width: usize,
+    /// Periodically save progress and pick up where an interrupted scan left off
+    #[clap(long)]
+    resume: bool,
+    /// Keep the results of each file in this file, and only count files that changed since
+    /// the last run with the same cache
+    #[clap(long, value_name = "FILE", conflicts_with = "resume")]
+    cache: Option<PathBuf>,
+    /// Where to save progress for `--resume` (defaults to a file in the per-user cache
+    /// directory)
+    #[clap(long, requires = "resume")]
+    checkpoint: Option<PathBuf>,
+    /// List the N directories with the most lines of code, including subdirectories
+    #[clap(long, value_name = "N")]
+    top_dirs: Option<usize>,
+    /// Only count files directly inside each directory for `--top-dirs`
+    #[clap(long, requires = "top-dirs")]
+    top_dirs_direct: bool,
+    /// Report how concentrated the code is in the largest files (top 1%/10% share and Gini
+    /// coefficient) for each file type
+    #[clap(long)]
+    concentration: bool,
+    /// Print a CSV matrix of how many directories contain each pair of file types
+    #[clap(long)]
+    colocation: bool,
+    /// List source files that no build configuration (Cargo targets and `mod` declarations,
+    /// or `CMakeLists.txt` sources) refers to
+    #[clap(long)]
+    unreferenced: bool,
+    /// List pairs of files with at least this percentage of their (whitespace-normalized)
+    /// lines in common, like diverged copies of the same module
+    #[clap(long, value_name = "PERCENT")]
+    near_duplicates: Option<f64>,
+    /// Report how many files of each language are executable or setuid (or hidden or system
+    /// files on Windows)
+    #[clap(long)]
+    perm_stats: bool,
+    /// Report indentation widths for whitespace-significant file types like Python and YAML
+    #[clap(long)]
+    indent_stats: bool,
+    /// Report the number of public items in the Rust files of each crate
+    #[clap(long)]
+    api_surface: bool,
+    /// Report the number of import, include and use statements per file for each file type
+    #[clap(long)]
+    fan_out: bool,
+    /// Report string and numeric literals per 1000 lines of code for each file type
+    #[clap(long)]
+    literal_density: bool,
+    /// Report comment lines that look like commented-out code and the files with the most
+    #[clap(long)]
+    commented_code: bool,
+    /// Report how many lines of C/C++ and Rust code are in conditionally compiled or
+    /// disabled (`#if 0`) blocks
+    #[clap(long)]
+    cfg_stats: bool,
+    /// Count binary files like images and executables, which are skipped by default (the
+    /// same as `--binary-policy none`)
+    #[clap(long)]
+    count_binary: bool,
+    /// How binary files are recognized and skipped
+    #[clap(long, arg_enum, default_value = "nul-sniff")]
+    binary_policy: binary::BinaryPolicy,
+    /// Number of bytes at the start of each file checked for NUL bytes by
+    /// `--binary-policy nul-sniff`
+    #[clap(long, value_name = "BYTES", default_value_t = binary::DEFAULT_SNIFF_BYTES)]
+    sniff_bytes: u64,
+    /// Comma-separated extensions of files that are always counted even if they look
+    /// binary, like `sql` for dumps with embedded NUL bytes (can be repeated)
+    #[clap(long, value_name = "EXTS", use_delimiter = true)]
+    always_count_ext: Vec<String>,
+    /// Do not count files or visit directories matching a glob like `vendor/**` or
+    /// `*.min.js`, relative to the scanned directory (can be repeated)
+    #[clap(long, value_name = "GLOB")]
+    exclude: Vec<glob::Glob>,
+    /// Only count files matching a glob like `*.rs` or `src/**` (can be repeated)
+    #[clap(long, value_name = "GLOB")]
+    include: Vec<glob::Glob>,
+    /// Estimate the effort, schedule and cost of developing the counted code with the basic
+    /// COCOMO model
+    #[clap(long)]
+    cocomo: bool,
+    /// Kind of project for `--cocomo`
+    #[clap(long, arg_enum, default_value = "organic")]
+    cocomo_mode: cocomo::CocomoMode,
+    /// Average annual salary of a developer for `--cocomo` cost estimates
+    #[clap(long, value_name = "SALARY", default_value_t = cocomo::DEFAULT_ANNUAL_SALARY)]
+    cocomo_rate: f64,
+    /// Remember the results of scanning this directory for `stats --all-projects`, in the
+    /// per-user cache directory (can also be set in the global config file)
+    #[clap(long)]
+    history: bool,
+    /// Harden the scan for untrusted input: never follow symlinks, limit directory depth
+    /// and disallow network access
+    #[clap(long)]
+    sandbox: bool,
+    /// Count the contents of Git LFS pointer files when they have been downloaded, instead of
+    /// skipping the pointers
+    #[clap(long)]
+    resolve_lfs: bool,
+    /// Follow symlinks to files and directories, counting each file once no matter how many
+    /// links lead to it (symlinks are left out by default)
+    #[clap(long, conflicts_with = "sandbox")]
+    follow_symlinks: bool,
+    /// Report files reached through symlinks under the path of the link or of the file it
+    /// leads to, which decides the directories they are rolled up into (by default, files
+    /// are reported at the path they were found at, preferring one without symlinks)
+    #[clap(long, arg_enum, value_name = "PATH", requires = "follow-symlinks")]
+    attribute_to: Option<walk::AttributeTo>,
+    /// Show progress (files found and counted, lines and bytes read and the estimated time
+    /// left) on stderr, as a status line or as JSON events for wrappers that show their own
+    /// progress bar
+    #[clap(
+        long,
+        arg_enum,
+        value_name = "FORMAT",
+        min_values = 0,
+        default_missing_value = "bar"
+    )]
+    progress: Option<progress::ProgressFormat>,
+    /// Log each skipped or unreadable path on stderr as it is found, and with `-vv` also each
+    /// path that is ignored on purpose, like excluded, binary and symlinked files
+    #[clap(short, long, parse(from_occurrences))]
+    verbose: usize,
+    /// Keep watching the directory and redraw the results in place whenever files change,
+    /// counting only the changed files again and showing the net change in lines
+    #[clap(long)]
+    watch: bool,
+    /// Stop at the first file or directory that cannot be read, instead of skipping it and
+    /// listing it at the end
+    #[clap(long)]
+    strict: bool,
+    /// Do not descend more than this many directories below the scanned directory
+    #[clap(long, value_name = "DEPTH")]
+    max_depth: Option<usize>,
+    /// Explain how a single file under the scanned directory is classified and counted
+    #[clap(long, value_name = "PATH")]
+    why: Option<PathBuf>,
+    /// Render the results with a handlebars-style template file instead of the usual output
+    #[clap(long, value_name = "FILE")]
+    template: Option<PathBuf>,
+    /// POST a summary of the results and any failed checks to a Slack-compatible webhook
+    #[clap(long, value_name = "URL")]
+    notify_webhook: Option<String>,
+    /// Extra header for `--notify-webhook` requests, like `Authorization: Bearer TOKEN`; best
+    /// set in the global config file so the secret is not in the process list (can be
+    /// repeated)
+    #[clap(long, value_name = "HEADER", requires = "notify-webhook")]
+    notify_header: Vec<String>,
+    /// Read default flags from this file instead of `.cloc.toml` in the scanned directory
+    /// and the global `rust-cloc/config.toml` in `$XDG_CONFIG_HOME`
+    #[clap(long, value_name = "FILE", conflicts_with = "no-config")]
+    config: Option<PathBuf>,
+    /// Ignore all configuration files
+    #[clap(long)]
+    no_config: bool,
 }
 
-/// Holds the number of empty and non-empty lines in a file.
-struct Results {
-    lines_of_code: usize,
-    empty_lines: usize,
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Report the distribution of lines added and removed per commit
+    GitStats {
+        /// Revision range to analyze, in any form accepted by `git log`
+        #[clap(default_value = "HEAD")]
+        range: String,
+        /// Path to the git repository
+        #[clap(short = 'C', long, default_value = ".")]
+        repo: String,
+        /// Also report statistics for each author
+        #[clap(long)]
+        by_author: bool,
+        /// Number of largest commits to list
+        #[clap(long, default_value_t = 5)]
+        top: usize,
+    },
+    /// Compare line counts across git revisions, or between two directories
+    Diff {
+        /// Two directories to compare, like two checkouts or releases of a project
+        #[clap(
+            value_name = "PATH",
+            min_values = 2,
+            max_values = 2,
+            conflicts_with = "matrix"
+        )]
+        paths: Vec<PathBuf>,
+        /// Comma-separated revisions to count side by side, e.g. `main,develop,release/1.2`
+        #[clap(long, required_unless_present = "paths", use_delimiter = true)]
+        matrix: Vec<String>,
+        /// Path to the git repository
+        #[clap(short = 'C', long, default_value = ".")]
+        repo: String,
+    },
+    /// List the stable ID, display name and file patterns of every known language
+    Languages {
+        #[clap(subcommand)]
+        action: Option<LanguagesCommand>,
+    },
+    /// Report statistics across scans
+    Stats {
+        /// Merge the last results of every project scanned by this user into one overview
+        #[clap(long)]
+        all_projects: bool,
+        /// Order of the rows
+        #[clap(long, arg_enum, default_value = "code")]
+        sort: FileSort,
+    },
+    /// Track the progress of migrating code from one language to another
+    Migration {
+        /// Comma-separated extensions of the language being migrated away from, e.g. `js,jsx`
+        #[clap(long, required = true, use_delimiter = true)]
+        from: Vec<String>,
+        /// Comma-separated extensions of the language being migrated to, e.g. `ts,tsx`
+        #[clap(long, required = true, use_delimiter = true)]
+        to: Vec<String>,
+        /// Directory to scan
+        #[clap(default_value = ".")]
+        directory: String,
+        /// How far back in the git history to look for the trend, in any form accepted by
+        /// `git log --before`
+        #[clap(long, default_value = "90 days ago")]
+        since: String,
+    },
 }
 
-impl Results {
-    /// Create a new zero-initialized Results instance.
-    fn new() -> Self {
-        Results {
-            lines_of_code: 0,
-            empty_lines: 0,
-        }
-    }
-
-    /// Compute the percentage of empty lines.
-    fn percent_empty(&self) -> f64 {
-        (self.empty_lines as f64) / ((self.lines_of_code + self.empty_lines) as f64) * 100.0f64
-    }
+#[derive(Subcommand, Debug)]
+enum LanguagesCommand {
+    /// Write the language table in use as a languages file, to audit, edit or pin it with
+    /// `--languages-file`
+    Export,
 }
@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+use crate::walk::{self, WalkOptions};
+use crate::{count_lines_per_file, get_ext, git, output};
+
+/// Lines of code still in the source language and already in the target language.
+#[derive(Default, Clone, Copy)]
+struct Progress {
+    remaining: usize,
+    converted: usize,
+}
+
+impl Progress {
+    /// Percentage of the code that has been converted.
+    fn percent(&self) -> f64 {
+        let total = self.remaining + self.converted;
+        if total == 0 {
+            0.0
+        } else {
+            (self.converted as f64) / (total as f64) * 100.0f64
+        }
+    }
+
+    /// Add the lines of code of a file type, if it is in the source or target language.
+    fn add(&mut self, ext: &str, code: usize, from: &[String], to: &[String]) {
+        if from.iter().any(|e| e == ext) {
+            self.remaining += code;
+        } else if to.iter().any(|e| e == ext) {
+            self.converted += code;
+        }
+    }
+}
+
+/// Get the top-level directory of a file under `root`, or `.` for files directly in it.
+fn top_dir(root: &Path, file: &Path) -> PathBuf {
+    let rel = file.strip_prefix(root).unwrap_or(file);
+    let mut components = rel.components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(dir)), Some(_)) => PathBuf::from(dir),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// Find the last commit before a date (in any form `git log --before` accepts) and get its
+/// hash and commit time, if the directory is in a git repository with such a commit.
+fn commit_before(dir: &Path, since: &str) -> Option<(String, u64)> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["log", "-1", "--format=%H %ct"])
+        .arg(format!("--before={}", since))
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let out = String::from_utf8_lossy(&output.stdout);
+    let (hash, time) = out.trim().split_once(' ')?;
+    Some((hash.to_owned(), time.parse().ok()?))
+}
+
+/// Print how far a migration from one language to another has come, overall and for each
+/// top-level directory, and project a completion date from the git history.
+///
+/// Languages are given as lists of file extensions. The projection compares the committed
+/// trees at `HEAD` and at the last commit before `since`, and extrapolates linearly.
+pub fn print_migration(root: &Path, from: &[String], to: &[String], since: &str, threads: usize) {
+    let opts = WalkOptions::default();
+    let mut files = Vec::new();
+    walk::find_all_files(root, &opts, &mut files);
+    files.retain(|f| {
+        let ext = get_ext(f);
+        from.contains(&ext) || to.contains(&ext)
+    });
+    let per_file = count_lines_per_file(&files, threads, &opts);
+
+    let mut now = Progress::default();
+    let mut by_dir: BTreeMap<PathBuf, Progress> = BTreeMap::new();
+    for (f, res) in files.iter().zip(&per_file) {
+        let ext = get_ext(f);
+        now.add(&ext, res.lines_of_code, from, to);
+        by_dir
+            .entry(top_dir(root, f))
+            .or_default()
+            .add(&ext, res.lines_of_code, from, to);
+    }
+
+    println!(
+        "Migration from {} to {} in {}: {} lines of code converted ({:.2}%), {} remaining.",
+        from.join(","),
+        to.join(","),
+        root.display(),
+        now.converted,
+        now.percent(),
+        now.remaining
+    );
+
+    println!(
+        "{:>10} {:>10} {:>8}  Directory",
+        "Converted", "Remaining", "%"
+    );
+    for (dir, p) in &by_dir {
+        println!(
+            "{:>10} {:>10} {:>8.2}  {}",
+            p.converted,
+            p.remaining,
+            p.percent(),
+            dir.display()
+        );
+    }
+
+    print_projection(root, from, to, since, threads);
+}
+
+/// Print the change in progress between `since` and `HEAD` and the projected completion date.
+fn print_projection(root: &Path, from: &[String], to: &[String], since: &str, threads: usize) {
+    let (then_rev, then_time) = match commit_before(root, since) {
+        Some(c) => c,
+        None => {
+            println!(
+                "No git history before {}, so no completion date can be projected.",
+                since
+            );
+            return;
+        }
+    };
+    let (_, head_time) = match commit_before(root, "now") {
+        Some(c) => c,
+        None => return,
+    };
+
+    let progress_at = |rev: &str| {
+        let mut p = Progress::default();
+        for (ext, res) in git::count_rev_by_ext(root, rev, threads) {
+            p.add(&ext, res.lines_of_code, from, to);
+        }
+        p
+    };
+    let then = progress_at(&then_rev);
+    let head = progress_at("HEAD");
+
+    let days = head_time.saturating_sub(then_time) as f64 / 86400.0f64;
+    let rate = if days > 0.0 {
+        (head.percent() - then.percent()) / days
+    } else {
+        0.0
+    };
+
+    println!(
+        "Committed progress went from {:.2}% at {} to {:.2}% at HEAD ({:+.3} percentage points \
+         per day).",
+        then.percent(),
+        output::utc_date(then_time),
+        head.percent(),
+        rate
+    );
+
+    if head.remaining == 0 {
+        println!("The migration is complete.");
+    } else if rate <= 0.0 {
+        println!("At this rate the migration will not be completed.");
+    } else {
+        let days_left = (100.0f64 - head.percent()) / rate;
+        println!(
+            "At this rate the migration will be completed around {}.",
+            output::utc_date(head_time + (days_left * 86400.0f64) as u64)
+        );
+    }
+}
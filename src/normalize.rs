@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use crate::exit;
+
+/// Rewrites the paths of counted files into the paths reported for them, so that results
+/// line up with the tree developers see rather than build-system internals.
+///
+/// Files are still read from their real paths; only grouping by file type or directory and
+/// the paths shown in reports use the rewritten ones.
+#[derive(Default)]
+pub struct PathNormalizer {
+    /// Prefixes (relative to the scan root) removed from paths, like Bazel output directories.
+    strip_prefixes: Vec<PathBuf>,
+    /// Generated files mapped to the sources they were generated from, relative to the
+    /// scan root.
+    map: HashMap<PathBuf, PathBuf>,
+}
+
+impl PathNormalizer {
+    /// Create a normalizer from prefixes to strip and an optional mapping file.
+    ///
+    /// Each line of the mapping file is "generated<TAB>source", with both paths relative
+    /// to the scan root. Empty lines and lines starting with `#` are ignored.
+    pub fn new(strip_prefixes: &[PathBuf], map_file: Option<&Path>) -> Self {
+        let mut map = HashMap::new();
+
+        if let Some(map_file) = map_file {
+            let src = fs::read_to_string(map_file).unwrap_or_else(|e| {
+                eprintln!("Unable to read path map {}: {}", map_file.display(), e);
+                process::exit(exit::USAGE);
+            });
+            for (i, line) in src.lines().enumerate() {
+                if line.trim().is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                match line.split_once('\t') {
+                    Some((from, to)) => {
+                        map.insert(PathBuf::from(from.trim()), PathBuf::from(to.trim()));
+                    }
+                    None => {
+                        eprintln!(
+                            "Invalid path map {} line {}: expected \"generated<TAB>source\"",
+                            map_file.display(),
+                            i + 1
+                        );
+                        process::exit(exit::USAGE);
+                    }
+                }
+            }
+        }
+
+        PathNormalizer {
+            strip_prefixes: strip_prefixes.to_vec(),
+            map,
+        }
+    }
+
+    /// Check whether any path would be rewritten at all.
+    pub fn is_empty(&self) -> bool {
+        self.strip_prefixes.is_empty() && self.map.is_empty()
+    }
+
+    /// Get the reported path of a file under the scan root. Mapped paths are looked up
+    /// first, then the first matching prefix is stripped.
+    pub fn normalize(&self, root: &Path, path: &Path) -> PathBuf {
+        let rel = match path.strip_prefix(root) {
+            Ok(rel) => rel,
+            Err(_) => return path.to_owned(),
+        };
+        let rel = self.map.get(rel).map(PathBuf::as_path).unwrap_or(rel);
+        let rel = self
+            .strip_prefixes
+            .iter()
+            .find_map(|p| rel.strip_prefix(p).ok())
+            .unwrap_or(rel);
+
+        root.join(rel)
+    }
+}
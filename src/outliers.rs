@@ -0,0 +1,102 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::Results;
+
+/// What is measured to decide whether a file is an outlier.
+#[derive(Clone, Copy, Debug)]
+enum Measure {
+    /// All lines, including comments and empty lines.
+    Lines,
+    Code,
+    Bytes,
+}
+
+/// A rule like `lines>20000` or `bytes>=1000000` for files that are moved into a separate
+/// outliers section instead of being counted with everything else.
+#[derive(Clone, Debug)]
+pub struct OutlierRule {
+    measure: Measure,
+    /// Files measuring more than this are outliers.
+    limit: u64,
+}
+
+impl FromStr for OutlierRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (measure, limit) = s
+            .split_once('>')
+            .ok_or_else(|| format!("expected a rule like lines>20000 but got \"{}\"", s))?;
+
+        // `>=N` is the same as `>N-1` for whole numbers
+        let (limit, inclusive) = match limit.strip_prefix('=') {
+            Some(limit) => (limit, true),
+            None => (limit, false),
+        };
+        let limit = limit
+            .trim()
+            .parse::<u64>()
+            .map_err(|_| format!("invalid limit in \"{}\"", s))?;
+
+        let measure = match measure.trim() {
+            "lines" => Measure::Lines,
+            "code" => Measure::Code,
+            "bytes" => Measure::Bytes,
+            m => {
+                return Err(format!(
+                    "unknown measure \"{}\", expected lines, code or bytes",
+                    m
+                ))
+            }
+        };
+
+        Ok(OutlierRule {
+            measure,
+            limit: if inclusive {
+                limit.saturating_sub(1)
+            } else {
+                limit
+            },
+        })
+    }
+}
+
+impl OutlierRule {
+    /// Check whether a file is an outlier, given its line counts.
+    pub fn matches(&self, path: &Path, res: &Results) -> bool {
+        let value = match self.measure {
+            Measure::Lines => res.total_lines() as u64,
+            Measure::Code => res.lines_of_code as u64,
+            Measure::Bytes => fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        };
+        value > self.limit
+    }
+}
+
+/// Remove the items at the positions of outliers, keeping the order of the rest.
+pub fn remove<T>(v: Vec<T>, is_outlier: &[bool]) -> Vec<T> {
+    v.into_iter()
+        .zip(is_outlier)
+        .filter(|(_, &o)| !o)
+        .map(|(x, _)| x)
+        .collect()
+}
+
+/// Print the outliers section, which accounts for the files left out of the other results.
+pub fn print_outliers(outliers: &[(PathBuf, Results)]) {
+    let total = outliers.iter().fold(Results::new(), |a, (_, res)| a + *res);
+
+    println!(
+        "Outliers (not included above): {} files with {} lines of code, {} comment lines and {} \
+         empty lines.",
+        outliers.len(),
+        total.lines_of_code,
+        total.comment_lines,
+        total.empty_lines
+    );
+    for (f, res) in outliers {
+        println!("  {:>10}  {}", res.lines_of_code, f.display());
+    }
+}
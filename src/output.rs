@@ -0,0 +1,1373 @@
+use clap::ArgEnum;
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::exit;
+use crate::languages;
+use crate::report::{LanguageStats, Report};
+use crate::{Comparison, Delta, FileChange, Results};
+
+/// Number of files listed in the "Largest files" section of a text report.
+const TOP_FILES: usize = 10;
+
+/// Output formats for the counted results.
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// Aligned table with a row for each file type and a SUM row
+    Table,
+    /// One sentence per statistic
+    Plain,
+    /// Fixed-width plain-text report suitable for email
+    Report,
+    /// JSON document with the totals and a breakdown for each file type
+    Json,
+    /// Comma-separated values with a header row and one row per file type or file
+    Csv,
+    /// Tab-separated values with a header row and one row per file type or file
+    Tsv,
+    /// Markdown summary of the changes since `--baseline`, to post as a pull request comment
+    PrComment,
+    /// A single compact line like `42.3k code · 12% comments · Rust 68%` for status bars
+    Statusline,
+    /// GitHub-flavored Markdown tables, e.g. for pull request descriptions
+    Markdown,
+    /// Standalone HTML page with tables that can be sorted by any column
+    Html,
+}
+
+/// Order of the rows of the table output and of the files listed by `--by-file`.
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum FileSort {
+    /// Most lines first
+    Lines,
+    /// Most lines of code first
+    Code,
+    /// Most comment lines first
+    Comments,
+    /// Most empty lines first
+    Blank,
+    /// Most files first
+    Files,
+    /// By path or name
+    Path,
+}
+
+/// Pair each reported path with its line counts, sorted for `--by-file`. Ties are broken
+/// by path so the order is stable.
+pub fn sorted_files<'a>(
+    paths: &'a [PathBuf],
+    per_file: &[Results],
+    sort: FileSort,
+) -> Vec<(&'a PathBuf, Results)> {
+    let mut files = paths
+        .iter()
+        .zip(per_file.iter().copied())
+        .collect::<Vec<_>>();
+    let key = |res: &Results| match sort {
+        FileSort::Lines => res.total_lines(),
+        FileSort::Code => res.lines_of_code,
+        FileSort::Comments => res.comment_lines,
+        FileSort::Blank => res.empty_lines,
+        FileSort::Files | FileSort::Path => 0,
+    };
+    files.sort_by(|a, b| key(&b.1).cmp(&key(&a.1)).then(a.0.cmp(b.0)));
+    files
+}
+
+/// Render a table of every file with its line counts, followed by the totals. With
+/// `mimes`, the sniffed MIME type of each file is shown before its path.
+pub fn file_table(
+    root: &Path,
+    files: &[(&PathBuf, Results)],
+    mimes: Option<&[&str]>,
+    total: &LanguageStats,
+) -> String {
+    let mut out = String::new();
+    let mime_width = mimes
+        .map(|m| m.iter().map(|m| m.len()).max().unwrap_or(0).max(9))
+        .unwrap_or(0);
+
+    writeln!(
+        out,
+        "{:>10} {:>10} {:>10} {:>10}  {}Path",
+        "Code",
+        "Comment",
+        "Empty",
+        "Lines",
+        match mimes {
+            Some(_) => format!("{:<w$}  ", "MIME type", w = mime_width),
+            None => String::new(),
+        }
+    )
+    .unwrap();
+    for (i, (f, res)) in files.iter().enumerate() {
+        let path = f.strip_prefix(root).unwrap_or(f);
+        let label = match mimes {
+            Some(mimes) => format!("{:<w$}  {}", mimes[i], path.display(), w = mime_width),
+            None => path.display().to_string(),
+        };
+        write_file_row(&mut out, res, &label);
+    }
+    writeln!(out, "{}", "-".repeat(48)).unwrap();
+    write_file_row(&mut out, &total.res, &total_label(total.files));
+
+    out
+}
+
+/// Get the label of the total row of a list of files, like `Total (3 files)`.
+fn total_label(files: usize) -> String {
+    if files == 1 {
+        "Total (1 file)".to_owned()
+    } else {
+        format!("Total ({} files)", files)
+    }
+}
+
+fn write_file_row(out: &mut String, res: &Results, label: &str) {
+    writeln!(
+        out,
+        "{:>10} {:>10} {:>10} {:>10}  {}",
+        res.lines_of_code,
+        res.comment_lines,
+        res.empty_lines,
+        res.total_lines(),
+        label
+    )
+    .unwrap();
+}
+
+/// Get the tables of a report with the heading of their first column: file types or
+/// languages, then the paths given on the command line with `--by-root` and directories
+/// with `--by-dir`. Rows are in the order given by `sort`, except that paths stay in the
+/// order they were given.
+fn report_tables(
+    report: &Report,
+    sort: FileSort,
+) -> Vec<(&'static str, Vec<&(String, LanguageStats)>)> {
+    let heading = if report.by_language {
+        "Language"
+    } else {
+        "Type"
+    };
+    let mut rows = report.types.iter().collect::<Vec<_>>();
+    let key = |stats: &LanguageStats| match sort {
+        FileSort::Lines => stats.res.total_lines(),
+        FileSort::Code => stats.res.lines_of_code,
+        FileSort::Comments => stats.res.comment_lines,
+        FileSort::Blank => stats.res.empty_lines,
+        FileSort::Files => stats.files,
+        FileSort::Path => 0,
+    };
+    rows.sort_by(|a, b| key(&b.1).cmp(&key(&a.1)).then(a.0.cmp(&b.0)));
+
+    let mut tables = vec![(heading, rows)];
+    if !report.roots.is_empty() {
+        tables.push(("Path", report.roots.iter().collect()));
+    }
+    if !report.dirs.is_empty() {
+        let mut dirs = report.dirs.iter().collect::<Vec<_>>();
+        dirs.sort_by(|a, b| key(&b.1).cmp(&key(&a.1)).then(a.0.cmp(&b.0)));
+        tables.push(("Directory", dirs));
+    }
+    tables
+}
+
+/// Render an aligned table with the files, empty, comment and code lines of each file type
+/// or language, in the order given by `sort`, followed by a SUM row. With `--by-root`, a
+/// second table breaks down the results by the paths given on the command line.
+pub fn table(report: &Report, sort: FileSort) -> String {
+    let mut out = String::new();
+    for (i, (heading, rows)) in report_tables(report, sort).iter().enumerate() {
+        if i > 0 {
+            writeln!(out).unwrap();
+        }
+        write_table(&mut out, heading, rows, &report.total);
+    }
+    out
+}
+
+fn write_table(
+    out: &mut String,
+    heading: &str,
+    rows: &[&(String, LanguageStats)],
+    total: &LanguageStats,
+) {
+    let name_width = rows
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .chain([heading.len(), 3])
+        .max()
+        .unwrap_or(0);
+    let write_row = |out: &mut String, name: &str, stats: &LanguageStats| {
+        writeln!(
+            out,
+            "{:<w$} {:>8} {:>10} {:>10} {:>10}",
+            name,
+            stats.files,
+            stats.res.empty_lines,
+            stats.res.comment_lines,
+            stats.res.lines_of_code,
+            w = name_width
+        )
+        .unwrap();
+    };
+    let rule = "-".repeat(name_width + 42);
+
+    writeln!(
+        out,
+        "{:<w$} {:>8} {:>10} {:>10} {:>10}",
+        heading,
+        "Files",
+        "Blank",
+        "Comment",
+        "Code",
+        w = name_width
+    )
+    .unwrap();
+    writeln!(out, "{}", rule).unwrap();
+    for (name, stats) in rows {
+        write_row(out, name, stats);
+    }
+    writeln!(out, "{}", rule).unwrap();
+    write_row(out, "SUM", total);
+}
+
+/// Render a comparison as an aligned table of the change in files and blank, comment and
+/// code lines for each file type or language that changed, with the net change below. When
+/// the comparison has the changes of each file, they are counted by kind at the end.
+pub fn delta_table(cmp: &Comparison, by_language: bool) -> String {
+    let heading = if by_language { "Language" } else { "Type" };
+    let rows = cmp
+        .types
+        .iter()
+        .filter(|(_, d)| d.files() != 0 || d.empty() != 0 || d.comments() != 0 || d.code() != 0)
+        .collect::<Vec<_>>();
+    let name_width = rows
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .chain([heading.len(), 3])
+        .max()
+        .unwrap_or(0);
+    let header = format!(
+        "{:<w$} {:>8} {:>10} {:>10} {:>10}",
+        heading,
+        "Files",
+        "Blank",
+        "Comment",
+        "Code",
+        w = name_width
+    );
+    let row = |name: &str, d: &Delta| {
+        format!(
+            "{:<w$} {:>+8} {:>+10} {:>+10} {:>+10}",
+            name,
+            d.files(),
+            d.empty(),
+            d.comments(),
+            d.code(),
+            w = name_width
+        )
+    };
+    let rule = "-".repeat(name_width + 42);
+
+    let mut out = String::new();
+    writeln!(out, "{}", header).unwrap();
+    writeln!(out, "{}", rule).unwrap();
+    for (name, d) in rows {
+        writeln!(out, "{}", row(name, d)).unwrap();
+    }
+    writeln!(out, "{}", rule).unwrap();
+    writeln!(out, "{}", row("SUM", &cmp.total)).unwrap();
+
+    if !cmp.files.is_empty() {
+        let count = |kind: fn(&FileChange) -> bool| cmp.files.iter().filter(|c| kind(c)).count();
+        writeln!(
+            out,
+            "\n{} files added, {} removed, {} modified, {} renamed",
+            count(|c| matches!(c, FileChange::Added(_))),
+            count(|c| matches!(c, FileChange::Removed(_))),
+            count(|c| matches!(c, FileChange::Modified { .. })),
+            count(|c| matches!(c, FileChange::Renamed { .. }))
+        )
+        .unwrap();
+    }
+    out
+}
+
+/// An output format and where to write it.
+#[derive(Clone, Debug)]
+pub struct FormatSpec {
+    pub format: Format,
+    /// File to write the output to, or `None` for stdout.
+    pub path: Option<PathBuf>,
+}
+
+impl FormatSpec {
+    /// Write a format to stdout.
+    pub fn stdout(format: Format) -> Self {
+        FormatSpec { format, path: None }
+    }
+}
+
+impl FromStr for FormatSpec {
+    type Err = String;
+
+    /// Parse `FORMAT` or `FORMAT=PATH`, where a path of `-` means stdout.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, path) = match s.split_once('=') {
+            Some((format, "-")) => (format, None),
+            Some((format, path)) => (format, Some(PathBuf::from(path))),
+            None => (s, None),
+        };
+
+        Ok(FormatSpec {
+            format: Format::from_str(format, true)?,
+            path,
+        })
+    }
+}
+
+/// Write an output to a file, or to stdout if there is no path.
+///
+/// Files are written to a temporary file next to the destination and then renamed into
+/// place, so readers never see a partially written output.
+pub fn write_output(path: Option<&Path>, out: &str) {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            let _ = io::stdout().write_all(out.as_bytes());
+            return;
+        }
+    };
+
+    let mut tmp_name = path.file_name().unwrap_or_default().to_owned();
+    tmp_name.push(format!(".tmp{}", process::id()));
+    let tmp = path.with_file_name(tmp_name);
+
+    let written = fs::write(&tmp, out).and_then(|_| fs::rename(&tmp, path));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp);
+        eprintln!("Unable to write {}: {}", path.display(), e);
+        process::exit(exit::USAGE);
+    }
+}
+
+/// Render results as sentences, either for the totals or for each file type.
+pub fn plain(total: &LanguageStats, types: Option<&[(String, LanguageStats)]>) -> String {
+    let mut out = String::new();
+
+    match types {
+        Some(types) => {
+            for (ext, stats) in types {
+                write_plain_group(&mut out, stats, &format!("\"{}\" files", ext));
+            }
+        }
+        None => {
+            let res = &total.res;
+            writeln!(out, "There are {} lines of code.", res.lines_of_code).unwrap();
+            writeln!(out, "There are {} comment lines.", res.comment_lines).unwrap();
+            writeln!(out, "There are {} empty lines.", res.empty_lines).unwrap();
+            writeln!(out, "{:.2}% of the lines are empty.", res.percent_empty()).unwrap();
+            writeln!(out, "There are {} bytes.", total.bytes).unwrap();
+        }
+    }
+
+    out
+}
+
+/// Render the results for each path given on the command line for `--by-root`, or for each
+/// directory for `--by-dir`, in the style of the plain output.
+pub fn plain_roots(roots: &[(String, LanguageStats)]) -> String {
+    let mut out = String::new();
+    for (root, stats) in roots {
+        write_plain_group(&mut out, stats, &format!("\"{}\"", root));
+    }
+    out
+}
+
+/// Write the plain output sentences for a group of files, described by `what`.
+fn write_plain_group(out: &mut String, stats: &LanguageStats, what: &str) {
+    let res = &stats.res;
+    writeln!(
+        out,
+        "There are {} lines of code in {}.",
+        res.lines_of_code, what
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "There are {} comment lines in {}.",
+        res.comment_lines, what
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "There are {} empty lines in {}.",
+        res.empty_lines, what
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "{:.2}% of the lines in {} are empty.",
+        res.percent_empty(),
+        what
+    )
+    .unwrap();
+    writeln!(out, "There are {} bytes in {}.", stats.bytes, what).unwrap();
+}
+
+/// Quote a CSV field if it contains characters that would otherwise break the row, like
+/// a comma, quote or newline in a file name.
+pub fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+/// Escape a TSV field, which cannot be quoted, by writing tabs, newlines and backslashes as
+/// `\t`, `\n` and `\\`.
+pub fn tsv_field(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Render results as CSV, or as TSV with `tab`, with one row for each file type or, if
+/// files are listed with `--by-file`, for each file with its path relative to the root. With
+/// `--by-dir`, rows are for each directory instead of each file type, with a `dir` column.
+///
+/// Rows have the same columns as the JSON output and the header row can be left out, so
+/// the output of several runs can be appended into one table. With `mimes`, file rows get
+/// a last column with the sniffed MIME type of each file.
+pub fn delimited(
+    root: &Path,
+    report: &Report,
+    files: Option<&[(&PathBuf, Results)]>,
+    mimes: Option<&[&str]>,
+    tab: bool,
+    header: bool,
+) -> String {
+    let (sep, field): (&str, fn(&str) -> String) = if tab {
+        ("\t", tsv_field)
+    } else {
+        (",", csv_field)
+    };
+    let mut out = String::new();
+    let mut write_row = |row: &[String]| writeln!(out, "{}", row.join(sep)).unwrap();
+
+    match files {
+        Some(files) => {
+            if header {
+                let mut row = ["path", "code", "comments", "empty"]
+                    .map(String::from)
+                    .to_vec();
+                if mimes.is_some() {
+                    row.push("mime".to_owned());
+                }
+                write_row(&row);
+            }
+            for (i, (f, res)) in files.iter().enumerate() {
+                let mut row = vec![
+                    field(&f.strip_prefix(root).unwrap_or(f).to_string_lossy()),
+                    res.lines_of_code.to_string(),
+                    res.comment_lines.to_string(),
+                    res.empty_lines.to_string(),
+                ];
+                if let Some(mimes) = mimes {
+                    row.push(field(mimes[i]));
+                }
+                write_row(&row);
+            }
+        }
+        None => {
+            let (key, rows) = if report.dirs.is_empty() {
+                ("type", &report.types)
+            } else {
+                ("dir", &report.dirs)
+            };
+            if header {
+                write_row(&[key, "files", "code", "comments", "empty", "bytes"].map(String::from));
+            }
+            for (name, stats) in rows {
+                let name = if report.dirs.is_empty() {
+                    report.type_id(name)
+                } else {
+                    name
+                };
+                write_row(&[
+                    field(name),
+                    stats.files.to_string(),
+                    stats.res.lines_of_code.to_string(),
+                    stats.res.comment_lines.to_string(),
+                    stats.res.empty_lines.to_string(),
+                    stats.bytes.to_string(),
+                ]);
+            }
+        }
+    }
+
+    out
+}
+
+/// A table of results to render as Markdown or HTML, with a row of cells for each file
+/// type, language, path or file and a row with the totals.
+struct Grid {
+    headings: Vec<&'static str>,
+    /// Number of leading columns that hold names rather than counts.
+    text_columns: usize,
+    rows: Vec<Vec<String>>,
+    total: Vec<String>,
+}
+
+/// Lay out the same tables as [`table`], or the files with `--by-file` like [`file_table`],
+/// as grids of cells.
+fn grids(
+    root: &Path,
+    report: &Report,
+    files: Option<&[(&PathBuf, Results)]>,
+    mimes: Option<&[&str]>,
+    sort: FileSort,
+) -> Vec<Grid> {
+    let counts = |res: &Results| {
+        [
+            res.lines_of_code,
+            res.comment_lines,
+            res.empty_lines,
+            res.total_lines(),
+        ]
+        .map(|n| n.to_string())
+    };
+
+    let files = match files {
+        Some(files) => files,
+        None => {
+            return report_tables(report, sort)
+                .into_iter()
+                .map(|(heading, rows)| {
+                    let cells = |name: &str, stats: &LanguageStats| {
+                        vec![
+                            name.to_owned(),
+                            stats.files.to_string(),
+                            stats.res.empty_lines.to_string(),
+                            stats.res.comment_lines.to_string(),
+                            stats.res.lines_of_code.to_string(),
+                        ]
+                    };
+                    Grid {
+                        headings: vec![heading, "Files", "Blank", "Comment", "Code"],
+                        text_columns: 1,
+                        rows: rows
+                            .iter()
+                            .map(|(name, stats)| cells(name, stats))
+                            .collect(),
+                        total: cells("SUM", &report.total),
+                    }
+                })
+                .collect();
+        }
+    };
+
+    let mut headings = vec!["Path"];
+    if mimes.is_some() {
+        headings.push("MIME type");
+    }
+    let text_columns = headings.len();
+    headings.extend(["Code", "Comment", "Empty", "Lines"]);
+
+    let rows = files
+        .iter()
+        .enumerate()
+        .map(|(i, (f, res))| {
+            let mut row = vec![f.strip_prefix(root).unwrap_or(f).display().to_string()];
+            row.extend(mimes.map(|m| m[i].to_owned()));
+            row.extend(counts(res));
+            row
+        })
+        .collect();
+    let mut total = vec![total_label(report.total.files)];
+    if mimes.is_some() {
+        total.push(String::new());
+    }
+    total.extend(counts(&report.total.res));
+
+    vec![Grid {
+        headings,
+        text_columns,
+        rows,
+        total,
+    }]
+}
+
+/// Escape the characters of a name that Markdown would treat as formatting or that would
+/// end a table cell, like the underscores of `__init__.py`. Control characters like
+/// newlines are shown escaped, as in the TSV output.
+fn markdown_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if "\\`*_[]<>|~#".contains(c) {
+            out.push('\\');
+        }
+        if c.is_control() {
+            out.push_str(&c.escape_default().to_string().replace('\\', "\\\\"));
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Render results as GitHub-flavored Markdown tables to paste into pull request
+/// descriptions and issues: the same tables as the table output, with counts right-aligned
+/// and the totals in bold.
+pub fn markdown(
+    root: &Path,
+    report: &Report,
+    files: Option<&[(&PathBuf, Results)]>,
+    mimes: Option<&[&str]>,
+    sort: FileSort,
+) -> String {
+    let mut out = String::new();
+
+    for (i, grid) in grids(root, report, files, mimes, sort).iter().enumerate() {
+        if i > 0 {
+            writeln!(out).unwrap();
+        }
+        let align = (0..grid.headings.len())
+            .map(|c| if c < grid.text_columns { "---" } else { "---:" })
+            .collect::<Vec<_>>();
+        writeln!(out, "| {} |", grid.headings.join(" | ")).unwrap();
+        writeln!(out, "|{}|", align.join("|")).unwrap();
+        for row in &grid.rows {
+            let cells = row.iter().map(|c| markdown_text(c)).collect::<Vec<_>>();
+            writeln!(out, "| {} |", cells.join(" | ")).unwrap();
+        }
+        let total = grid
+            .total
+            .iter()
+            .map(|c| match c.as_str() {
+                "" => String::new(),
+                c => format!("**{}**", markdown_text(c)),
+            })
+            .collect::<Vec<_>>();
+        writeln!(out, "| {} |", total.join(" | ")).unwrap();
+    }
+
+    out
+}
+
+/// Escape a string for HTML text or a quoted attribute.
+fn html_text(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+const HTML_STYLE: &str = "\
+body { font-family: system-ui, sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { padding: 0.3em 0.8em; border-bottom: 1px solid #ddd; }
+th { cursor: pointer; user-select: none; background: #f4f4f4; }
+th[aria-sort=ascending]::after { content: \" \\25b2\"; }
+th[aria-sort=descending]::after { content: \" \\25bc\"; }
+.num { text-align: right; font-variant-numeric: tabular-nums; }
+tfoot td { font-weight: bold; border-top: 2px solid #999; }
+";
+
+/// Sorts the rows of a table when a column heading is clicked, counts largest first and
+/// names alphabetically, and the other way around on a second click. The totals stay last.
+const HTML_SCRIPT: &str = "\
+document.querySelectorAll(\"th\").forEach(function (th) {
+  th.addEventListener(\"click\", function () {
+    var table = th.closest(\"table\"), body = table.tBodies[0], col = th.cellIndex;
+    var num = th.classList.contains(\"num\"), sort = th.getAttribute(\"aria-sort\");
+    var up = sort ? sort === \"descending\" : !num;
+    table.querySelectorAll(\"th\").forEach(function (h) { h.removeAttribute(\"aria-sort\"); });
+    th.setAttribute(\"aria-sort\", up ? \"ascending\" : \"descending\");
+    Array.from(body.rows).sort(function (a, b) {
+      var x = a.cells[col].textContent, y = b.cells[col].textContent;
+      var d = num ? x - y : x.localeCompare(y);
+      return up ? d : -d;
+    }).forEach(function (row) { body.appendChild(row); });
+  });
+});
+";
+
+/// Render results as a standalone HTML page, with the same tables as the table output.
+/// Clicking a column heading sorts the table by that column. The page needs no other files
+/// and loads nothing from the network.
+pub fn html(
+    root: &Path,
+    report: &Report,
+    files: Option<&[(&PathBuf, Results)]>,
+    mimes: Option<&[&str]>,
+    sort: FileSort,
+) -> String {
+    let title = if root.as_os_str().is_empty() {
+        "Line counts".to_owned()
+    } else {
+        format!("Line counts of {}", root.display())
+    };
+    let mut out = String::new();
+
+    writeln!(out, "<!DOCTYPE html>").unwrap();
+    writeln!(out, "<html lang=\"en\">").unwrap();
+    writeln!(out, "<head>").unwrap();
+    writeln!(out, "<meta charset=\"utf-8\">").unwrap();
+    writeln!(out, "<title>{}</title>", html_text(&title)).unwrap();
+    write!(out, "<style>\n{}</style>\n", HTML_STYLE).unwrap();
+    writeln!(out, "</head>").unwrap();
+    writeln!(out, "<body>").unwrap();
+    writeln!(out, "<h1>{}</h1>", html_text(&title)).unwrap();
+
+    for grid in grids(root, report, files, mimes, sort) {
+        let class = |c: usize| {
+            if c < grid.text_columns {
+                ""
+            } else {
+                " class=\"num\""
+            }
+        };
+        let row = |out: &mut String, tag: &str, cells: &[String]| {
+            let cells = cells
+                .iter()
+                .enumerate()
+                .map(|(c, cell)| format!("<{0}{1}>{2}</{0}>", tag, class(c), html_text(cell)))
+                .collect::<String>();
+            writeln!(out, "<tr>{}</tr>", cells).unwrap();
+        };
+
+        writeln!(out, "<table>").unwrap();
+        writeln!(out, "<thead>").unwrap();
+        let headings = grid
+            .headings
+            .iter()
+            .map(|h| h.to_string())
+            .collect::<Vec<_>>();
+        row(&mut out, "th", &headings);
+        writeln!(out, "</thead>").unwrap();
+        writeln!(out, "<tbody>").unwrap();
+        for cells in &grid.rows {
+            row(&mut out, "td", cells);
+        }
+        writeln!(out, "</tbody>").unwrap();
+        writeln!(out, "<tfoot>").unwrap();
+        row(&mut out, "td", &grid.total);
+        writeln!(out, "</tfoot>").unwrap();
+        writeln!(out, "</table>").unwrap();
+    }
+
+    write!(out, "<script>\n{}</script>\n", HTML_SCRIPT).unwrap();
+    writeln!(out, "</body>").unwrap();
+    writeln!(out, "</html>").unwrap();
+    out
+}
+
+/// Escape a string as a JSON string literal.
+pub fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Get the current UTC time as "YYYY-MM-DD HH:MM".
+pub fn utc_now() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    format!(
+        "{} {:02}:{:02}",
+        utc_date(secs),
+        secs % 86400 / 3600,
+        secs % 3600 / 60
+    )
+}
+
+/// Get the UTC date of a Unix timestamp as "YYYY-MM-DD".
+pub fn utc_date(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+
+    // convert days since the epoch to a civil date (Howard Hinnant's algorithm)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Format a count compactly, like `950`, `42.3k` or `1.2M`.
+fn compact_count(n: usize) -> String {
+    match n {
+        0..=999 => n.to_string(),
+        1_000..=999_999 => format!("{:.1}k", n as f64 / 1e3),
+        _ => format!("{:.1}M", n as f64 / 1e6),
+    }
+}
+
+/// Render a single line summary for editor status bars, like
+/// `42.3k code · 12% comments · Rust 68%`: the lines of code, the share of comment lines
+/// among code and comment lines, and the language or file type with the most code with its
+/// share of the code.
+pub fn statusline(report: &Report) -> String {
+    let total = &report.total.res;
+    let mut out = format!("{} code", compact_count(total.lines_of_code));
+
+    let commented = total.lines_of_code + total.comment_lines;
+    if commented > 0 {
+        write!(
+            out,
+            " · {:.0}% comments",
+            total.comment_lines as f64 * 100.0 / commented as f64
+        )
+        .unwrap();
+    }
+
+    let top = report.types.iter().max_by(|a, b| {
+        a.1.res
+            .lines_of_code
+            .cmp(&b.1.res.lines_of_code)
+            .then(b.0.cmp(&a.0))
+    });
+    if let (Some((name, stats)), true) = (top, total.lines_of_code > 0) {
+        write!(
+            out,
+            " · {} {:.0}%",
+            name,
+            stats.res.lines_of_code as f64 * 100.0 / total.lines_of_code as f64
+        )
+        .unwrap();
+    }
+
+    out.push('\n');
+    out
+}
+
+/// Render a fixed-width plain-text report with totals, a per-file-type table and the
+/// largest files, meant to be read as-is (e.g. in a cron email).
+pub fn text_report(
+    root: &Path,
+    report: &Report,
+    files: &[PathBuf],
+    per_file: &[Results],
+) -> String {
+    let mut out = String::new();
+    let title = format!("Line count report for {}", root.display());
+    let rule = "=".repeat(title.len().max(72));
+
+    writeln!(out, "{}", title).unwrap();
+    writeln!(out, "Generated {} UTC", utc_now()).unwrap();
+    writeln!(out, "{}", rule).unwrap();
+    writeln!(out).unwrap();
+
+    let total = &report.total;
+    writeln!(out, "Totals").unwrap();
+    writeln!(out, "  Files           {:>12}", total.files).unwrap();
+    writeln!(out, "  Lines of code   {:>12}", total.res.lines_of_code).unwrap();
+    writeln!(out, "  Comment lines   {:>12}", total.res.comment_lines).unwrap();
+    writeln!(out, "  Empty lines     {:>12}", total.res.empty_lines).unwrap();
+    writeln!(out, "  Empty lines (%) {:>12.2}", total.res.percent_empty()).unwrap();
+    writeln!(out, "  Bytes           {:>12}", total.bytes).unwrap();
+    writeln!(out).unwrap();
+
+    writeln!(out, "By file type").unwrap();
+    let header = format!(
+        "  {:<16} {:>8} {:>10} {:>10} {:>10} {:>8} {:>12}",
+        "Type", "Files", "Code", "Comment", "Empty", "Empty %", "Bytes"
+    );
+    writeln!(out, "{}", header).unwrap();
+    writeln!(out, "  {}", "-".repeat(header.len() - 2)).unwrap();
+    for (ext, stats) in &report.types {
+        write_type_row(&mut out, ext, stats);
+    }
+    writeln!(out, "  {}", "-".repeat(header.len() - 2)).unwrap();
+    write_type_row(&mut out, "Total", total);
+    writeln!(out).unwrap();
+
+    let mut largest = files.iter().zip(per_file).collect::<Vec<_>>();
+    largest.sort_by(|a, b| b.1.lines_of_code.cmp(&a.1.lines_of_code).then(a.0.cmp(b.0)));
+    writeln!(out, "Largest files").unwrap();
+    writeln!(out, "  {:>10}  Path", "Code").unwrap();
+    for (f, res) in largest.into_iter().take(TOP_FILES) {
+        let path = f.strip_prefix(root).unwrap_or(f);
+        writeln!(out, "  {:>10}  {}", res.lines_of_code, path.display()).unwrap();
+    }
+
+    out
+}
+
+fn write_type_row(out: &mut String, label: &str, stats: &LanguageStats) {
+    writeln!(
+        out,
+        "  {:<16} {:>8} {:>10} {:>10} {:>10} {:>8.2} {:>12}",
+        label,
+        stats.files,
+        stats.res.lines_of_code,
+        stats.res.comment_lines,
+        stats.res.empty_lines,
+        stats.res.percent_empty(),
+        stats.bytes
+    )
+    .unwrap();
+}
+
+/// Version of the JSON output schema, increased whenever existing fields change meaning
+/// or are removed.
+const JSON_SCHEMA_VERSION: u32 = 2;
+
+/// Render results as a JSON document:
+///
+/// ```text
+/// {
+///   "schema_version": 1,
+///   "root": "<scanned directory>",
+///   "total": {"files": N, "code": N, "comments": N, "empty": N, "bytes": N},
+///   "types": [{"type": "<ext or language ID>", "files": N, "code": N, ...}, ...],
+///   "files": [{"path": "<path>", "code": N, "comments": N, "empty": N}, ...],
+///   "roots": [{"root": "<path given on the command line>", "files": N, "code": N, ...}, ...],
+///   "dirs": [{"dir": "<directory relative to the root>", "files": N, "code": N, ...}, ...]
+/// }
+/// ```
+///
+/// When several paths are given on the command line, `root` is empty and paths are given
+/// as they were found from the current directory. `roots` is only present with `--by-root`,
+/// in the order the paths were given, and `dirs` only with `--by-dir`, sorted by name.
+///
+/// `files` is only present when files are listed with `--by-file`, in the requested order,
+/// with paths relative to the root. With `--with-mime`, each file also has a `"mime"`
+/// member with its sniffed MIME type.
+/// With `--by-lang`, `type` is the stable ID of the language (see the `languages`
+/// subcommand) and a `"name"` member gives its display name.
+/// Types are sorted by name and every count is an integer, so the output is stable.
+pub fn json(
+    root: &Path,
+    report: &Report,
+    files: Option<&[(&PathBuf, Results)]>,
+    mimes: Option<&[&str]>,
+) -> String {
+    let mut out = String::new();
+
+    writeln!(out, "{{").unwrap();
+    writeln!(out, "  \"schema_version\": {},", JSON_SCHEMA_VERSION).unwrap();
+    writeln!(out, "  \"root\": {},", json_string(&root.to_string_lossy())).unwrap();
+    writeln!(out, "  \"total\": {{{}}},", json_fields(&report.total)).unwrap();
+    writeln!(out, "  \"types\": [").unwrap();
+    for (i, (ext, stats)) in report.types.iter().enumerate() {
+        let sep = if i + 1 < report.types.len() { "," } else { "" };
+        let name = match report.language_of(ext) {
+            Some(lang) => format!("\"name\": {}, ", json_string(lang.name)),
+            None => String::new(),
+        };
+        writeln!(
+            out,
+            "    {{\"type\": {}, {}{}}}{}",
+            json_string(report.type_id(ext)),
+            name,
+            json_fields(stats),
+            sep
+        )
+        .unwrap();
+    }
+    match files {
+        Some(files) => {
+            writeln!(out, "  ],").unwrap();
+            writeln!(out, "  \"files\": [").unwrap();
+            for (i, (f, res)) in files.iter().enumerate() {
+                let sep = if i + 1 < files.len() { "," } else { "" };
+                let mime = match mimes {
+                    Some(mimes) => format!(", \"mime\": {}", json_string(mimes[i])),
+                    None => String::new(),
+                };
+                writeln!(
+                    out,
+                    "    {{\"path\": {}, \"code\": {}, \"comments\": {}, \"empty\": {}{}}}{}",
+                    json_string(&f.strip_prefix(root).unwrap_or(f).to_string_lossy()),
+                    res.lines_of_code,
+                    res.comment_lines,
+                    res.empty_lines,
+                    mime,
+                    sep
+                )
+                .unwrap();
+            }
+            write!(out, "  ]").unwrap();
+        }
+        None => write!(out, "  ]").unwrap(),
+    }
+    if !report.roots.is_empty() {
+        writeln!(out, ",").unwrap();
+        writeln!(out, "  \"roots\": [").unwrap();
+        for (i, (root, stats)) in report.roots.iter().enumerate() {
+            let sep = if i + 1 < report.roots.len() { "," } else { "" };
+            writeln!(
+                out,
+                "    {{\"root\": {}, {}}}{}",
+                json_string(root),
+                json_fields(stats),
+                sep
+            )
+            .unwrap();
+        }
+        write!(out, "  ]").unwrap();
+    }
+    if !report.dirs.is_empty() {
+        writeln!(out, ",").unwrap();
+        writeln!(out, "  \"dirs\": [").unwrap();
+        for (i, (dir, stats)) in report.dirs.iter().enumerate() {
+            let sep = if i + 1 < report.dirs.len() { "," } else { "" };
+            writeln!(
+                out,
+                "    {{\"dir\": {}, {}}}{}",
+                json_string(dir),
+                json_fields(stats),
+                sep
+            )
+            .unwrap();
+        }
+        write!(out, "  ]").unwrap();
+    }
+    writeln!(out).unwrap();
+    writeln!(out, "}}").unwrap();
+
+    out
+}
+
+/// Render the version and capabilities of this build as a JSON document, so tools can check
+/// compatibility before relying on specific flags:
+///
+/// ```text
+/// {
+///   "name": "rust-cloc",
+///   "version": "0.1.0",
+///   "semver": {"major": 0, "minor": 1, "patch": 0},
+///   "features": [],
+///   "formats": ["table", "plain", ...],
+///   "json_schema_version": 2,
+///   "languages": {"count": N, "hash": "<16 hex digits>", "classification_version": N}
+/// }
+/// ```
+///
+/// `features` lists the enabled cargo features. The language hash changes whenever the
+/// language table does (see `languages::table_hash`), and `classification_version` is the
+/// latest version that `--classification-version` accepts.
+pub fn version_json() -> String {
+    // there are no optional cargo features yet
+    let features: &[&str] = &[];
+    let formats = Format::value_variants()
+        .iter()
+        .filter_map(|f| f.to_possible_value())
+        .map(|v| json_string(v.get_name()))
+        .collect::<Vec<_>>();
+
+    let mut out = String::new();
+    writeln!(out, "{{").unwrap();
+    writeln!(out, "  \"name\": {},", json_string(env!("CARGO_PKG_NAME"))).unwrap();
+    writeln!(
+        out,
+        "  \"version\": {},",
+        json_string(env!("CARGO_PKG_VERSION"))
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "  \"semver\": {{\"major\": {}, \"minor\": {}, \"patch\": {}}},",
+        env!("CARGO_PKG_VERSION_MAJOR"),
+        env!("CARGO_PKG_VERSION_MINOR"),
+        env!("CARGO_PKG_VERSION_PATCH")
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "  \"features\": [{}],",
+        features
+            .iter()
+            .map(|f| json_string(f))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+    .unwrap();
+    writeln!(out, "  \"formats\": [{}],", formats.join(", ")).unwrap();
+    writeln!(out, "  \"json_schema_version\": {},", JSON_SCHEMA_VERSION).unwrap();
+    writeln!(
+        out,
+        "  \"languages\": {{\"count\": {}, \"hash\": \"{:016x}\", \"classification_version\": {}}}",
+        languages::all().count(),
+        languages::table_hash(),
+        languages::CLASSIFICATION_VERSION
+    )
+    .unwrap();
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+/// Format the counts of a group of files as JSON object members.
+fn json_fields(stats: &LanguageStats) -> String {
+    format!(
+        "\"files\": {}, \"code\": {}, \"comments\": {}, \"empty\": {}, \"bytes\": {}",
+        stats.files,
+        stats.res.lines_of_code,
+        stats.res.comment_lines,
+        stats.res.empty_lines,
+        stats.bytes
+    )
+}
+
+/// Hidden marker at the start of a pull request comment, so a bot can find and update the
+/// comment it posted before instead of adding a new one.
+pub const PR_COMMENT_START: &str = "<!-- rust-cloc:pr-comment -->";
+/// Hidden marker at the end of a pull request comment.
+pub const PR_COMMENT_END: &str = "<!-- /rust-cloc:pr-comment -->";
+
+/// Render a compact Markdown comment for a pull request with the change in totals since the
+/// `baseline` revision, the change for every file type that changed, and the largest new
+/// files, which are given with paths relative to the root.
+pub fn pr_comment(
+    baseline_rev: &str,
+    report: &Report,
+    baseline: &Report,
+    new_files: &[(&PathBuf, Results)],
+) -> String {
+    let mut out = String::new();
+
+    writeln!(out, "{}", PR_COMMENT_START).unwrap();
+    writeln!(out, "### Line counts compared to `{}`", baseline_rev).unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "| | Files | Code | Comment | Empty |").unwrap();
+    writeln!(out, "|---|---:|---:|---:|---:|").unwrap();
+    write_delta_row(
+        &mut out,
+        "**Total**",
+        Some(&report.total),
+        Some(&baseline.total),
+    );
+
+    let mut types = report
+        .types
+        .iter()
+        .chain(&baseline.types)
+        .map(|(t, _)| t.as_str())
+        .collect::<Vec<_>>();
+    types.sort_unstable();
+    types.dedup();
+    fn find<'a>(report: &'a Report, t: &str) -> Option<&'a LanguageStats> {
+        report
+            .types
+            .iter()
+            .find(|(name, _)| name == t)
+            .map(|(_, stats)| stats)
+    }
+    // unchanged file types are left out to keep the comment short
+    for t in types {
+        let (now, before) = (find(report, t), find(baseline, t));
+        let counts = |s: Option<&LanguageStats>| {
+            s.map(|s| {
+                (
+                    s.files,
+                    s.res.lines_of_code,
+                    s.res.comment_lines,
+                    s.res.empty_lines,
+                )
+            })
+        };
+        if counts(now).unwrap_or_default() != counts(before).unwrap_or_default() {
+            write_delta_row(&mut out, &format!("`{}`", t), now, before);
+        }
+    }
+
+    if !new_files.is_empty() {
+        writeln!(out).unwrap();
+        writeln!(out, "<details><summary>Largest new files</summary>").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "| Code | Path |").unwrap();
+        writeln!(out, "|---:|---|").unwrap();
+        for (f, res) in new_files.iter().take(TOP_FILES) {
+            writeln!(out, "| {} | `{}` |", res.lines_of_code, f.display()).unwrap();
+        }
+        if new_files.len() > TOP_FILES {
+            writeln!(out, "| | and {} more |", new_files.len() - TOP_FILES).unwrap();
+        }
+        writeln!(out).unwrap();
+        writeln!(out, "</details>").unwrap();
+    }
+
+    writeln!(out, "{}", PR_COMMENT_END).unwrap();
+    out
+}
+
+/// Write a Markdown table row with each count followed by its change since the baseline.
+fn write_delta_row(
+    out: &mut String,
+    label: &str,
+    now: Option<&LanguageStats>,
+    before: Option<&LanguageStats>,
+) {
+    let cell = |f: fn(&LanguageStats) -> usize| {
+        let now = now.map(f).unwrap_or(0);
+        let before = before.map(f).unwrap_or(0);
+        let delta = now as i64 - before as i64;
+        match delta {
+            0 => format!("{}", now),
+            d if d > 0 => format!("{} (+{})", now, d),
+            d => format!("{} ({})", now, d),
+        }
+    };
+
+    writeln!(
+        out,
+        "| {} | {} | {} | {} | {} |",
+        label,
+        cell(|s| s.files),
+        cell(|s| s.res.lines_of_code),
+        cell(|s| s.res.comment_lines),
+        cell(|s| s.res.empty_lines)
+    )
+    .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_cloc::json::{self, Value};
+
+    fn stats(files: usize, code: usize, comments: usize, empty: usize) -> LanguageStats {
+        LanguageStats {
+            files,
+            res: Results {
+                lines_of_code: code,
+                comment_lines: comments,
+                empty_lines: empty,
+            },
+            bytes: 100,
+        }
+    }
+
+    fn report(by_language: bool, types: Vec<(String, LanguageStats)>) -> Report {
+        let mut total = LanguageStats::default();
+        for (_, s) in &types {
+            total.add(s);
+        }
+        Report {
+            total,
+            types,
+            by_language,
+            roots: Vec::new(),
+            dirs: Vec::new(),
+            skipped: Vec::new(),
+        }
+    }
+
+    fn count(value: &Value, key: &str) -> u64 {
+        value.get(key).and_then(Value::as_u64).unwrap()
+    }
+
+    #[test]
+    fn json_has_totals_and_types() {
+        let report = report(
+            false,
+            vec![
+                ("py".to_owned(), stats(1, 2, 1, 0)),
+                ("rs".to_owned(), stats(2, 10, 3, 4)),
+            ],
+        );
+        let doc = json::parse(&json(Path::new("proj"), &report, None, None)).unwrap();
+
+        assert_eq!(count(&doc, "schema_version"), JSON_SCHEMA_VERSION as u64);
+        assert_eq!(doc.get("root").and_then(Value::as_str), Some("proj"));
+        let total = doc.get("total").unwrap();
+        assert_eq!(
+            ["files", "code", "comments", "empty", "bytes"].map(|k| count(total, k)),
+            [3, 12, 4, 4, 200]
+        );
+
+        let types = doc.get("types").and_then(Value::as_array).unwrap();
+        let ids = types
+            .iter()
+            .map(|t| t.get("type").and_then(Value::as_str).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["py", "rs"]);
+        assert_eq!(count(&types[1], "code"), 10);
+        assert!(types[0].get("name").is_none());
+        assert!(doc.get("files").is_none());
+    }
+
+    #[test]
+    fn json_languages_have_ids_and_names() {
+        let report = report(true, vec![("C++".to_owned(), stats(1, 1, 0, 0))]);
+        let doc = json::parse(&json(Path::new(""), &report, None, None)).unwrap();
+        let types = doc.get("types").and_then(Value::as_array).unwrap();
+        assert_eq!(types[0].get("type").and_then(Value::as_str), Some("cpp"));
+        assert_eq!(types[0].get("name").and_then(Value::as_str), Some("C++"));
+    }
+
+    #[test]
+    fn json_files_are_relative_and_escaped() {
+        let report = report(false, vec![("rs".to_owned(), stats(1, 1, 0, 0))]);
+        let path = PathBuf::from("proj/we\"ird\\name\n.rs");
+        let res = Results {
+            lines_of_code: 1,
+            comment_lines: 0,
+            empty_lines: 0,
+        };
+        let out = json(
+            Path::new("proj"),
+            &report,
+            Some(&[(&path, res)]),
+            Some(&["text/x-rust"]),
+        );
+        let doc = json::parse(&out).unwrap();
+
+        let files = doc.get("files").and_then(Value::as_array).unwrap();
+        assert_eq!(
+            files[0].get("path").and_then(Value::as_str),
+            Some("we\"ird\\name\n.rs")
+        );
+        assert_eq!(count(&files[0], "code"), 1);
+        assert_eq!(
+            files[0].get("mime").and_then(Value::as_str),
+            Some("text/x-rust")
+        );
+    }
+
+    #[test]
+    fn json_string_escapes_control_characters() {
+        assert_eq!(
+            json_string("a\"b\\c\n\t\u{1}é"),
+            "\"a\\\"b\\\\c\\n\\t\\u0001é\""
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::{get_ext, languages};
+
+/// Permission bits and attributes of the files of one language that matter in security
+/// reviews. Which ones are tracked depends on the platform.
+#[derive(Default)]
+struct PermStats {
+    files: usize,
+    /// Files anyone can execute.
+    #[cfg(unix)]
+    executable: usize,
+    /// Files that run as their owner.
+    #[cfg(unix)]
+    setuid: usize,
+    /// Files with the hidden attribute.
+    #[cfg(windows)]
+    hidden: usize,
+    /// Files with the system attribute.
+    #[cfg(windows)]
+    system: usize,
+}
+
+impl PermStats {
+    #[cfg(unix)]
+    fn add(&mut self, meta: &fs::Metadata) {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = meta.permissions().mode();
+        self.executable += (mode & 0o111 != 0) as usize;
+        self.setuid += (mode & 0o4000 != 0) as usize;
+    }
+
+    #[cfg(windows)]
+    fn add(&mut self, meta: &fs::Metadata) {
+        use std::os::windows::fs::MetadataExt;
+        const HIDDEN: u32 = 0x2;
+        const SYSTEM: u32 = 0x4;
+        let attributes = meta.file_attributes();
+        self.hidden += (attributes & HIDDEN != 0) as usize;
+        self.system += (attributes & SYSTEM != 0) as usize;
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn add(&mut self, _meta: &fs::Metadata) {}
+
+    #[cfg(unix)]
+    fn describe(&self) -> String {
+        format!(
+            "{} are executable and {} are setuid",
+            self.executable, self.setuid
+        )
+    }
+
+    #[cfg(windows)]
+    fn describe(&self) -> String {
+        format!(
+            "{} are hidden and {} are system files",
+            self.hidden, self.system
+        )
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn describe(&self) -> String {
+        "permissions are not tracked on this platform".to_owned()
+    }
+}
+
+/// Print how many files of each language are executable or setuid on Unix, or hidden or
+/// system files on Windows.
+pub fn print_perm_stats(files: &[PathBuf]) {
+    let mut stats: BTreeMap<String, PermStats> = BTreeMap::new();
+
+    for f in files {
+        let meta = match fs::metadata(f) {
+            Ok(meta) => meta,
+            Err(_) => continue,
+        };
+        let key = match languages::detect(f) {
+            Some(lang) => lang.name.to_owned(),
+            None => format!("\"{}\"", get_ext(f)),
+        };
+
+        let s = stats.entry(key).or_default();
+        s.files += 1;
+        s.add(&meta);
+    }
+
+    for (lang, s) in stats {
+        println!("In {} {} files, {}.", s.files, lang, s.describe());
+    }
+}
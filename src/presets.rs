@@ -0,0 +1,54 @@
+use std::path::{Component, Path, PathBuf, Prefix};
+
+/// Directories under a filesystem root that hold virtual files, devices or snapshots
+/// rather than source code. Walking them can hang (e.g. reading `/proc/kmsg`), never end
+/// (e.g. `/proc/self/root` loops), or count the same files many times over.
+#[cfg(target_os = "linux")]
+const SYSTEM_DIRS: &[&str] = &["proc", "sys", "dev", "run"];
+
+#[cfg(target_os = "macos")]
+const SYSTEM_DIRS: &[&str] = &[
+    "dev",
+    "System/Volumes",
+    "Volumes/com.apple.TimeMachine.localsnapshots",
+    "Volumes/.timemachine",
+    ".MobileBackups",
+    ".Spotlight-V100",
+];
+
+#[cfg(windows)]
+const SYSTEM_DIRS: &[&str] = &[
+    "Windows\\WinSxS",
+    "System Volume Information",
+    "$Recycle.Bin",
+];
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+const SYSTEM_DIRS: &[&str] = &["proc", "sys", "dev"];
+
+/// Get the directories to skip when scanning `root`.
+///
+/// The presets only apply when `root` is the root of a filesystem (like `/` or `C:\`),
+/// so explicitly scanning e.g. `/proc` still works.
+pub fn system_excludes(root: &Path) -> Vec<PathBuf> {
+    let is_fs_root = root
+        .canonicalize()
+        .map(|r| r.parent().is_none())
+        .unwrap_or(false);
+
+    if is_fs_root {
+        SYSTEM_DIRS.iter().map(|d| root.join(d)).collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Check whether a path is on a network share, like `\\server\share\code` on Windows.
+pub fn is_network_path(path: &Path) -> bool {
+    match path.components().next() {
+        Some(Component::Prefix(prefix)) => {
+            matches!(prefix.kind(), Prefix::UNC(..) | Prefix::VerbatimUNC(..))
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,151 @@
+use clap::ArgEnum;
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::tally::Tally;
+
+/// Time between progress events.
+const INTERVAL: Duration = Duration::from_millis(500);
+
+/// How progress is reported while scanning.
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ProgressFormat {
+    /// A status line on stderr that is updated in place
+    Bar,
+    /// One JSON object per line on stderr
+    Json,
+}
+
+/// Estimate the time left in milliseconds, assuming the remaining files take as long on
+/// average as the ones counted so far. This is unknown until the walk has finished, since
+/// the number of files left is unknown before then.
+fn eta_ms(tally: &Tally, elapsed: u128) -> Option<u128> {
+    let (discovered, counted) = (tally.discovered(), tally.counted());
+    if tally.walk_done() && counted > 0 {
+        let left = discovered.saturating_sub(counted) as u128;
+        Some(elapsed * left / counted as u128)
+    } else {
+        None
+    }
+}
+
+/// Render a progress event as a line of JSON:
+///
+/// ```text
+/// {"event": "progress", "discovered": N, "counted": N, "bytes": N, "lines": N,
+///  "elapsed_ms": N, "eta_ms": N}
+/// ```
+///
+/// `eta_ms` is `null` until the walk has finished, since the number of files left is
+/// unknown before then. The last event of a scan is `"done"` instead of `"progress"`.
+fn event(kind: &str, tally: &Tally, start: Instant) -> String {
+    let elapsed = start.elapsed().as_millis();
+
+    let eta = match eta_ms(tally, elapsed) {
+        _ if kind == "done" => "0".to_owned(),
+        Some(eta) => eta.to_string(),
+        None => "null".to_owned(),
+    };
+
+    format!(
+        "{{\"event\": \"{}\", \"discovered\": {}, \"counted\": {}, \"bytes\": {}, \"lines\": {}, \
+         \"elapsed_ms\": {}, \"eta_ms\": {}}}",
+        kind,
+        tally.discovered(),
+        tally.counted(),
+        tally.bytes(),
+        tally.lines(),
+        elapsed,
+        eta
+    )
+}
+
+/// Render the progress so far as a status line for people, like
+/// `Counted 1200/3400 files (35%), 180000 lines, 12.5 MB, 8s left`.
+fn status_line(tally: &Tally, start: Instant, done: bool) -> String {
+    let discovered = tally.discovered();
+    let counted = tally.counted();
+    let elapsed = start.elapsed().as_millis();
+
+    let files = if tally.walk_done() && discovered > 0 {
+        format!(
+            "{}/{} files ({}%)",
+            counted,
+            discovered,
+            counted * 100 / discovered
+        )
+    } else {
+        format!("{}/{}+ files", counted, discovered)
+    };
+    let eta = match eta_ms(tally, elapsed) {
+        Some(eta) if !done => format!(", {}s left", eta.div_ceil(1000)),
+        _ => String::new(),
+    };
+
+    format!(
+        "Counted {}, {} lines, {:.1} MB{}",
+        files,
+        tally.lines(),
+        tally.bytes() as f64 / 1e6,
+        eta
+    )
+}
+
+fn print_event(format: ProgressFormat, kind: &str, tally: &Tally, start: Instant) {
+    match format {
+        // clear the rest of the previous line, which may have been longer
+        ProgressFormat::Bar if kind == "done" => {
+            eprintln!("\r{}\x1b[K", status_line(tally, start, true))
+        }
+        ProgressFormat::Bar => eprint!("\r{}\x1b[K", status_line(tally, start, false)),
+        ProgressFormat::Json => eprintln!("{}", event(kind, tally, start)),
+    }
+}
+
+/// Reports the progress recorded in a tally on stderr from a background thread until it is
+/// dropped.
+pub struct Reporter {
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Reporter {
+    /// Start reporting the progress recorded in `tally` in a format.
+    pub fn start(format: ProgressFormat, tally: Arc<Tally>) -> Self {
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+        let thread_stop = Arc::clone(&stop);
+        let start = Instant::now();
+
+        let thread = thread::spawn(move || {
+            let (lock, cvar) = &*thread_stop;
+            let mut stopped = lock.lock().unwrap();
+            loop {
+                stopped = cvar.wait_timeout(stopped, INTERVAL).unwrap().0;
+                if *stopped {
+                    break;
+                }
+                print_event(format, "progress", &tally, start);
+            }
+            print_event(format, "done", &tally, start);
+        });
+
+        Reporter {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Reporter {
+    /// Stop reporting after a final `"done"` event.
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            let (lock, cvar) = &*self.stop;
+            *lock.lock().unwrap() = true;
+            cvar.notify_one();
+            let _ = thread.join();
+        }
+    }
+}
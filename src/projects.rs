@@ -0,0 +1,142 @@
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use crate::cache;
+use crate::output::{self, FileSort};
+use crate::report::{LanguageStats, Report};
+use crate::Results;
+
+/// Get the directory with a summary of every project scanned by this user.
+fn projects_dir() -> Option<PathBuf> {
+    cache::cache_dir().map(|d| d.join("projects"))
+}
+
+/// Remember the per-file-type results of scanning a project, replacing the results of the
+/// previous scan of the same directory.
+///
+/// The first line of the summary is the canonical path of the project, followed by a line
+/// "type<TAB>files<TAB>code<TAB>comments<TAB>empty<TAB>bytes" for each file type. Failing to
+/// save it never fails the scan.
+pub fn record(root: &Path, report: &Report) {
+    let dir = match projects_dir() {
+        Some(dir) => dir,
+        None => return,
+    };
+    let root = fs::canonicalize(root).unwrap_or_else(|_| root.to_owned());
+    // paths with newlines cannot be stored in the line-based format
+    let root_str = match root.to_str().filter(|r| !r.contains('\n')) {
+        Some(r) => r,
+        None => return,
+    };
+
+    let mut out = format!("{}\n", root_str);
+    for (ext, stats) in &report.types {
+        out.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\n",
+            ext,
+            stats.files,
+            stats.res.lines_of_code,
+            stats.res.comment_lines,
+            stats.res.empty_lines,
+            stats.bytes
+        ));
+    }
+
+    let mut hasher = DefaultHasher::new();
+    root.hash(&mut hasher);
+    let path = dir.join(format!("{:016x}.tsv", hasher.finish()));
+    let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+    let written = fs::create_dir_all(&dir)
+        .and_then(|_| fs::write(&tmp, out))
+        .and_then(|_| fs::rename(&tmp, &path));
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+}
+
+/// Read a project summary saved by `record`, as its root and the stats of each file type.
+fn load(path: &Path) -> Option<(String, Vec<(String, LanguageStats)>)> {
+    let src = fs::read_to_string(path).ok()?;
+    let mut lines = src.lines();
+    let root = lines.next()?.to_owned();
+
+    let mut types = Vec::new();
+    for line in lines {
+        let fields = line.split('\t').collect::<Vec<_>>();
+        let nums = fields
+            .get(1..6)?
+            .iter()
+            .map(|f| f.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        types.push((
+            fields[0].to_owned(),
+            LanguageStats {
+                files: nums[0] as usize,
+                res: Results {
+                    lines_of_code: nums[1] as usize,
+                    comment_lines: nums[2] as usize,
+                    empty_lines: nums[3] as usize,
+                },
+                bytes: nums[4],
+            },
+        ));
+    }
+
+    Some((root, types))
+}
+
+/// Merge the summaries of every project scanned by this user into one report, with the
+/// projects as its roots, sorted by most lines of code first.
+pub fn all_projects() -> Report {
+    let mut projects = projects_dir()
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|p| p.extension().map(|e| e == "tsv").unwrap_or(false))
+        .filter_map(|p| load(&p))
+        .collect::<Vec<_>>();
+    projects.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut total = LanguageStats::default();
+    let mut types: HashMap<String, LanguageStats> = HashMap::new();
+    let mut roots = Vec::new();
+    for (root, project_types) in projects {
+        let mut project = LanguageStats::default();
+        for (ext, stats) in &project_types {
+            project.add(stats);
+            types.entry(ext.clone()).or_default().add(stats);
+        }
+        total.add(&project);
+        roots.push((root, project));
+    }
+    roots.sort_by_key(|r| Reverse(r.1.res.lines_of_code));
+
+    let mut types = types.into_iter().collect::<Vec<_>>();
+    types.sort_by(|a, b| a.0.cmp(&b.0));
+
+    Report {
+        total,
+        types,
+        by_language: false,
+        roots,
+        dirs: Vec::new(),
+        skipped: Vec::new(),
+    }
+}
+
+/// Print a cross-project overview of every project scanned by this user: a table of the
+/// file types in all projects together, and a table of the projects.
+pub fn print_all_projects(sort: FileSort) {
+    let report = all_projects();
+    if report.roots.is_empty() {
+        println!("No projects have been scanned yet.");
+        return;
+    }
+    print!("{}", output::table(&report, sort));
+}
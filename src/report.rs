@@ -0,0 +1,313 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::families::Families;
+use crate::json;
+use crate::languages;
+use crate::walk::WalkOptions;
+use crate::{file_size, get_ext, Results};
+
+/// Line counts, file count and total size for a group of files, like the files in one
+/// language or file type, or all files.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LanguageStats {
+    pub files: usize,
+    pub res: Results,
+    pub bytes: u64,
+}
+
+impl LanguageStats {
+    /// Add the counts of another group of files to this one.
+    pub fn add(&mut self, other: &LanguageStats) {
+        self.files += other.files;
+        self.res = self.res + other.res;
+        self.bytes += other.bytes;
+    }
+}
+
+/// Everything that was counted, in total and for each file type, in a form that output
+/// formats can render.
+pub struct Report {
+    pub total: LanguageStats,
+    /// Stats for each file type or language family, sorted by name.
+    pub types: Vec<(String, LanguageStats)>,
+    /// Whether the types are languages, given by their display names, rather than file
+    /// extensions or families.
+    pub by_language: bool,
+    /// Stats for each path given on the command line with `--by-root`, in the order they
+    /// were given.
+    pub roots: Vec<(String, LanguageStats)>,
+    /// Stats for each directory at the depth given with `--by-dir`, sorted by name.
+    pub dirs: Vec<(String, LanguageStats)>,
+    /// Paths that could not be fully counted, with the reason why, in the order they were
+    /// found. The results are partial if there are any.
+    pub skipped: Vec<(PathBuf, String)>,
+}
+
+impl Report {
+    /// Gather per-file-type line counts and sizes into a report.
+    pub fn new(
+        files: &[PathBuf],
+        res_map: &HashMap<String, Results>,
+        bytes_map: &HashMap<String, u64>,
+    ) -> Self {
+        let mut file_counts: HashMap<String, usize> = HashMap::new();
+        for f in files {
+            *file_counts.entry(get_ext(f)).or_insert(0) += 1;
+        }
+
+        let mut types = res_map
+            .iter()
+            .map(|(ext, res)| {
+                let stats = LanguageStats {
+                    files: file_counts.get(ext).copied().unwrap_or(0),
+                    res: *res,
+                    bytes: bytes_map.get(ext).copied().unwrap_or(0),
+                };
+                (ext.clone(), stats)
+            })
+            .collect::<Vec<_>>();
+        types.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let total = LanguageStats {
+            files: files.len(),
+            res: res_map.values().fold(Results::new(), |a, b| a + *b),
+            bytes: bytes_map.values().sum(),
+        };
+
+        Report {
+            total,
+            types,
+            by_language: false,
+            roots: Vec::new(),
+            dirs: Vec::new(),
+            skipped: Vec::new(),
+        }
+    }
+
+    /// Gather per-file line counts into a report broken down by detected language rather
+    /// than by extension. Files in no known language are reported under their extension.
+    ///
+    /// Languages are detected from the real paths in `files`, found with `opts`, while
+    /// `paths` are the paths reported for them.
+    pub fn by_language(
+        files: &[PathBuf],
+        paths: &[PathBuf],
+        per_file: &[Results],
+        opts: &WalkOptions,
+    ) -> Self {
+        let mut groups: HashMap<String, LanguageStats> = HashMap::new();
+        let mut total = LanguageStats {
+            files: 0,
+            res: Results::new(),
+            bytes: 0,
+        };
+
+        for ((f, p), res) in files.iter().zip(paths).zip(per_file) {
+            let key = match languages::detect(f) {
+                Some(lang) => lang.name.to_owned(),
+                None => get_ext(p),
+            };
+            let stats = LanguageStats {
+                files: 1,
+                res: *res,
+                bytes: file_size(f, opts),
+            };
+            total.add(&stats);
+            groups
+                .entry(key)
+                .or_insert(LanguageStats {
+                    files: 0,
+                    res: Results::new(),
+                    bytes: 0,
+                })
+                .add(&stats);
+        }
+
+        let mut types = groups.into_iter().collect::<Vec<_>>();
+        types.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Report {
+            total,
+            types,
+            by_language: true,
+            roots: Vec::new(),
+            dirs: Vec::new(),
+            skipped: Vec::new(),
+        }
+    }
+
+    /// Read a report saved with `--format json`, like the results of an earlier run.
+    ///
+    /// Only the totals and the stats of each file type are read. A report of languages is
+    /// recognized by the display names it gives them, and languages are looked up by their
+    /// stable IDs so a renamed language still matches the current results.
+    pub fn from_json(src: &str) -> Result<Self, String> {
+        let doc = json::parse(src)?;
+        if doc.get("schema_version").and_then(|v| v.as_u64()).is_none() {
+            return Err("not a JSON report (no `schema_version`)".to_owned());
+        }
+
+        let stats = |v: &json::Value| -> Result<LanguageStats, String> {
+            let num = |key: &str| {
+                v.get(key)
+                    .and_then(|n| n.as_u64())
+                    .ok_or_else(|| format!("missing or invalid `{}`", key))
+            };
+            Ok(LanguageStats {
+                files: num("files")? as usize,
+                res: Results {
+                    lines_of_code: num("code")? as usize,
+                    comment_lines: num("comments")? as usize,
+                    empty_lines: num("empty")? as usize,
+                },
+                bytes: num("bytes")?,
+            })
+        };
+
+        let total = stats(doc.get("total").ok_or("missing `total`")?)?;
+        let entries = doc
+            .get("types")
+            .and_then(|t| t.as_array())
+            .ok_or("missing `types`")?;
+        let by_language = entries.iter().any(|t| t.get("name").is_some());
+        let mut types = Vec::new();
+        for t in entries {
+            let id = t
+                .get("type")
+                .and_then(|v| v.as_str())
+                .ok_or("missing `type`")?;
+            let name = match t.get("name").and_then(|v| v.as_str()) {
+                Some(name) => languages::all()
+                    .find(|l| l.id == id)
+                    .map(|l| l.name)
+                    .unwrap_or(name),
+                None => id,
+            };
+            types.push((name.to_owned(), stats(t)?));
+        }
+        types.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(Report {
+            total,
+            types,
+            by_language,
+            roots: Vec::new(),
+            dirs: Vec::new(),
+            skipped: Vec::new(),
+        })
+    }
+
+    /// Add a breakdown by the paths given on the command line. Each file, found with `opts`,
+    /// counts towards the first path that contains it.
+    pub fn with_roots(
+        mut self,
+        roots: &[PathBuf],
+        files: &[PathBuf],
+        per_file: &[Results],
+        opts: &WalkOptions,
+    ) -> Self {
+        let mut groups = roots
+            .iter()
+            .map(|_| LanguageStats {
+                files: 0,
+                res: Results::new(),
+                bytes: 0,
+            })
+            .collect::<Vec<_>>();
+
+        for (f, res) in files.iter().zip(per_file) {
+            if let Some(i) = roots.iter().position(|r| f.starts_with(r)) {
+                groups[i].add(&LanguageStats {
+                    files: 1,
+                    res: *res,
+                    bytes: file_size(f, opts),
+                });
+            }
+        }
+
+        self.roots = roots
+            .iter()
+            .map(|r| r.display().to_string())
+            .zip(groups)
+            .collect();
+        self
+    }
+
+    /// Break down the results by the directories `depth` levels below `root` that contain
+    /// each file, or the directory a file is in if it is less deep. Files directly in the
+    /// root are reported under `.`. `paths` are the paths to group `files`, found with
+    /// `opts`, by.
+    pub fn with_dirs(
+        mut self,
+        root: &Path,
+        files: &[PathBuf],
+        paths: &[PathBuf],
+        per_file: &[Results],
+        depth: usize,
+        opts: &WalkOptions,
+    ) -> Self {
+        let mut groups: BTreeMap<String, LanguageStats> = BTreeMap::new();
+
+        for ((f, p), res) in files.iter().zip(paths).zip(per_file) {
+            let rel = p.strip_prefix(root).unwrap_or(p);
+            let dir = rel
+                .parent()
+                .map(|d| d.components().take(depth).collect::<PathBuf>())
+                .filter(|d| !d.as_os_str().is_empty())
+                .map(|d| d.display().to_string())
+                .unwrap_or_else(|| ".".to_owned());
+            groups.entry(dir).or_default().add(&LanguageStats {
+                files: 1,
+                res: *res,
+                bytes: file_size(f, opts),
+            });
+        }
+
+        self.dirs = groups.into_iter().collect();
+        self
+    }
+
+    /// Get the language of a type in this report, if the report is broken down by language.
+    pub fn language_of(&self, key: &str) -> Option<&'static languages::Language> {
+        if self.by_language {
+            languages::by_display_name(key)
+        } else {
+            None
+        }
+    }
+
+    /// Get the identifier of a type for machine-readable output. Languages are identified
+    /// by stable IDs, so renaming one does not break consumers.
+    pub fn type_id<'a>(&self, key: &'a str) -> &'a str {
+        self.language_of(key).map(|l| l.id).unwrap_or(key)
+    }
+
+    /// Merge the per-file-type stats into one entry for each language family, sorted by
+    /// family name.
+    pub fn group_by_family(self, families: &Families) -> Self {
+        let mut groups: HashMap<String, LanguageStats> = HashMap::new();
+        for (ext, stats) in &self.types {
+            groups
+                .entry(families.family_of(ext).to_owned())
+                .or_insert(LanguageStats {
+                    files: 0,
+                    res: Results::new(),
+                    bytes: 0,
+                })
+                .add(stats);
+        }
+
+        let mut types = groups.into_iter().collect::<Vec<_>>();
+        types.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Report {
+            total: self.total,
+            types,
+            by_language: false,
+            roots: self.roots,
+            dirs: self.dirs,
+            skipped: self.skipped,
+        }
+    }
+}
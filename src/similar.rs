@@ -0,0 +1,170 @@
+use rayon::prelude::*;
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+
+/// Number of MinHash values computed for each file.
+const HASHES: usize = 64;
+
+/// Number of MinHash values in each band. Files that agree on every value of any band are
+/// compared, which finds most pairs that are at least about 50% similar.
+const ROWS: usize = 4;
+
+/// Files with fewer distinct lines are too small to be meaningfully similar.
+const MIN_LINES: usize = 5;
+
+/// Hash a normalized line with FNV-1a, which is the same on every platform and run.
+fn hash_line(line: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in line.bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// Mix a hash with a seed (SplitMix64), giving a different hash function for each seed.
+fn mix(hash: u64, seed: u64) -> u64 {
+    let mut z = hash ^ seed.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Get the sorted hashes of the distinct lines of a file, ignoring whitespace and empty
+/// lines, so reindented or reformatted copies still match.
+fn line_hashes(path: &PathBuf) -> Vec<u64> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return Vec::new(),
+    };
+    let mut hashes = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .map(|l| l.split_whitespace().collect::<String>())
+        .filter(|l| !l.is_empty())
+        .map(|l| hash_line(&l))
+        .collect::<Vec<_>>();
+    hashes.sort_unstable();
+    hashes.dedup();
+    hashes
+}
+
+/// Compute the MinHash signature of a set of line hashes.
+fn signature(hashes: &[u64]) -> [u64; HASHES] {
+    let mut sig = [u64::MAX; HASHES];
+    for &h in hashes {
+        for (seed, min) in sig.iter_mut().enumerate() {
+            *min = (*min).min(mix(h, seed as u64));
+        }
+    }
+    sig
+}
+
+/// Count the values two sorted sets have in common.
+fn shared(a: &[u64], b: &[u64]) -> usize {
+    let (mut i, mut j, mut n) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                n += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    n
+}
+
+/// A pair of files with many lines in common.
+pub struct SimilarPair {
+    pub a: PathBuf,
+    pub b: PathBuf,
+    /// Distinct lines found in both files.
+    pub shared: usize,
+    /// Distinct lines found in either file.
+    pub total: usize,
+}
+
+impl SimilarPair {
+    /// Jaccard similarity of the lines of the two files, as a percentage.
+    pub fn percent(&self) -> f64 {
+        (self.shared as f64) / (self.total as f64) * 100.0f64
+    }
+}
+
+/// Find pairs of files whose distinct lines are at least `min_percent`% the same, most
+/// similar first. `paths` are the paths to report for `files`.
+///
+/// Candidate pairs are found with MinHash and locality-sensitive hashing, so the tree is not
+/// compared pair by pair, and the similarity of each candidate is then computed exactly.
+pub fn near_duplicates(files: &[PathBuf], paths: &[PathBuf], min_percent: f64) -> Vec<SimilarPair> {
+    let hashes = files.par_iter().map(line_hashes).collect::<Vec<_>>();
+    let sigs = hashes.par_iter().map(|h| signature(h)).collect::<Vec<_>>();
+
+    let mut buckets: HashMap<(usize, &[u64]), Vec<usize>> = HashMap::new();
+    for (i, sig) in sigs.iter().enumerate() {
+        if hashes[i].len() < MIN_LINES {
+            continue;
+        }
+        for (band, rows) in sig.chunks(ROWS).enumerate() {
+            buckets.entry((band, rows)).or_default().push(i);
+        }
+    }
+
+    let mut candidates = HashSet::new();
+    for bucket in buckets.values() {
+        for (k, &i) in bucket.iter().enumerate() {
+            for &j in &bucket[k + 1..] {
+                candidates.insert((i, j));
+            }
+        }
+    }
+
+    let mut pairs = candidates
+        .into_iter()
+        .filter_map(|(i, j)| {
+            let shared = shared(&hashes[i], &hashes[j]);
+            let total = hashes[i].len() + hashes[j].len() - shared;
+            let (a, b) = if paths[i] <= paths[j] { (i, j) } else { (j, i) };
+            let pair = SimilarPair {
+                a: paths[a].clone(),
+                b: paths[b].clone(),
+                shared,
+                total,
+            };
+            Some(pair).filter(|p| p.percent() >= min_percent)
+        })
+        .collect::<Vec<_>>();
+    pairs.sort_by(|x, y| {
+        y.percent()
+            .total_cmp(&x.percent())
+            .then_with(|| (&x.a, &x.b).cmp(&(&y.a, &y.b)))
+    });
+    pairs
+}
+
+/// Print pairs of files whose distinct lines are at least `min_percent`% the same.
+pub fn print_near_duplicates(files: &[PathBuf], paths: &[PathBuf], min_percent: f64) {
+    let pairs = near_duplicates(files, paths, min_percent);
+    if pairs.is_empty() {
+        println!("No files are at least {}% similar.", min_percent);
+        return;
+    }
+
+    println!("Files that are at least {}% similar:", min_percent);
+    for p in pairs {
+        println!(
+            "{:>6.2}%  {}  {}  ({} of {} distinct lines shared)",
+            p.percent(),
+            p.a.display(),
+            p.b.display(),
+            p.shared,
+            p.total
+        );
+    }
+}
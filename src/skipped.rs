@@ -0,0 +1,65 @@
+use std::process;
+
+use crate::exit;
+use crate::tally::Tally;
+
+/// Number of skipped paths listed individually in the report.
+const MAX_LISTED: usize = 20;
+
+/// Print what a scan left out to stderr: which paths were skipped or only partially
+/// counted, and how many binary files and Git LFS pointers were skipped and files decoded
+/// from other encodings, if any.
+pub fn print_report(tally: &Tally, resolve_lfs: bool) {
+    let mut skipped = tally.skipped();
+    if !skipped.is_empty() {
+        skipped.sort();
+        eprintln!(
+            "{} paths were skipped or only partially counted:",
+            skipped.len()
+        );
+        for (path, reason) in skipped.iter().take(MAX_LISTED) {
+            eprintln!("  {}: {}", path.display(), reason);
+        }
+        if skipped.len() > MAX_LISTED {
+            eprintln!("  ... and {} more", skipped.len() - MAX_LISTED);
+        }
+    }
+
+    if tally.binary() > 0 {
+        eprintln!(
+            "Skipped {} binary files (use --count-binary or --binary-policy to count them).",
+            tally.binary()
+        );
+    }
+
+    if tally.transcoded() > 0 {
+        eprintln!(
+            "Decoded {} files that were not UTF-8 (UTF-16, or Latin-1 for other 8-bit encodings).",
+            tally.transcoded()
+        );
+    }
+
+    let pointers = tally.lfs_pointers();
+    if pointers > 0 && resolve_lfs {
+        eprintln!(
+            "Skipped {} Git LFS pointers whose contents are not available locally.",
+            pointers
+        );
+    } else if pointers > 0 {
+        eprintln!(
+            "Skipped {} Git LFS pointers (use --resolve-lfs to count their contents).",
+            pointers
+        );
+    }
+}
+
+/// Print the first path a strict scan could not read and exit, if it stopped.
+pub fn exit_if_stopped(tally: &Tally) {
+    if !tally.stopped() {
+        return;
+    }
+    if let Some((path, reason)) = tally.skipped().first() {
+        eprintln!("Error: {}: {}", path.display(), reason);
+    }
+    process::exit(exit::PARTIAL);
+}
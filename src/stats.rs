@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::{get_ext, Results};
+
+/// How concentrated lines of code are among the files of one file type.
+pub struct Concentration {
+    pub files: usize,
+    /// Percentage of lines of code in the largest 1% of files (at least one file).
+    pub top_1_percent: f64,
+    /// Percentage of lines of code in the largest 10% of files (at least one file).
+    pub top_10_percent: f64,
+    /// Gini coefficient of lines of code per file, from 0 (all files are the same size)
+    /// to nearly 1 (one file holds all the code).
+    pub gini: f64,
+}
+
+impl Concentration {
+    /// Compute concentration statistics from the lines of code of each file.
+    pub fn new(mut sizes: Vec<usize>) -> Self {
+        sizes.sort_unstable();
+        let n = sizes.len();
+        let total = sizes.iter().sum::<usize>();
+
+        // share of the code in the largest `percent`% of files
+        let top_share = |percent: usize| {
+            let k = (n * percent).div_ceil(100).max(1).min(n);
+            let top = sizes[n - k..].iter().sum::<usize>();
+            ratio(top, total) * 100.0f64
+        };
+
+        // with ascending sizes x_1..x_n, G = 2 * sum(i * x_i) / (n * sum(x)) - (n + 1) / n
+        let weighted = sizes
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| (i + 1) * x)
+            .sum::<usize>();
+        let gini = if n == 0 || total == 0 {
+            0.0
+        } else {
+            2.0 * ratio(weighted, n * total) - ratio(n + 1, n)
+        };
+
+        Concentration {
+            files: n,
+            top_1_percent: if n == 0 { 0.0 } else { top_share(1) },
+            top_10_percent: if n == 0 { 0.0 } else { top_share(10) },
+            gini,
+        }
+    }
+}
+
+fn ratio(a: usize, b: usize) -> f64 {
+    if b == 0 {
+        0.0
+    } else {
+        (a as f64) / (b as f64)
+    }
+}
+
+/// Print concentration statistics of lines of code for all files and for each file type.
+pub fn print_concentration(files: &[PathBuf], per_file: &[Results]) {
+    let mut by_ext: HashMap<String, Vec<usize>> = HashMap::new();
+    for (f, res) in files.iter().zip(per_file) {
+        by_ext
+            .entry(get_ext(f))
+            .or_default()
+            .push(res.lines_of_code);
+    }
+
+    let mut by_ext = by_ext.into_iter().collect::<Vec<_>>();
+    by_ext.sort();
+
+    let all = per_file.iter().map(|r| r.lines_of_code).collect();
+    print_row("files", &Concentration::new(all));
+    for (ext, sizes) in by_ext {
+        print_row(&format!("\"{}\" files", ext), &Concentration::new(sizes));
+    }
+}
+
+fn print_row(label: &str, c: &Concentration) {
+    println!(
+        "In {} {}, the largest 1% of files have {:.2}% of the code, the largest 10% have {:.2}%, \
+         and the Gini coefficient is {:.3}.",
+        c.files, label, c.top_1_percent, c.top_10_percent, c.gini
+    );
+}
@@ -0,0 +1,179 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Everything recorded while a scan runs: the paths that could not be fully counted, how
+/// many files were left out or decoded on the way, and how far the scan has come.
+///
+/// One tally is shared by all the threads of a scan. Each scan starts with a new tally, so
+/// nothing recorded by one scan shows up in the results of the next.
+#[derive(Debug, Default)]
+pub struct Tally {
+    /// How much is logged while walking: 1 logs each skipped path as it is found, and 2 also
+    /// logs each path that is ignored on purpose, like excluded, binary and symlinked files.
+    verbosity: usize,
+    /// Whether the scan stops at the first path that cannot be read.
+    strict: bool,
+    /// Whether a strict scan found a path it cannot read.
+    stopped: AtomicBool,
+    /// Files and directories that could not be fully counted, with the reason why, in the
+    /// order they were found.
+    skipped: Mutex<Vec<(PathBuf, String)>>,
+    binary: AtomicUsize,
+    lfs_pointers: AtomicUsize,
+    transcoded: AtomicUsize,
+    discovered: AtomicUsize,
+    counted: AtomicUsize,
+    bytes: AtomicU64,
+    lines: AtomicUsize,
+    walk_done: AtomicBool,
+}
+
+impl Tally {
+    /// Start a tally that records silently and never stops the scan.
+    pub fn new() -> Self {
+        Tally::default()
+    }
+
+    /// Set how much is logged to stderr while walking.
+    pub fn verbosity(mut self, level: usize) -> Self {
+        self.verbosity = level;
+        self
+    }
+
+    /// Stop the scan at the first path that cannot be read, instead of recording it and
+    /// going on.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Record that a path was skipped or only partially counted.
+    pub fn record(&self, path: &Path, reason: String) {
+        if self.verbosity >= 1 {
+            eprintln!("Skipped {}: {}", path.display(), reason);
+        }
+        self.skipped.lock().unwrap().push((path.to_owned(), reason));
+    }
+
+    /// Record that a path could not be read, like when permission is denied or it was
+    /// removed during the scan. A strict scan stops after the first one.
+    pub fn record_error(&self, path: &Path, reason: String) {
+        if self.strict {
+            self.stopped.store(true, Ordering::Relaxed);
+        }
+        self.record(path, reason);
+    }
+
+    /// Check whether a strict scan found a path it cannot read, so the rest of the tree is
+    /// not walked or counted.
+    pub fn stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    /// Log that a path was ignored on purpose, which unlike a skipped path does not make the
+    /// results partial.
+    pub fn log_ignored(&self, path: &Path, reason: &str) {
+        if self.verbosity >= 2 {
+            eprintln!("Ignored {}: {}", path.display(), reason);
+        }
+    }
+
+    /// Record that a binary file was skipped.
+    pub fn record_binary(&self, path: &Path) {
+        self.binary.fetch_add(1, Ordering::Relaxed);
+        self.log_ignored(path, "binary file");
+    }
+
+    /// Record that a Git LFS pointer file was skipped.
+    pub fn record_lfs_pointer(&self, path: &Path) {
+        self.lfs_pointers.fetch_add(1, Ordering::Relaxed);
+        self.log_ignored(path, "Git LFS pointer");
+    }
+
+    /// Record that a file was decoded from an encoding other than UTF-8.
+    pub fn record_transcoded(&self) {
+        self.transcoded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record that some files were found and will be counted.
+    pub fn record_discovered(&self, files: usize) {
+        self.discovered.fetch_add(files, Ordering::Relaxed);
+    }
+
+    /// Record that every file to count has been found.
+    pub fn record_walk_done(&self) {
+        self.walk_done.store(true, Ordering::Relaxed);
+    }
+
+    /// Record that a file of `bytes` bytes with `lines` lines was counted.
+    pub fn record_counted(&self, bytes: u64, lines: usize) {
+        self.counted.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        self.lines.fetch_add(lines, Ordering::Relaxed);
+    }
+
+    /// Forget everything recorded so far, so the tree can be scanned again from scratch.
+    pub fn reset(&self) {
+        self.stopped.store(false, Ordering::Relaxed);
+        self.skipped.lock().unwrap().clear();
+        for count in [
+            &self.binary,
+            &self.lfs_pointers,
+            &self.transcoded,
+            &self.discovered,
+            &self.counted,
+            &self.lines,
+        ] {
+            count.store(0, Ordering::Relaxed);
+        }
+        self.bytes.store(0, Ordering::Relaxed);
+        self.walk_done.store(false, Ordering::Relaxed);
+    }
+
+    /// Get the paths that were skipped or only partially counted, with the reason why, in
+    /// the order they were found.
+    pub fn skipped(&self) -> Vec<(PathBuf, String)> {
+        self.skipped.lock().unwrap().clone()
+    }
+
+    /// Get the number of binary files that were skipped.
+    pub fn binary(&self) -> usize {
+        self.binary.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of Git LFS pointer files that were skipped.
+    pub fn lfs_pointers(&self) -> usize {
+        self.lfs_pointers.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of files that were decoded from an encoding other than UTF-8.
+    pub fn transcoded(&self) -> usize {
+        self.transcoded.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of files found to count so far.
+    pub fn discovered(&self) -> usize {
+        self.discovered.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of files counted so far.
+    pub fn counted(&self) -> usize {
+        self.counted.load(Ordering::Relaxed)
+    }
+
+    /// Get the total size of the files counted so far.
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// Get the total number of lines in the files counted so far.
+    pub fn lines(&self) -> usize {
+        self.lines.load(Ordering::Relaxed)
+    }
+
+    /// Check whether every file to count has been found.
+    pub fn walk_done(&self) -> bool {
+        self.walk_done.load(Ordering::Relaxed)
+    }
+}
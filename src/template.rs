@@ -0,0 +1,114 @@
+use crate::report::{LanguageStats, Report};
+
+/// A parsed piece of a template.
+enum Node {
+    Text(String),
+    /// `{{name}}`, replaced by the value of a variable.
+    Var(String),
+    /// `{{#each types}}...{{/each}}`, rendered once per file type.
+    EachType(Vec<Node>),
+}
+
+/// A small handlebars-style template for rendering a report into arbitrary text.
+///
+/// `{{files}}`, `{{code}}`, `{{comments}}`, `{{empty}}`, `{{lines}}`, `{{percent_empty}}` and
+/// `{{bytes}}` refer to the totals, except inside a `{{#each types}}...{{/each}}` block, where
+/// they refer to the current file type and `{{ext}}` is its extension.
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    /// Parse a template, returning a message describing the problem if it is malformed.
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut rest = src;
+        let nodes = parse_nodes(&mut rest, false)?;
+        Ok(Template { nodes })
+    }
+
+    /// Render the template for a report.
+    pub fn render(&self, report: &Report) -> Result<String, String> {
+        let mut out = String::new();
+        render_nodes(&self.nodes, report, None, &mut out)?;
+        Ok(out)
+    }
+}
+
+/// Parse nodes until the end of the input, or until `{{/each}}` if inside a block.
+fn parse_nodes(rest: &mut &str, in_block: bool) -> Result<Vec<Node>, String> {
+    let mut nodes = Vec::new();
+
+    loop {
+        let start = match rest.find("{{") {
+            Some(i) => i,
+            None => {
+                if in_block {
+                    return Err("unclosed {{#each types}} block".to_owned());
+                }
+                nodes.push(Node::Text(rest.to_string()));
+                *rest = "";
+                return Ok(nodes);
+            }
+        };
+
+        nodes.push(Node::Text(rest[..start].to_owned()));
+        let end = rest[start..]
+            .find("}}")
+            .ok_or_else(|| "unclosed {{ tag".to_owned())?
+            + start;
+        let tag = rest[start + 2..end].trim().to_owned();
+        *rest = &rest[end + 2..];
+
+        match tag.as_str() {
+            "#each types" => nodes.push(Node::EachType(parse_nodes(rest, true)?)),
+            "/each" if in_block => return Ok(nodes),
+            "/each" => return Err("{{/each}} without a matching {{#each types}}".to_owned()),
+            _ if tag.starts_with('#') || tag.starts_with('/') => {
+                return Err(format!("unknown block {{{{{}}}}}", tag))
+            }
+            _ => nodes.push(Node::Var(tag)),
+        }
+    }
+}
+
+fn render_nodes(
+    nodes: &[Node],
+    report: &Report,
+    current: Option<(&str, &LanguageStats)>,
+    out: &mut String,
+) -> Result<(), String> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var(name) => {
+                let value = match current {
+                    Some((ext, _)) if name == "ext" => ext.to_owned(),
+                    Some((_, stats)) => stats_var(name, stats)?,
+                    None => stats_var(name, &report.total)?,
+                };
+                out.push_str(&value);
+            }
+            Node::EachType(body) => {
+                for (ext, stats) in &report.types {
+                    render_nodes(body, report, Some((ext, stats)), out)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Look up a variable that describes a group of files.
+fn stats_var(name: &str, stats: &LanguageStats) -> Result<String, String> {
+    Ok(match name {
+        "files" => stats.files.to_string(),
+        "code" => stats.res.lines_of_code.to_string(),
+        "comments" => stats.res.comment_lines.to_string(),
+        "empty" => stats.res.empty_lines.to_string(),
+        "lines" => stats.res.total_lines().to_string(),
+        "percent_empty" => format!("{:.2}", stats.res.percent_empty()),
+        "bytes" => stats.bytes.to_string(),
+        _ => return Err(format!("unknown template variable {{{{{}}}}}", name)),
+    })
+}
@@ -0,0 +1,44 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Instant;
+
+/// Maximum percentage of time each counting thread may spend working. 100 means unthrottled.
+static MAX_CPU_PERCENT: AtomicUsize = AtomicUsize::new(100);
+
+/// Limit the fraction of time each counting thread spends busy.
+pub fn set_max_cpu_percent(percent: usize) {
+    MAX_CPU_PERCENT.store(percent.clamp(1, 100), Ordering::Relaxed);
+}
+
+/// Run a unit of work, then sleep long enough that the thread stays under the
+/// configured CPU percentage on average.
+pub fn throttled<T>(f: impl FnOnce() -> T) -> T {
+    let percent = MAX_CPU_PERCENT.load(Ordering::Relaxed) as u32;
+    if percent >= 100 {
+        return f();
+    }
+
+    let start = Instant::now();
+    let res = f();
+    // working for `elapsed` means idling for `elapsed * (100 - p) / p` to keep a p% duty cycle
+    thread::sleep(start.elapsed() * (100 - percent) / percent);
+    res
+}
+
+/// Lower the scheduling priority of the whole process by `increment`, like `nice(1)`.
+#[cfg(unix)]
+pub fn renice(increment: i32) {
+    // getpriority can legitimately return -1, but setpriority reports errors unambiguously
+    let failed = unsafe {
+        let current = libc::getpriority(libc::PRIO_PROCESS, 0);
+        libc::setpriority(libc::PRIO_PROCESS, 0, current + increment) != 0
+    };
+    if failed {
+        eprintln!("Failed to change process priority.");
+    }
+}
+
+#[cfg(not(unix))]
+pub fn renice(_increment: i32) {
+    eprintln!("--nice is only supported on Unix, ignoring it.");
+}
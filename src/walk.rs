@@ -0,0 +1,442 @@
+use clap::ArgEnum;
+use rayon::prelude::*;
+
+use std::collections::HashMap;
+use std::fs::{self, DirEntry};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::binary::BinaryDetector;
+use crate::glob::Glob;
+use crate::lfs;
+use crate::tally::Tally;
+
+/// Settings that control which parts of a directory tree are visited, and the tally of the
+/// scan they are used for.
+#[derive(Clone, Default)]
+pub struct WalkOptions {
+    /// Directories that are never descended into.
+    pub skip_dirs: Vec<PathBuf>,
+    /// Maximum depth of directories to descend into below the root, if limited.
+    pub max_depth: Option<usize>,
+    /// Whether to skip symlinks entirely and record them in the skip report, for scans that
+    /// must never leave the tree.
+    pub skip_symlinks: bool,
+    /// Whether to follow symlinks to files and directories. Otherwise they are left out.
+    pub follow_symlinks: bool,
+    /// Which path files reached through symlinks are reported under when following them,
+    /// if not the path they were found at.
+    pub attribute_to: Option<AttributeTo>,
+    /// Files and directories matching any of these patterns are not visited.
+    pub exclude: Vec<Glob>,
+    /// If not empty, only files matching one of these patterns are counted. Directories
+    /// are still visited.
+    pub include: Vec<Glob>,
+    /// Which binary files like images and executables are skipped.
+    pub binary: BinaryDetector,
+    /// Whether to count the contents that Git LFS pointers refer to, when they are
+    /// available, instead of skipping the pointers.
+    pub resolve_lfs: bool,
+    /// Whether to leave Git LFS pointers in without reading files to recognize them.
+    pub skip_lfs_check: bool,
+    /// Where skipped files and progress are recorded. Clones of the options share it.
+    pub tally: Arc<Tally>,
+}
+
+/// Which path a file reached through a symlink is reported under.
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum AttributeTo {
+    /// The path through the symlink, like a file in a Bazel runfiles tree
+    Link,
+    /// The path of the file the symlink leads to, relative to the scanned directory if it is
+    /// inside it
+    Target,
+}
+
+/// What to do with a directory entry.
+enum Visit {
+    Skip,
+    /// Descend into a directory, with the directories above it when following symlinks.
+    Descend(PathBuf, Vec<FileId>),
+    File(PathBuf),
+}
+
+/// Identifies a file or directory no matter which path it is reached by: the device and
+/// inode numbers on Unix, and the canonical path elsewhere.
+#[cfg(unix)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FileId(u64, u64);
+
+#[cfg(not(unix))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FileId(PathBuf);
+
+/// Get the identity of the file or directory a path leads to, following symlinks.
+#[cfg(unix)]
+pub fn file_id(path: &Path) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|m| FileId(m.dev(), m.ino()))
+}
+
+#[cfg(not(unix))]
+pub fn file_id(path: &Path) -> Option<FileId> {
+    fs::canonicalize(path).ok().map(FileId)
+}
+
+/// Check whether a path goes through a symlink, either to the file itself or to one of the
+/// directories above it.
+fn through_link(path: &Path) -> bool {
+    path.ancestors()
+        .filter(|p| !p.as_os_str().is_empty())
+        .any(|p| {
+            fs::symlink_metadata(p)
+                .map(|m| m.file_type().is_symlink())
+                .unwrap_or(false)
+        })
+}
+
+/// Find which files are reached through more than one path, like a file that is found both
+/// directly and through a symlinked directory. Each file is kept once, at a path that goes
+/// through a symlink if there is one when attributing files to links and at one that does
+/// not otherwise, and then at the path that sorts first, so the result does not depend on
+/// the order directories were read in.
+pub fn first_paths(files: &[PathBuf], attribute_to: Option<AttributeTo>) -> Vec<bool> {
+    let keys = files
+        .iter()
+        .map(|f| {
+            let preferred = through_link(f) == (attribute_to == Some(AttributeTo::Link));
+            file_id(f).map(|id| (id, (!preferred, f)))
+        })
+        .collect::<Vec<_>>();
+    let mut first: HashMap<&FileId, &(bool, &PathBuf)> = HashMap::new();
+    for (id, key) in keys.iter().flatten() {
+        let best = first.entry(id).or_insert(key);
+        if key < *best {
+            *best = key;
+        }
+    }
+
+    keys.iter()
+        .map(|k| match k {
+            Some((id, key)) => first[id] == key,
+            None => true,
+        })
+        .collect()
+}
+
+/// Get the path a file found below `root` is reported under. When attributing files to
+/// their targets, a path through a symlink is replaced by the path of the file it leads to,
+/// under `root` if the file is inside it and as an absolute path otherwise.
+pub fn attributed_path(root: &Path, path: PathBuf, attribute_to: Option<AttributeTo>) -> PathBuf {
+    if attribute_to != Some(AttributeTo::Target) || !through_link(&path) {
+        return path;
+    }
+    let target = match fs::canonicalize(&path) {
+        Ok(target) => target,
+        Err(_) => return path,
+    };
+    // paths given without a single root are relative to the current directory
+    let base = if root.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        root
+    };
+    match fs::canonicalize(base)
+        .ok()
+        .and_then(|r| target.strip_prefix(r).ok().map(|rel| root.join(rel)))
+    {
+        Some(inside) => inside,
+        None => target,
+    }
+}
+
+/// Keep each file reached through more than one path once, at the path it is attributed
+/// to, together with its results if they were already counted.
+pub fn dedup_links<T>(
+    root: &Path,
+    files: Vec<PathBuf>,
+    per_file: Vec<T>,
+    attribute_to: Option<AttributeTo>,
+) -> (Vec<PathBuf>, Vec<T>) {
+    let keep = first_paths(&files, attribute_to);
+    files
+        .into_iter()
+        .zip(per_file)
+        .zip(keep)
+        .filter(|&(_, keep)| keep)
+        .map(|((f, res), _)| (attributed_path(root, f, attribute_to), res))
+        .unzip()
+}
+
+impl WalkOptions {
+    /// Get the first exclude pattern that matches a path under `root`, if any. For a
+    /// directory, patterns matching everything inside it count too.
+    pub fn excluded_by(&self, root: &Path, path: &Path, is_dir: bool) -> Option<&Glob> {
+        let rel = path.strip_prefix(root).unwrap_or(path);
+        self.exclude.iter().find(|g| {
+            if is_dir {
+                g.matches_dir(rel)
+            } else {
+                g.matches(rel)
+            }
+        })
+    }
+
+    /// Get the file or directory that excludes a file, given by its path relative to the
+    /// scan root, together with the exclude pattern that matches it.
+    pub fn excluded_ancestor<'a>(&self, rel: &'a Path) -> Option<(&'a Path, &Glob)> {
+        rel.ancestors().find_map(|p| {
+            let is_dir = p != rel;
+            self.excluded_by(Path::new(""), p, is_dir).map(|g| (p, g))
+        })
+    }
+
+    /// Check whether a file under `root` is matched by the include patterns, if there are any.
+    pub fn included(&self, root: &Path, path: &Path) -> bool {
+        let rel = path.strip_prefix(root).unwrap_or(path);
+        self.include.is_empty() || self.include.iter().any(|g| g.matches(rel))
+    }
+
+    /// Check whether a file is binary or a Git LFS pointer and should be skipped, recording
+    /// it if so.
+    pub fn skips_binary(&self, path: &Path) -> bool {
+        if !self.skip_lfs_check && lfs::skips_pointer(path, self.resolve_lfs) {
+            self.tally.record_lfs_pointer(path);
+            return true;
+        }
+        let skip = self.binary.is_binary(path);
+        if skip {
+            self.tally.record_binary(path);
+        }
+        skip
+    }
+
+    /// Get the file to read for a counted file, which is the downloaded object of an LFS
+    /// pointer when resolving them.
+    pub fn contents_path(&self, path: &Path) -> PathBuf {
+        lfs::contents_path(path, self.resolve_lfs)
+    }
+
+    /// Get the directories above the directories found in the root when walking, which are
+    /// only tracked when following symlinks.
+    fn root_ancestors(&self, root: &Path) -> Vec<FileId> {
+        if self.follow_symlinks {
+            file_id(root).into_iter().collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Decide what to do with an entry found in a directory at `depth` below `root`, given
+    /// the identities of that directory and the ones above it when following symlinks.
+    fn visit(&self, root: &Path, entry: DirEntry, depth: usize, ancestors: &[FileId]) -> Visit {
+        let curr_path = entry.path();
+
+        if self.skip_dirs.contains(&curr_path) {
+            return Visit::Skip;
+        }
+
+        if entry.file_type().map(|t| t.is_symlink()).unwrap_or(false) {
+            if self.skip_symlinks {
+                self.tally
+                    .log_ignored(&curr_path, "symlinks are not followed in a sandbox");
+                return Visit::Skip;
+            }
+            if !self.follow_symlinks {
+                self.tally
+                    .log_ignored(&curr_path, "symlink (use --follow-symlinks)");
+                return Visit::Skip;
+            }
+            // a link to nothing is neither a file nor a directory, so it is not counted
+            if fs::metadata(&curr_path).is_err() {
+                self.tally
+                    .record_error(&curr_path, "broken symlink".to_owned());
+                return Visit::Skip;
+            }
+        }
+
+        let is_dir = curr_path.is_dir();
+        if let Some(glob) = self.excluded_by(root, &curr_path, is_dir) {
+            self.tally
+                .log_ignored(&curr_path, &format!("excluded by {}", glob));
+            return Visit::Skip;
+        }
+
+        if is_dir {
+            if self.max_depth.map(|max| depth >= max).unwrap_or(false) {
+                self.tally
+                    .log_ignored(&curr_path, "deeper than the maximum depth");
+                return Visit::Skip;
+            }
+            if !self.follow_symlinks {
+                return Visit::Descend(curr_path, Vec::new());
+            }
+
+            // a symlink back to a directory above would otherwise be followed forever
+            match file_id(&curr_path) {
+                Some(id) if ancestors.contains(&id) => {
+                    self.tally.log_ignored(&curr_path, "symlink cycle");
+                    Visit::Skip
+                }
+                id => {
+                    let mut ancestors = ancestors.to_vec();
+                    ancestors.extend(id);
+                    Visit::Descend(curr_path, ancestors)
+                }
+            }
+        } else if !self.included(root, &curr_path) {
+            self.tally
+                .log_ignored(&curr_path, "not matched by --include");
+            Visit::Skip
+        } else if self.skips_binary(&curr_path) {
+            Visit::Skip
+        } else {
+            self.tally.record_discovered(1);
+            Visit::File(curr_path)
+        }
+    }
+}
+
+/// Recursively explore a directory to get a list of file paths.
+pub fn find_all_files(path: &Path, opts: &WalkOptions, files: &mut Vec<PathBuf>) {
+    find_files_at_depth(path, path, opts, 0, &opts.root_ancestors(path), files);
+}
+
+fn find_files_at_depth(
+    root: &Path,
+    path: &Path,
+    opts: &WalkOptions,
+    depth: usize,
+    ancestors: &[FileId],
+    files: &mut Vec<PathBuf>,
+) {
+    if path.is_dir() {
+        let entries = match read_dir_if_exists(path, &opts.tally) {
+            Some(entries) => entries,
+            None => return,
+        };
+
+        for entry in entries {
+            match opts.visit(root, entry, depth, ancestors) {
+                Visit::Skip => (),
+                Visit::Descend(dir, ancestors) => {
+                    find_files_at_depth(root, &dir, opts, depth + 1, &ancestors, files)
+                }
+                Visit::File(f) => files.push(f),
+            }
+        }
+    }
+}
+
+/// Recursively explore a directory in parallel, reading sibling subdirectories on
+/// separate threads. Files are returned in the same order as `find_all_files`.
+pub fn find_all_files_par(path: &Path, opts: &WalkOptions) -> Vec<PathBuf> {
+    find_files_at_depth_par(path, path, opts, 0, &opts.root_ancestors(path))
+}
+
+fn find_files_at_depth_par(
+    root: &Path,
+    path: &Path,
+    opts: &WalkOptions,
+    depth: usize,
+    ancestors: &[FileId],
+) -> Vec<PathBuf> {
+    if !path.is_dir() {
+        return Vec::new();
+    }
+
+    let entries = match read_dir_if_exists(path, &opts.tally) {
+        Some(entries) => entries,
+        None => return Vec::new(),
+    };
+
+    let entries = entries
+        .map(|entry| opts.visit(root, entry, depth, ancestors))
+        .collect::<Vec<_>>();
+
+    entries
+        .into_par_iter()
+        .flat_map_iter(|visit| match visit {
+            Visit::Skip => Vec::new(),
+            Visit::Descend(dir, ancestors) => {
+                find_files_at_depth_par(root, &dir, opts, depth + 1, &ancestors)
+            }
+            Visit::File(f) => vec![f],
+        })
+        .collect()
+}
+
+/// Recursively find all files under a path in parallel, calling `found` for each file as
+/// soon as it is found instead of collecting them, so work on the first files can start
+/// while the rest of the tree is still being walked.
+///
+/// Each directory is read by its own rayon task in the current thread pool, so files are
+/// found in no particular order.
+pub fn stream_files(path: &Path, opts: &WalkOptions, found: &(dyn Fn(PathBuf) + Sync)) {
+    if !path.is_dir() {
+        return;
+    }
+    let ancestors = opts.root_ancestors(path);
+    rayon::scope(|s| stream_files_at_depth(s, path, path.to_owned(), opts, 0, ancestors, found));
+}
+
+fn stream_files_at_depth<'s>(
+    s: &rayon::Scope<'s>,
+    root: &'s Path,
+    path: PathBuf,
+    opts: &'s WalkOptions,
+    depth: usize,
+    ancestors: Vec<FileId>,
+    found: &'s (dyn Fn(PathBuf) + Sync),
+) {
+    let entries = match read_dir_if_exists(&path, &opts.tally) {
+        Some(entries) => entries,
+        None => return,
+    };
+
+    for entry in entries {
+        match opts.visit(root, entry, depth, &ancestors) {
+            Visit::Skip => (),
+            Visit::Descend(dir, ancestors) => s.spawn(move |s| {
+                stream_files_at_depth(s, root, dir, opts, depth + 1, ancestors, found)
+            }),
+            Visit::File(f) => found(f),
+        }
+    }
+}
+
+/// Read the entries of a directory, recording it in the tally instead of failing if it
+/// cannot be read, like when it was removed after it was found or when a network share
+/// goes offline. Entries that fail partway through listing are recorded and left out.
+/// Nothing more is read once a strict scan has stopped.
+fn read_dir_if_exists<'a>(
+    path: &'a Path,
+    tally: &'a Tally,
+) -> Option<impl Iterator<Item = DirEntry> + 'a> {
+    if tally.stopped() {
+        return None;
+    }
+    let entries = match fs::read_dir(path) {
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            tally.record_error(
+                path,
+                "directory disappeared before it could be read".to_owned(),
+            );
+            return None;
+        }
+        Err(e) => {
+            tally.record_error(path, format!("unable to read directory ({})", e));
+            return None;
+        }
+        Ok(entries) => entries,
+    };
+
+    Some(entries.filter_map(move |entry| match entry {
+        Ok(entry) => Some(entry),
+        Err(e) => {
+            tally.record_error(path, format!("unable to list an entry ({})", e));
+            None
+        }
+    }))
+}
@@ -0,0 +1,335 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::output::{self, FileSort};
+use crate::report::Report;
+use crate::walk::WalkOptions;
+use crate::{add_ext_results, count_bytes_by_ext, count_lines_in_file, get_ext, Results};
+
+/// How long to wait for more changes after one is seen, so saving many files at once only
+/// redraws the report once.
+const SETTLE: Duration = Duration::from_millis(200);
+
+/// The counted files below a directory, kept up to date as files change.
+struct Tree<'a> {
+    root: &'a Path,
+    opts: &'a WalkOptions,
+    files: BTreeMap<PathBuf, Results>,
+}
+
+impl Tree<'_> {
+    /// Check whether a path is not excluded by the walk options. The root itself always is.
+    fn visits(&self, path: &Path) -> bool {
+        match path.strip_prefix(self.root) {
+            Ok(rel) if rel.as_os_str().is_empty() => true,
+            Ok(rel) => self.opts.excluded_ancestor(rel).is_none(),
+            Err(_) => false,
+        }
+    }
+
+    /// Count every file in a directory and watch it and its subdirectories for changes.
+    fn scan_dir(&mut self, dir: &Path, watcher: &mut Watcher) {
+        watcher.add(dir);
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_link = entry.file_type().map(|t| t.is_symlink()).unwrap_or(false);
+            if (is_link && !self.opts.follow_symlinks) || !self.visits(&path) {
+                continue;
+            }
+            if path.is_dir() {
+                // symlinked directories are not watched, so links back up cannot loop
+                if !is_link {
+                    self.scan_dir(&path, watcher);
+                }
+            } else {
+                self.update_file(&path);
+            }
+        }
+    }
+
+    /// Count a file again, or forget it if it is no longer counted.
+    fn update_file(&mut self, path: &Path) {
+        let rel = path.strip_prefix(self.root).unwrap_or(path);
+        if path.is_file()
+            && self.visits(path)
+            && self.opts.included(Path::new(""), rel)
+            && !self.opts.skips_binary(path)
+        {
+            self.files
+                .insert(path.to_owned(), count_lines_in_file(path, self.opts));
+        } else {
+            self.files.remove(path);
+        }
+    }
+
+    /// Bring the results up to date after a path changed.
+    fn update(&mut self, path: &Path, watcher: &mut Watcher) {
+        if path.is_dir() {
+            if self.visits(path) {
+                self.scan_dir(path, watcher);
+            }
+        } else if path.exists() {
+            self.update_file(path);
+        } else {
+            // a removed or renamed directory takes every file below it along
+            let gone = self
+                .files
+                .range(path.to_owned()..)
+                .map(|(f, _)| f)
+                .take_while(|f| f.starts_with(path))
+                .cloned()
+                .collect::<Vec<_>>();
+            for f in gone {
+                self.files.remove(&f);
+            }
+        }
+    }
+
+    fn report(&self, by_language: bool) -> Report {
+        let files = self.files.keys().cloned().collect::<Vec<_>>();
+        if by_language {
+            let per_file = self.files.values().copied().collect::<Vec<_>>();
+            Report::by_language(&files, &files, &per_file, self.opts)
+        } else {
+            let res_map = self
+                .files
+                .iter()
+                .map(|(f, res)| (get_ext(f), *res))
+                .fold(HashMap::new(), add_ext_results);
+            Report::new(
+                &files,
+                &res_map,
+                &count_bytes_by_ext(&files, &files, 1, self.opts),
+            )
+        }
+    }
+}
+
+/// Format a change in a number of lines with its sign.
+fn signed(before: usize, after: usize) -> String {
+    format!("{:+}", after as i64 - before as i64)
+}
+
+/// Count the files below `root`, then keep watching it and redraw the results in place
+/// whenever files change, together with the net change in lines since watching started.
+/// Only the files that changed are counted again. This runs until the process is stopped.
+pub fn watch(root: &Path, opts: &WalkOptions, by_language: bool, sort: FileSort) -> ! {
+    let mut watcher = Watcher::new().unwrap_or_else(|e| {
+        eprintln!("Unable to watch {}: {}", root.display(), e);
+        std::process::exit(crate::exit::USAGE);
+    });
+    let mut tree = Tree {
+        root,
+        opts,
+        files: BTreeMap::new(),
+    };
+    tree.scan_dir(root, &mut watcher);
+    let start = tree.report(by_language).total.res;
+
+    loop {
+        let report = tree.report(by_language);
+        let now = report.total.res;
+        // clear the screen and move the cursor to the top left corner before redrawing
+        print!(
+            "\x1b[2J\x1b[H{}\nNet change since watching started: {} code, {} comment, {} empty \
+             lines\n",
+            output::table(&report, sort),
+            signed(start.lines_of_code, now.lines_of_code),
+            signed(start.comment_lines, now.comment_lines),
+            signed(start.empty_lines, now.empty_lines)
+        );
+        let _ = io::stdout().flush();
+
+        match watcher.wait() {
+            Some(changed) => {
+                for path in changed {
+                    tree.update(&path, &mut watcher);
+                }
+            }
+            None => {
+                // changes were lost, so the only way to catch up is to count everything again
+                tree.files.clear();
+                tree.opts.tally.reset();
+                tree.scan_dir(root, &mut watcher);
+            }
+        }
+    }
+}
+
+/// Watches directories for changes with inotify.
+#[cfg(target_os = "linux")]
+struct Watcher {
+    fd: i32,
+    /// Watched directories by watch descriptor.
+    dirs: HashMap<i32, PathBuf>,
+}
+
+#[cfg(target_os = "linux")]
+impl Watcher {
+    const MASK: u32 = libc::IN_CLOSE_WRITE
+        | libc::IN_MODIFY
+        | libc::IN_CREATE
+        | libc::IN_DELETE
+        | libc::IN_MOVED_FROM
+        | libc::IN_MOVED_TO;
+
+    fn new() -> io::Result<Self> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Watcher {
+            fd,
+            dirs: HashMap::new(),
+        })
+    }
+
+    /// Watch a directory for files that are created, changed, removed or renamed in it.
+    fn add(&mut self, dir: &Path) {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = match std::ffi::CString::new(dir.as_os_str().as_bytes()) {
+            Ok(path) => path,
+            Err(_) => return,
+        };
+        let wd = unsafe { libc::inotify_add_watch(self.fd, path.as_ptr(), Self::MASK) };
+        if wd >= 0 {
+            self.dirs.insert(wd, dir.to_owned());
+        }
+    }
+
+    /// Wait until something changes, and then until things settle down, and get the paths
+    /// that changed. If the kernel's event queue overflowed, some changes were dropped and
+    /// there is no telling what changed, so nothing is returned.
+    fn wait(&mut self) -> Option<Vec<PathBuf>> {
+        let header = std::mem::size_of::<libc::inotify_event>();
+        let mut buf = vec![0u8; 64 * 1024];
+        let mut changed = Vec::new();
+        let mut overflowed = false;
+        let mut timeout = -1;
+
+        loop {
+            let mut pfd = libc::pollfd {
+                fd: self.fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let ready = unsafe { libc::poll(&mut pfd, 1, timeout) };
+            if ready == 0 && overflowed {
+                return None;
+            }
+            if ready == 0 && !changed.is_empty() {
+                changed.sort();
+                changed.dedup();
+                return Some(changed);
+            }
+            if ready <= 0 {
+                continue;
+            }
+
+            let n =
+                unsafe { libc::read(self.fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+            let mut offset = 0;
+            while n > 0 && offset + header <= n as usize {
+                // events are packed one after another, each followed by its file name
+                let event = unsafe {
+                    std::ptr::read_unaligned(buf[offset..].as_ptr() as *const libc::inotify_event)
+                };
+                let name = &buf[offset + header..offset + header + event.len as usize];
+                let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(name.len())];
+                offset += header + event.len as usize;
+
+                if event.mask & libc::IN_Q_OVERFLOW != 0 {
+                    overflowed = true;
+                } else if event.mask & libc::IN_IGNORED != 0 {
+                    self.dirs.remove(&event.wd);
+                } else if let Some(dir) = self.dirs.get(&event.wd) {
+                    use std::os::unix::ffi::OsStrExt;
+                    changed.push(dir.join(std::ffi::OsStr::from_bytes(name)));
+                }
+            }
+            timeout = SETTLE.as_millis() as i32;
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.fd) };
+    }
+}
+
+/// Watches directories for changes by listing them periodically, where inotify is not
+/// available.
+#[cfg(not(target_os = "linux"))]
+struct Watcher {
+    /// Modification times and sizes of the entries of each watched directory.
+    dirs: HashMap<PathBuf, HashMap<PathBuf, (Option<std::time::SystemTime>, u64)>>,
+}
+
+#[cfg(not(target_os = "linux"))]
+impl Watcher {
+    fn new() -> io::Result<Self> {
+        Ok(Watcher {
+            dirs: HashMap::new(),
+        })
+    }
+
+    fn list(dir: &Path) -> HashMap<PathBuf, (Option<std::time::SystemTime>, u64)> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return HashMap::new(),
+        };
+        entries
+            .flatten()
+            .map(|e| {
+                let meta = e.metadata().ok();
+                let stamp = (
+                    meta.as_ref().and_then(|m| m.modified().ok()),
+                    meta.map(|m| m.len()).unwrap_or(0),
+                );
+                (e.path(), stamp)
+            })
+            .collect()
+    }
+
+    /// Watch a directory for files that are created, changed, removed or renamed in it.
+    fn add(&mut self, dir: &Path) {
+        self.dirs.insert(dir.to_owned(), Self::list(dir));
+    }
+
+    /// Wait until something changes and get the paths that changed. Listing directories
+    /// never loses changes, so there always are some.
+    fn wait(&mut self) -> Option<Vec<PathBuf>> {
+        loop {
+            std::thread::sleep(SETTLE * 5);
+            let mut changed = Vec::new();
+            for (dir, before) in self.dirs.iter_mut() {
+                let after = Self::list(dir);
+                for (path, stamp) in &after {
+                    if before.get(path) != Some(stamp) {
+                        changed.push(path.clone());
+                    }
+                }
+                changed.extend(before.keys().filter(|p| !after.contains_key(*p)).cloned());
+                *before = after;
+            }
+            // directories that are gone are no longer listed
+            self.dirs.retain(|dir, _| dir.is_dir());
+
+            if !changed.is_empty() {
+                changed.sort();
+                return Some(changed);
+            }
+        }
+    }
+}
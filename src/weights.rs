@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::Results;
+
+/// A weight like `sql=0.1` that scales the lines of code in one file type when
+/// computing the weighted total. File types without a weight count fully.
+#[derive(Clone, Debug)]
+pub struct Weight {
+    ext: String,
+    factor: f64,
+}
+
+impl FromStr for Weight {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (ext, factor) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected EXT=FACTOR but got \"{}\"", s))?;
+        let factor = factor
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|f| *f >= 0.0)
+            .ok_or_else(|| format!("invalid weight in \"{}\"", s))?;
+
+        Ok(Weight {
+            ext: ext.trim().trim_start_matches('.').to_owned(),
+            factor,
+        })
+    }
+}
+
+/// Compute the total lines of code with each file type scaled by its weight.
+///
+/// File types are summed in sorted order so the floating-point result does not depend on
+/// the iteration order of the map.
+pub fn weighted_code(weights: &[Weight], res_map: &HashMap<String, Results>) -> f64 {
+    let mut types = res_map.iter().collect::<Vec<_>>();
+    types.sort_by(|a, b| a.0.cmp(b.0));
+
+    types
+        .into_iter()
+        .map(|(ext, res)| {
+            // the last weight given for a file type wins
+            let factor = weights
+                .iter()
+                .rev()
+                .find(|w| &w.ext == ext)
+                .map(|w| w.factor)
+                .unwrap_or(1.0);
+            res.lines_of_code as f64 * factor
+        })
+        .sum()
+}
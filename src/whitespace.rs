@@ -0,0 +1,179 @@
+use rayon::prelude::*;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// How to decide whether a line counts as empty.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlankRule {
+    /// A line is empty if it only contains whitespace.
+    Trim,
+    /// Like `Trim`, but a line starting with a tab is never empty. In Makefiles such a line
+    /// is an (empty) recipe line rather than a blank separator.
+    TabIsCode,
+}
+
+impl BlankRule {
+    /// Pick the rule for a file based on its name.
+    pub fn for_path(path: &Path) -> Self {
+        if is_makefile(path) {
+            BlankRule::TabIsCode
+        } else {
+            BlankRule::Trim
+        }
+    }
+
+    /// Check whether a line is empty under this rule.
+    pub fn is_empty(self, line: &str) -> bool {
+        match self {
+            BlankRule::Trim => line.trim().is_empty(),
+            BlankRule::TabIsCode => !line.starts_with('\t') && line.trim().is_empty(),
+        }
+    }
+}
+
+fn is_makefile(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    matches!(name, "Makefile" | "makefile" | "GNUmakefile") || name.ends_with(".mk")
+}
+
+/// Get a name for the whitespace-significant file type of a path, if it is one.
+fn indent_type(path: &Path) -> Option<&'static str> {
+    if is_makefile(path) {
+        return Some("Makefile");
+    }
+
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("py") | Some("pyw") => Some("Python"),
+        Some("yaml") | Some("yml") => Some("YAML"),
+        Some("coffee") => Some("CoffeeScript"),
+        Some("haml") => Some("Haml"),
+        Some("nim") => Some("Nim"),
+        _ => None,
+    }
+}
+
+/// Indentation of non-empty lines in whitespace-significant files.
+#[derive(Default)]
+struct IndentStats {
+    files: usize,
+    lines: usize,
+    total_width: usize,
+    max_width: usize,
+}
+
+/// Default number of columns between tab stops.
+pub const DEFAULT_TAB_WIDTH: usize = 8;
+
+/// Width of the leading whitespace of a line, with tabs advancing to the next multiple of
+/// `tab_width`.
+fn indent_width(line: &str, tab_width: usize) -> usize {
+    let mut width = 0;
+    for c in line.chars() {
+        match c {
+            ' ' => width += 1,
+            '\t' => width += tab_width - width % tab_width,
+            _ => break,
+        }
+    }
+    width
+}
+
+/// Width of a line in columns as an editor shows it, with tabs advancing to the next
+/// multiple of `tab_width` and every other character taking one column.
+pub fn display_width(line: &str, tab_width: usize) -> usize {
+    line.chars().fold(0, |width, c| match c {
+        '\t' => width + tab_width - width % tab_width,
+        _ => width + 1,
+    })
+}
+
+/// Find lines wider than `max` columns, returning a message with the widest line of each
+/// file that has one. `paths` are the paths to report for `files`.
+pub fn check_line_widths(
+    files: &[PathBuf],
+    paths: &[PathBuf],
+    max: usize,
+    tab_width: usize,
+) -> Vec<String> {
+    let mut violations = files
+        .par_iter()
+        .zip(paths)
+        .filter_map(|(f, p)| {
+            let file = File::open(f).ok()?;
+            let (line, width) = BufReader::new(file)
+                .lines()
+                .map_while(Result::ok)
+                .map(|l| display_width(&l, tab_width))
+                .enumerate()
+                // the first of the widest lines
+                .fold(
+                    None,
+                    |widest: Option<(usize, usize)>, (i, w)| match widest {
+                        Some((_, max_w)) if max_w >= w => widest,
+                        _ => Some((i + 1, w)),
+                    },
+                )?;
+            Some((p.clone(), line, width)).filter(|_| width > max)
+        })
+        .collect::<Vec<_>>();
+    violations.sort();
+
+    violations
+        .into_iter()
+        .map(|(path, line, width)| {
+            format!(
+                "Limit exceeded: --max-line-width {} ({}:{} is {} columns wide)",
+                max,
+                path.display(),
+                line,
+                width
+            )
+        })
+        .collect()
+}
+
+/// Print the average and maximum indentation width of code lines for each
+/// whitespace-significant file type, like Python, YAML and Makefiles, with tabs advancing to
+/// the next multiple of `tab_width`.
+pub fn print_indent_stats(files: &[PathBuf], tab_width: usize) {
+    let mut stats: BTreeMap<&str, IndentStats> = BTreeMap::new();
+
+    for f in files {
+        let kind = match indent_type(f) {
+            Some(k) => k,
+            None => continue,
+        };
+        let file = match File::open(f) {
+            Ok(file) => file,
+            Err(_) => continue,
+        };
+
+        let s = stats.entry(kind).or_default();
+        s.files += 1;
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let width = indent_width(&line, tab_width);
+            s.lines += 1;
+            s.total_width += width;
+            s.max_width = s.max_width.max(width);
+        }
+    }
+
+    for (kind, s) in stats {
+        let mean = if s.lines == 0 {
+            0.0
+        } else {
+            (s.total_width as f64) / (s.lines as f64)
+        };
+        println!(
+            "In {} {} files, code lines are indented {:.2} columns on average and at most {} \
+             columns.",
+            s.files, kind, mean, s.max_width
+        );
+    }
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use std::fs;
+
+use common::{cloc, stdout, temp_dir};
+
+#[test]
+fn aggregates_by_directory_depth() {
+    let root = temp_dir("by-dir");
+    fs::create_dir_all(root.join("src/backend/db")).unwrap();
+    fs::create_dir_all(root.join("src/frontend")).unwrap();
+    fs::write(root.join("src/backend/db/query.rs"), "fn q() {}\n\n").unwrap();
+    fs::write(root.join("src/backend/api.rs"), "fn a() {}\n").unwrap();
+    fs::write(root.join("src/frontend/app.ts"), "let x = 1;\nlet y = 2;\n").unwrap();
+    fs::write(root.join("build.rs"), "fn main() {}\n").unwrap();
+
+    let by_dir = |args: &[&str]| {
+        stdout(
+            cloc()
+                .args(["--by-dir", "--format", "csv"])
+                .arg(&root)
+                .args(args),
+        )
+    };
+
+    assert_eq!(
+        by_dir(&[]),
+        "dir,files,code,comments,empty,bytes\n.,1,1,0,0,13\nsrc,3,4,0,1,43\n"
+    );
+    assert_eq!(
+        by_dir(&["--depth", "2"]),
+        "dir,files,code,comments,empty,bytes\n.,1,1,0,0,13\nsrc/backend,2,2,0,1,21\n\
+         src/frontend,1,2,0,0,22\n"
+    );
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use common::{cloc, stdout, temp_dir, TempDir};
+
+/// Create a directory with one file.
+fn make_tree(test: &str) -> TempDir {
+    let root = temp_dir(&format!("cache-{}", test));
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/a.rs"), "fn a() {}\n").unwrap();
+    root
+}
+
+/// Count the `src` directory of a tree with a cache and return the SUM row of the table.
+fn sum(root: &Path) -> String {
+    sum_with(root, &[])
+}
+
+fn sum_with(root: &Path, args: &[&str]) -> String {
+    let out = stdout(
+        cloc()
+            .arg("--cache")
+            .arg(root.join("cache.tsv"))
+            .arg(root.join("src"))
+            .args(args),
+    );
+    out.lines()
+        .find(|l| l.starts_with("SUM"))
+        .unwrap()
+        .to_owned()
+}
+
+#[test]
+fn reuses_unchanged_files() {
+    let root = make_tree("reuse");
+    let file = root.join("src/a.rs");
+    assert!(sum(&root).ends_with("1          0          0          1"));
+
+    // a cached entry for the file as it is now is trusted without reading the file
+    let meta = fs::metadata(&file).unwrap();
+    let mtime = meta
+        .modified()
+        .unwrap()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let cache = fs::read_to_string(root.join("cache.tsv")).unwrap();
+    let (header, entry) = cache.trim_end().split_once('\n').unwrap();
+    let fields = entry.split('\t').collect::<Vec<_>>();
+    assert_eq!(fields[0], meta.len().to_string());
+    assert_eq!(fields[1], mtime.to_string());
+    fs::write(
+        root.join("cache.tsv"),
+        format!(
+            "{}\n{}\t{}\t{}\t42\t0\t0\t{}\n",
+            header, fields[0], fields[1], fields[2], fields[6]
+        ),
+    )
+    .unwrap();
+    assert!(sum(&root).ends_with("1          0          0         42"));
+}
+
+#[test]
+fn recounts_changed_files() {
+    let root = make_tree("changed");
+    assert!(sum(&root).ends_with("1          0          0          1"));
+
+    fs::write(root.join("src/a.rs"), "fn a() {}\n// b\n\n").unwrap();
+    fs::write(root.join("src/b.rs"), "fn b() {}\n").unwrap();
+    assert!(sum(&root).ends_with("2          1          1          2"));
+
+    fs::remove_file(root.join("src/b.rs")).unwrap();
+    assert!(sum(&root).ends_with("1          1          1          1"));
+    assert_eq!(
+        fs::read_to_string(root.join("cache.tsv"))
+            .unwrap()
+            .lines()
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn changed_settings_drop_the_cache() {
+    let root = make_tree("settings");
+    fs::write(
+        root.join("src/a.rs"),
+        "fn a() {}
+# b
+",
+    )
+    .unwrap();
+    fs::write(
+        root.join("languages.toml"),
+        "[rust]
+line_comments = [\"#\"]\n",
+    )
+    .unwrap();
+    let languages = root.join("languages.toml");
+    let languages = languages.to_str().unwrap();
+
+    assert!(sum(&root).ends_with("1          0          0          2"));
+    assert!(sum_with(&root, &["--languages", languages])
+        .ends_with("1          0          1          1"));
+    assert!(sum(&root).ends_with("1          0          0          2"));
+
+    // binary detection is part of the settings too
+    let cache = fs::read_to_string(root.join("cache.tsv")).unwrap();
+    sum_with(&root, &["--binary-policy", "extension"]);
+    assert_ne!(fs::read_to_string(root.join("cache.tsv")).unwrap(), cache);
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use std::fs;
+
+use common::{cloc, stdout, temp_dir};
+
+#[test]
+fn comments_are_not_counted_as_code() {
+    let root = temp_dir("cfg-stats");
+    fs::write(
+        root.join("a.c"),
+        "/* license\n * text\n */\nint x;\n\
+         #ifdef FOO\n// only with FOO\nint y;\n#endif\n\
+         #if 0\nint z;\n#endif\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("b.rs"),
+        "//! Crate docs.\nfn a() {}\n\n\
+         #[cfg(test)]\n/// Tests.\nmod tests {\n    // nothing yet\n}\n",
+    )
+    .unwrap();
+
+    let out = stdout(cloc().arg("--cfg-stats").arg(&root));
+    assert!(
+        out.contains(
+            "In 1 C/C++ files, 3 lines of code are unconditional, 2 are conditionally \
+             compiled and 2 are disabled."
+        ),
+        "{}",
+        out
+    );
+    assert!(
+        out.contains(
+            "In 1 Rust files, 1 lines of code are unconditional, 3 are conditionally \
+             compiled and 0 are disabled."
+        ),
+        "{}",
+        out
+    );
+}
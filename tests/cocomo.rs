@@ -0,0 +1,23 @@
+mod common;
+
+use std::fs;
+
+use common::{cloc, stdout, temp_dir};
+
+#[test]
+fn estimates_effort_for_one_kloc() {
+    let root = temp_dir("cocomo");
+    fs::write(root.join("a.c"), "x;\n".repeat(1000)).unwrap();
+
+    let out = stdout(
+        cloc()
+            .args(["--cocomo", "--cocomo-rate", "120000"])
+            .arg(&root),
+    );
+
+    // with 1 KLOC, organic effort is exactly a = 2.4 person-months and the schedule is
+    // 2.5 * 2.4^0.38 months
+    assert!(out.contains("Effort:   2.40 person-months"), "{}", out);
+    assert!(out.contains("Schedule: 3.49 months"), "{}", out);
+    assert!(out.contains("Cost:     57600 "), "{}", out);
+}
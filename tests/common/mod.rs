@@ -0,0 +1,77 @@
+//! Helpers shared by the integration tests.
+
+// each test file is its own crate and uses only some of the helpers
+#![allow(dead_code)]
+
+use std::ffi::OsStr;
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// A directory for a test that is removed with everything in it when dropped.
+pub struct TempDir(PathBuf);
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<OsStr> for TempDir {
+    fn as_ref(&self) -> &OsStr {
+        self.0.as_os_str()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Create an empty directory for a test. Directories are named after the test and this
+/// process, so tests can run in parallel without seeing each other's files.
+pub fn temp_dir(test: &str) -> TempDir {
+    let dir = std::env::temp_dir().join(format!("rust-cloc-{}-{}", test, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    TempDir(dir)
+}
+
+/// Get a command that runs the binary with the configuration files it would normally read.
+pub fn cloc_with_config() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_rust-cloc"))
+}
+
+/// Get a command that runs the binary without configuration files, so the tests do not
+/// depend on the configuration of whoever runs them.
+pub fn cloc() -> Command {
+    let mut cmd = cloc_with_config();
+    cmd.arg("--no-config");
+    cmd
+}
+
+/// Run a command to completion.
+pub fn run(cmd: &mut Command) -> Output {
+    cmd.output().expect("Failed to run rust-cloc")
+}
+
+/// Run a command that is expected to succeed and get its standard output.
+pub fn stdout(cmd: &mut Command) -> String {
+    let output = run(cmd);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::temp_dir;
+use rust_cloc::{Comparison, Counter, FileChange};
+
+#[test]
+fn comparison_finds_changed_and_renamed_files() {
+    let base = temp_dir("compare");
+    let (old, new) = (base.join("old"), base.join("new"));
+    for dir in [&old, &new] {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("same.rs"), "fn same() {}\n").unwrap();
+    }
+    fs::write(old.join("edited.rs"), "fn f() {}\n").unwrap();
+    fs::write(new.join("edited.rs"), "// f\nfn f() {}\n\n").unwrap();
+    fs::write(old.join("before.py"), "x = 1\ny = 2\n").unwrap();
+    fs::write(new.join("after.py"), "x = 1\ny = 2\n").unwrap();
+    fs::write(old.join("gone.c"), "int x;\n").unwrap();
+    fs::write(new.join("added.rs"), "fn g() {}\nfn h() {}\n").unwrap();
+
+    let counter = Counter::new().threads(2);
+    let cmp = Comparison::between(&counter.scan(&old), &counter.scan(&new));
+
+    assert_eq!(cmp.total.files(), 0);
+    assert_eq!(cmp.total.code(), 1);
+    assert_eq!(cmp.total.comments(), 1);
+    assert_eq!(cmp.total.empty(), 1);
+
+    let types = cmp
+        .types
+        .iter()
+        .map(|(name, d)| (name.as_str(), d.files(), d.code()))
+        .collect::<Vec<_>>();
+    assert_eq!(types, [("c", -1, -1), ("py", 0, 0), ("rs", 1, 2)]);
+
+    let files = cmp
+        .files
+        .iter()
+        .map(|c| match c {
+            FileChange::Added(f) => format!("added {}", f.path.display()),
+            FileChange::Removed(f) => format!("removed {}", f.path.display()),
+            FileChange::Modified { before, after } => format!(
+                "modified {} ({} -> {} comments)",
+                after.path.display(),
+                before.res.comment_lines,
+                after.res.comment_lines
+            ),
+            FileChange::Renamed { before, after } => format!(
+                "renamed {} -> {}",
+                before.path.display(),
+                after.path.display()
+            ),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        files,
+        [
+            "added added.rs",
+            "renamed before.py -> after.py",
+            "modified edited.rs (0 -> 1 comments)",
+            "removed gone.c",
+        ]
+    );
+    assert_eq!(cmp.files[1].path(), Path::new("after.py"));
+}
+
+#[test]
+fn empty_files_are_not_renames() {
+    let base = temp_dir("compare-empty");
+    let (old, new) = (base.join("old"), base.join("new"));
+    for dir in [&old, &new] {
+        fs::create_dir_all(dir).unwrap();
+    }
+    fs::write(old.join("a.rs"), "").unwrap();
+    fs::write(new.join("b.rs"), "").unwrap();
+
+    let counter = Counter::new();
+    let cmp = Comparison::between(&counter.scan(&old), &counter.scan(&new));
+    assert!(matches!(cmp.files[0], FileChange::Removed(_)));
+    assert!(matches!(cmp.files[1], FileChange::Added(_)));
+}
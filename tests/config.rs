@@ -0,0 +1,143 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+use common::{cloc_with_config, TempDir};
+
+/// Create a project with a `.cloc.toml` and a global configuration directory.
+fn make_tree(test: &str, project: &str, global: &str) -> TempDir {
+    let base = common::temp_dir(&format!("config-{}", test));
+    fs::create_dir_all(base.join("project/vendor")).unwrap();
+    fs::create_dir_all(base.join("xdg/rust-cloc")).unwrap();
+
+    fs::write(base.join("project/main.rs"), "fn main() {}\n\n").unwrap();
+    fs::write(base.join("project/vendor/lib.rs"), "fn f() {}\n").unwrap();
+    fs::write(base.join("project/.cloc.toml"), project).unwrap();
+    fs::write(base.join("xdg/rust-cloc/config.toml"), global).unwrap();
+
+    base
+}
+
+/// Run the binary on the project with the global configuration of a tree.
+fn run(base: &Path, args: &[&str]) -> Output {
+    common::run(
+        cloc_with_config()
+            .env("XDG_CONFIG_HOME", base.join("xdg"))
+            .arg(base.join("project"))
+            .args(args),
+    )
+}
+
+fn stdout(output: Output) -> String {
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn project_and_global_settings_apply() {
+    let base = make_tree(
+        "apply",
+        "# skip third party code\nexclude = [\n  \"vendor/**\",\n  \"*.toml\", # ourselves\n]\n",
+        "output = \"csv\"\nthreads = 2\n",
+    );
+    let out = stdout(run(&base, &[]));
+    assert!(out.starts_with("type,files,"), "{}", out);
+    assert!(out.contains("\nrs,1,"), "{}", out);
+}
+
+#[test]
+fn cli_flags_override_config() {
+    let base = make_tree("override", "format = \"json\"\n", "format = \"csv\"\n");
+    assert!(stdout(run(&base, &[])).contains("\"schema_version\""));
+    assert!(stdout(run(&base, &["--format", "tsv"])).starts_with("type\tfiles\t"));
+}
+
+#[test]
+fn no_config_ignores_files() {
+    let base = make_tree("no-config", "format = \"json\"\n", "");
+    let out = stdout(run(&base, &["--no-config"]));
+    assert!(out.starts_with("Type "), "{}", out);
+}
+
+#[test]
+fn explicit_config_replaces_others() {
+    let base = make_tree("explicit", "format = \"json\"\n", "");
+    let path = base.join("other.toml");
+    fs::write(&path, "format = \"csv\"\nexclude = [\"*.toml\"]\n").unwrap();
+    let out = stdout(run(&base, &["--config", path.to_str().unwrap()]));
+    assert!(out.starts_with("type,files,"), "{}", out);
+}
+
+#[test]
+fn invalid_config_is_an_error() {
+    let base = make_tree("invalid", "no_such_flag = 1\n", "");
+    let output = run(&base, &[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no_such_flag"));
+}
+
+#[test]
+fn eol_rules_from_config() {
+    let base = make_tree("eol", "eol_rule = [\"rs=crlf\"]\n", "");
+    let output = run(&base, &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("main.rs has 2 LF line endings"));
+}
+
+#[test]
+fn project_config_cannot_run_programs_or_write_files() {
+    for project in [
+        "query_tool = \"./evil.sh\"\nbazel_query = \"x\"\n",
+        "format = \"json=/tmp/rust-cloc-evil.json\"\n",
+        "output = [\"csv\", \"json=out.json\"]\n",
+        "cache = \"cache.tsv\"\n",
+        "notify_webhook = \"http://localhost/\"\n",
+        "threads = 64\n",
+        "count_threads = 64\n",
+    ] {
+        let base = make_tree("untrusted", project, "");
+        let output = run(&base, &[]);
+        assert_eq!(output.status.code(), Some(2), "{}", project);
+        assert!(
+            String::from_utf8_lossy(&output.stderr).contains("cannot be set in"),
+            "{}",
+            project
+        );
+    }
+
+    // the same settings are fine in the global file
+    let base = make_tree("trusted", "", "format = \"csv=-\"\n");
+    assert!(stdout(run(&base, &[])).starts_with("type,files,"));
+}
+
+#[test]
+fn sandbox_ignores_project_config() {
+    let base = make_tree(
+        "sandbox",
+        "query_tool = \"./evil.sh\"\nformat = \"json\"\n",
+        "",
+    );
+    let out = stdout(run(&base, &["--sandbox"]));
+    assert!(out.starts_with("Type "), "{}", out);
+}
+
+#[test]
+fn languages_from_config() {
+    let base = make_tree(
+        "languages",
+        "by_lang = true\n\n\
+         [languages.my_dsl]\nname = \"My DSL\"\nextensions = [\"dsl\"]\nline_comments = [\";;\"]\n",
+        "",
+    );
+    fs::write(base.join("project/a.dsl"), ";; comment\nrule x\n").unwrap();
+    let out = stdout(run(&base, &["--format", "csv"]));
+    assert!(out.contains("\nmy_dsl,1,1,1,0,"), "{}", out);
+
+    // other tables are not settings
+    let base = make_tree("tables", "[output]\nformat = \"csv\"\n", "");
+    let output = run(&base, &[]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("only `[languages.ID]` tables"));
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::{cloc, stdout, temp_dir, TempDir};
+
+/// Create a small tree with several file types and nested directories.
+fn make_tree() -> TempDir {
+    let root = temp_dir("determinism");
+
+    for (i, dir) in ["a", "a/b", "a/b/c", "d", "d/e"].iter().enumerate() {
+        fs::create_dir_all(root.join(dir)).unwrap();
+        for (j, ext) in ["rs", "py", "c", "h", "md", "txt"].iter().enumerate() {
+            let lines = "x = 1;\n\n".repeat(i * 7 + j * 3 + 1);
+            fs::write(root.join(dir).join(format!("f{}.{}", j, ext)), lines).unwrap();
+        }
+        fs::write(root.join(dir).join("Makefile"), "all:\n\techo hi\n").unwrap();
+    }
+
+    root
+}
+
+/// Run the binary on a tree and return its stdout.
+fn run(root: &Path, args: &[&str]) -> String {
+    stdout(cloc().arg(root).args(args))
+}
+
+#[test]
+fn output_is_identical_across_thread_counts() {
+    let root = make_tree();
+    let modes: &[&[&str]] = &[
+        &[],
+        &["-A"],
+        &["--files-only", "-A"],
+        &["--concentration"],
+        &["--top-dirs", "5"],
+        &["--colocation"],
+        &["--output", "json"],
+        &["--by-file"],
+        &["--output", "csv", "--by-file"],
+        &["--format", "statusline"],
+    ];
+
+    for mode in modes {
+        let expected = run(&root, &[&["-j", "1"], *mode].concat());
+        for threads in ["2", "8", "32"] {
+            assert_eq!(
+                run(&root, &[&["-j", threads], *mode].concat()),
+                expected,
+                "-j {} differs from -j 1 with {:?}",
+                threads,
+                mode
+            );
+            assert_eq!(
+                run(
+                    &root,
+                    &[&["--walk-threads", threads, "--count-threads", "1"], *mode].concat()
+                ),
+                expected,
+                "--walk-threads {} differs from -j 1 with {:?}",
+                threads,
+                mode
+            );
+        }
+    }
+
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use std::fs;
+use std::process::Output;
+
+use common::{cloc, TempDir};
+
+fn make_dir(test: &str) -> TempDir {
+    common::temp_dir(&format!("diff-{}", test))
+}
+
+fn run(args: &[&str]) -> Output {
+    common::run(cloc().args(args))
+}
+
+/// Get the rows of a table, without the header and rules.
+fn rows(stdout: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(stdout)
+        .lines()
+        .filter(|l| !l.starts_with("Type") && !l.starts_with('-'))
+        .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect()
+}
+
+#[test]
+fn diff_two_directories() {
+    let root = make_dir("dirs");
+    let (a, b) = (root.join("a"), root.join("b"));
+    fs::create_dir_all(&a).unwrap();
+    fs::create_dir_all(&b).unwrap();
+    fs::write(a.join("x.rs"), "fn a() {}\n").unwrap();
+    fs::write(b.join("x.rs"), "fn a() {}\n// b\nfn b() {}\n").unwrap();
+    fs::write(a.join("gone.c"), "int x;\n\n").unwrap();
+    fs::write(b.join("y.py"), "x = 1\n").unwrap();
+
+    let out = run(&["diff", a.to_str().unwrap(), b.to_str().unwrap()]);
+    assert!(out.status.success());
+    assert_eq!(
+        rows(&out.stdout),
+        [
+            "c -1 -1 +0 -1",
+            "py +1 +0 +0 +1",
+            "rs +0 +0 +1 +1",
+            "SUM +0 -1 +1 +1",
+            "",
+            "1 files added, 1 removed, 1 modified, 0 renamed",
+        ]
+    );
+}
+
+#[test]
+fn compare_with_saved_report() {
+    let root = make_dir("saved");
+    let dir = root.join("src");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("x.rs"), "fn a() {}\n").unwrap();
+    let saved = root.join("saved.json");
+    let spec = format!("json={}", saved.display());
+    assert!(run(&[dir.to_str().unwrap(), "--format", &spec])
+        .status
+        .success());
+
+    fs::write(dir.join("x.rs"), "fn a() {}\n\nfn b() {}\n").unwrap();
+    let out = run(&[dir.to_str().unwrap(), "--compare", saved.to_str().unwrap()]);
+    assert!(out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let changes = stdout.split("Changes since").nth(1).unwrap();
+    assert_eq!(
+        rows(changes.as_bytes())[1..],
+        ["rs +0 +1 +0 +1", "SUM +0 +1 +0 +1"]
+    );
+
+    // results by file type cannot be compared with results by language
+    let out = run(&[
+        dir.to_str().unwrap(),
+        "--by-lang",
+        "--compare",
+        saved.to_str().unwrap(),
+    ]);
+    assert_eq!(out.status.code(), Some(2));
+}
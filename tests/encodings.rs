@@ -0,0 +1,48 @@
+mod common;
+
+use std::fs;
+
+use common::{cloc, run, temp_dir};
+
+#[test]
+fn legacy_and_utf16_files_are_counted() {
+    let root = temp_dir("encodings");
+
+    // Latin-1, UTF-16 with a byte order mark, and UTF-8 with a byte order mark
+    fs::write(root.join("latin1.rs"), b"// caf\xe9\nlet x = \"\xe9\";\n\n").unwrap();
+    let utf16 = "\u{feff}// comment\r\nfn main() {}\r\n\r\n"
+        .encode_utf16()
+        .flat_map(|u| u.to_le_bytes())
+        .collect::<Vec<_>>();
+    fs::write(root.join("utf16.rs"), utf16).unwrap();
+    fs::write(root.join("bom.rs"), b"\xef\xbb\xbf// comment\nfn f() {}\n").unwrap();
+
+    let output = run(cloc().args(["--by-file", "--format", "csv"]).arg(&root));
+    assert!(output.status.success());
+
+    let out = String::from_utf8(output.stdout).unwrap();
+    assert!(out.contains("\nlatin1.rs,1,1,1\n"), "{}", out);
+    assert!(out.contains("\nutf16.rs,1,1,1\n"), "{}", out);
+    assert!(out.contains("\nbom.rs,1,1,0\n"), "{}", out);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Decoded 2 files"));
+}
+
+#[test]
+fn legacy_files_are_read_by_the_analyses() {
+    let root = temp_dir("encodings-cfg");
+    fs::write(
+        root.join("a.c"),
+        b"// caf\xe9\nint x;\n#ifdef FOO\nint y;\n#endif\n",
+    )
+    .unwrap();
+
+    let out = String::from_utf8(run(cloc().arg("--cfg-stats").arg(&root)).stdout).unwrap();
+    assert!(
+        out.contains(
+            "In 1 C/C++ files, 2 lines of code are unconditional, 2 are conditionally \
+             compiled and 0 are disabled."
+        ),
+        "{}",
+        out
+    );
+}
@@ -0,0 +1,193 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::{cloc, stdout, temp_dir, TempDir};
+
+/// File names that break naively formatted CSV or JSON output.
+const NAMES: &[&str] = &[
+    "a,b.rs",
+    "quote\".rs",
+    "back\\slash.rs",
+    "new\nline.rs",
+    "tab\t.rs",
+    "ctrl\u{1}.rs",
+    "x.c,d",
+    "y.q\"z",
+    "z.n\nl",
+];
+
+/// Create a directory with a file for each adversarial name.
+fn make_tree(test: &str) -> TempDir {
+    let root = temp_dir(&format!("escaping-{}", test));
+
+    for name in NAMES {
+        fs::write(root.join(name), "x = 1;\n\n").unwrap();
+    }
+
+    root
+}
+
+/// Run the binary on a tree and return its stdout.
+fn run(root: &Path, args: &[&str]) -> String {
+    stdout(cloc().arg(root).args(args))
+}
+
+/// Split CSV text into records of unquoted fields.
+fn parse_csv(s: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') => quoted = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    assert!(!quoted, "unterminated quoted field");
+
+    records
+}
+
+#[test]
+fn json_escapes_file_names() {
+    let root = make_tree("json-names");
+    let out = run(&root, &["--by-file", "--output", "json"]);
+
+    // every file is listed on a line of its own, so no name leaked a raw newline
+    let listed = out
+        .lines()
+        .filter(|l| l.starts_with("    {\"path\": "))
+        .count();
+    assert_eq!(listed, NAMES.len(), "{}", out);
+    assert!(
+        !out.chars().any(|c| c.is_control() && c != '\n'),
+        "raw control character in {}",
+        out
+    );
+
+    for escaped in [
+        r#""a,b.rs""#,
+        r#""quote\".rs""#,
+        r#""back\\slash.rs""#,
+        r#""new\nline.rs""#,
+        r#""tab\t.rs""#,
+        r#""ctrl\u0001.rs""#,
+    ] {
+        assert!(
+            out.contains(&format!("{{\"path\": {},", escaped)),
+            "missing {} in {}",
+            escaped,
+            out
+        );
+    }
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn csv_quotes_file_types() {
+    let root = make_tree("csv-types");
+    let out = run(&root, &["--colocation"]);
+
+    let records = parse_csv(&out);
+    let header = &records[0];
+    for record in &records {
+        assert_eq!(record.len(), header.len(), "ragged CSV: {}", out);
+    }
+    for ext in ["c,d", "q\"z", "n\nl"] {
+        assert!(
+            header.iter().any(|f| f == ext),
+            "missing {:?} in {}",
+            ext,
+            out
+        );
+    }
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn csv_quotes_file_names() {
+    let root = make_tree("csv-names");
+    let out = run(&root, &["--by-file", "--output", "csv"]);
+
+    let records = parse_csv(&out);
+    assert_eq!(records.len(), NAMES.len() + 1, "{}", out);
+    for record in &records {
+        assert_eq!(record.len(), 4, "ragged CSV: {}", out);
+    }
+    for name in NAMES {
+        assert!(
+            records.iter().any(|r| r[0] == *name),
+            "missing {:?} in {}",
+            name,
+            out
+        );
+    }
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn tsv_escapes_file_names() {
+    let root = make_tree("tsv-names");
+    let out = run(&root, &["--by-file", "--output", "tsv", "--no-header"]);
+
+    // one line per file with exactly four columns, so no name leaked a tab or newline
+    let lines = out.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), NAMES.len(), "{}", out);
+    for line in &lines {
+        assert_eq!(line.split('\t').count(), 4, "ragged TSV: {}", out);
+    }
+    for escaped in [r"new\nline.rs", r"tab\t.rs", r"back\\slash.rs"] {
+        assert!(
+            lines
+                .iter()
+                .any(|l| l.starts_with(&format!("{}\t", escaped))),
+            "missing {} in {}",
+            escaped,
+            out
+        );
+    }
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn markdown_escapes_file_names() {
+    let root = make_tree("markdown-names");
+    let out = run(&root, &["--by-file", "--output", "markdown"]);
+
+    // a header, an alignment row, a row per file and the totals, each with five cells, so
+    // no name leaked a newline or ended a cell early
+    let lines = out.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), NAMES.len() + 3, "{}", out);
+    for line in &lines {
+        assert_eq!(line.matches('|').count(), 6, "ragged table: {}", out);
+    }
+    for escaped in [
+        r"| new\\nline.rs |",
+        r"| back\\slash.rs |",
+        r"| ctrl\\u{1}.rs |",
+    ] {
+        assert!(out.contains(escaped), "missing {} in {}", escaped, out);
+    }
+
+    fs::remove_dir_all(&root).unwrap();
+}
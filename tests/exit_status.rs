@@ -0,0 +1,132 @@
+mod common;
+
+use std::fs;
+
+use common::{cloc, run, TempDir};
+
+fn make_dir(test: &str) -> TempDir {
+    common::temp_dir(&format!("exit-{}", test))
+}
+
+/// Run the binary and return its exit status.
+fn status(args: &[&str]) -> i32 {
+    run(cloc().args(args)).status.code().unwrap()
+}
+
+#[test]
+fn success() {
+    let root = make_dir("success");
+    fs::write(root.join("a.rs"), "fn main() {}\n").unwrap();
+    assert_eq!(status(&[root.to_str().unwrap()]), 0);
+}
+
+#[test]
+fn threshold_violated() {
+    let root = make_dir("threshold");
+    fs::write(root.join("a.rs"), "fn main() {}\n").unwrap();
+    let root = root.to_str().unwrap();
+    assert_eq!(status(&[root, "--fail-if-lang-share", "rs>50%"]), 1);
+    assert_eq!(status(&[root, "--fail-if-lang-share", "rs<50%"]), 0);
+}
+
+#[test]
+fn usage_error() {
+    let root = make_dir("usage");
+    fs::write(root.join("a.rs"), "fn main() {}\n").unwrap();
+    let root = root.to_str().unwrap();
+    assert_eq!(status(&[root, "--no-such-flag"]), 2);
+    assert_eq!(status(&[root, "--template", "/nonexistent/template"]), 2);
+    assert_eq!(
+        status(&[root, "--resume", "--checkpoint", "/nonexistent/x.ckpt"]),
+        2
+    );
+}
+
+#[test]
+fn missing_git_is_a_usage_error() {
+    let root = make_dir("no-git");
+    let output = run(cloc()
+        .args(["diff", "--matrix", "HEAD"])
+        .current_dir(&root)
+        .env("PATH", ""));
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unable to run git"));
+}
+
+#[test]
+fn partial_results() {
+    let root = make_dir("partial");
+    fs::write(root.join("top.rs"), "fn main() {}\n").unwrap();
+    let list = root.join("files.txt");
+    fs::write(
+        &list,
+        format!(
+            "{}\n{}\n",
+            root.join("top.rs").display(),
+            root.join("gone.rs").display()
+        ),
+    )
+    .unwrap();
+    assert_eq!(status(&["--files-from", list.to_str().unwrap()]), 3);
+}
+
+#[test]
+fn limits_are_not_partial_results() {
+    let root = make_dir("depth");
+    fs::create_dir_all(root.join("a/b")).unwrap();
+    fs::write(root.join("a/b/deep.rs"), "fn f() {}\n").unwrap();
+    fs::write(root.join("top.rs"), "fn main() {}\n").unwrap();
+    let root = root.to_str().unwrap();
+    assert_eq!(status(&[root, "--max-depth", "1"]), 0);
+    assert_eq!(status(&[root, "--sandbox", "--max-depth", "1"]), 0);
+}
+
+#[test]
+fn nothing_counted() {
+    let root = make_dir("nothing");
+    let root = root.to_str().unwrap();
+    assert_eq!(status(&[root]), 4);
+    assert_eq!(status(&[root, "--files-only"]), 4);
+}
+
+#[test]
+fn limits_exceeded() {
+    let root = make_dir("limits");
+    fs::write(root.join("a.rs"), "fn main() {}\n\n\n// done\n").unwrap();
+    fs::write(root.join("b.rs"), "fn f() {}\n").unwrap();
+    let root = root.to_str().unwrap();
+    assert_eq!(status(&[root, "--max-lines", "1"]), 1);
+    assert_eq!(status(&[root, "--max-lines", "2"]), 0);
+    assert_eq!(status(&[root, "--max-file-lines", "3"]), 1);
+    assert_eq!(status(&[root, "--max-file-lines", "4"]), 0);
+    assert_eq!(status(&[root, "--max-percent-blank", "35"]), 1);
+    assert_eq!(status(&[root, "--max-percent-blank", "50"]), 0);
+}
+
+#[test]
+fn eol_rule_violated() {
+    let root = make_dir("eol");
+    fs::write(root.join("a.rs"), "fn main() {}\r\n").unwrap();
+    fs::write(root.join("b.bat"), "echo hi\r\n").unwrap();
+    let root = root.to_str().unwrap();
+    assert_eq!(status(&[root, "--eol-rule", "rs=lf"]), 1);
+    assert_eq!(status(&[root, "--eol-rule", "rs=crlf"]), 0);
+    assert_eq!(status(&[root, "--eol-rule", "bat=crlf"]), 0);
+    assert_eq!(status(&[root, "--eol-rule", "rs=cr"]), 2);
+}
+
+#[test]
+fn line_width_with_tabs() {
+    let root = make_dir("width");
+    fs::write(root.join("a.rs"), "fn main() {\n\t\tx();\n}\n").unwrap();
+    let root = root.to_str().unwrap();
+    // two tabs and four characters
+    assert_eq!(status(&[root, "--max-line-width", "19"]), 1);
+    assert_eq!(status(&[root, "--max-line-width", "20"]), 0);
+    let with_tabs =
+        |max, tab_width| status(&[root, "--max-line-width", max, "--tab-width", tab_width]);
+    assert_eq!(with_tabs("11", "4"), 1);
+    assert_eq!(with_tabs("12", "4"), 0);
+    assert_eq!(with_tabs("11", "2"), 0);
+    assert_eq!(status(&[root, "--tab-width", "0"]), 2);
+}
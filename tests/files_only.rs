@@ -0,0 +1,39 @@
+mod common;
+
+use std::fs;
+
+use common::{cloc, stdout, temp_dir};
+
+#[test]
+fn files_are_counted_without_being_read() {
+    let root = temp_dir("files-only");
+    fs::create_dir_all(root.join("src/assets")).unwrap();
+    fs::write(root.join("main.rs"), "fn main() {}\n").unwrap();
+    // a NUL byte or an LFS pointer can only be recognized by reading the file
+    fs::write(root.join("src/dump.txt"), "a\0b\n").unwrap();
+    fs::write(
+        root.join("src/assets/model.onnx"),
+        format!(
+            "version https://git-lfs.github.com/spec/v1\noid sha256:{}\nsize 4\n",
+            "0".repeat(64)
+        ),
+    )
+    .unwrap();
+    fs::write(root.join("src/assets/logo.png"), "not really an image\n").unwrap();
+
+    assert_eq!(
+        stdout(cloc().arg("--files-only").arg(&root)),
+        "There are 3 files with 143 bytes.\n"
+    );
+    assert_eq!(
+        stdout(
+            cloc()
+                .args(["--files-only", "--by-dir", "--depth", "2"])
+                .arg(&root)
+        ),
+        "There are 3 files with 143 bytes.\n\
+         There are 1 files with 13 bytes in \".\".\n\
+         There are 1 files with 4 bytes in \"src\".\n\
+         There are 1 files with 126 bytes in \"src/assets\".\n"
+    );
+}
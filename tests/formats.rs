@@ -0,0 +1,99 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::{cloc, stdout, temp_dir, TempDir};
+
+/// Create a small project.
+fn make_tree(test: &str) -> TempDir {
+    let root = temp_dir(&format!("formats-{}", test));
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join("py")).unwrap();
+    fs::write(
+        root.join("src/main.rs"),
+        "fn main() {}\n// run\n\nfn run() {}\n",
+    )
+    .unwrap();
+    fs::write(root.join("py/__init__.py"), "x = 1\n").unwrap();
+    root
+}
+
+fn run(root: &Path, args: &[&str]) -> String {
+    stdout(cloc().arg(root).args(args))
+}
+
+#[test]
+fn markdown_tables_for_each_breakdown() {
+    let root = make_tree("markdown");
+
+    assert_eq!(
+        run(&root, &["--output", "markdown"]),
+        "| Type | Files | Blank | Comment | Code |\n\
+         |---|---:|---:|---:|---:|\n\
+         | rs | 1 | 1 | 1 | 2 |\n\
+         | py | 1 | 0 | 0 | 1 |\n\
+         | **SUM** | **2** | **1** | **1** | **3** |\n"
+    );
+    assert_eq!(
+        run(&root, &["--output", "markdown", "--by-lang", "--by-dir"]),
+        "| Language | Files | Blank | Comment | Code |\n\
+         |---|---:|---:|---:|---:|\n\
+         | Rust | 1 | 1 | 1 | 2 |\n\
+         | Python | 1 | 0 | 0 | 1 |\n\
+         | **SUM** | **2** | **1** | **1** | **3** |\n\
+         \n\
+         | Directory | Files | Blank | Comment | Code |\n\
+         |---|---:|---:|---:|---:|\n\
+         | src | 1 | 1 | 1 | 2 |\n\
+         | py | 1 | 0 | 0 | 1 |\n\
+         | **SUM** | **2** | **1** | **1** | **3** |\n"
+    );
+    assert_eq!(
+        run(&root, &["--output", "markdown", "--by-file"]),
+        "| Path | Code | Comment | Empty | Lines |\n\
+         |---|---:|---:|---:|---:|\n\
+         | src/main.rs | 2 | 1 | 1 | 4 |\n\
+         | py/\\_\\_init\\_\\_.py | 1 | 0 | 0 | 1 |\n\
+         | **Total (2 files)** | **3** | **1** | **1** | **5** |\n"
+    );
+    assert_eq!(
+        run(&root.join("py"), &["--output", "markdown", "--by-file"]),
+        "| Path | Code | Comment | Empty | Lines |\n\
+         |---|---:|---:|---:|---:|\n\
+         | \\_\\_init\\_\\_.py | 1 | 0 | 0 | 1 |\n\
+         | **Total (1 file)** | **1** | **0** | **0** | **1** |\n"
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn html_page_with_sortable_tables() {
+    let root = make_tree("html");
+    fs::write(root.join("src/<b>&.rs"), "fn f() {}\n").unwrap();
+
+    let out = run(&root, &["--output", "html", "--by-file"]);
+    assert!(out.starts_with("<!DOCTYPE html>\n"), "{}", out);
+    assert!(out.ends_with("</html>\n"), "{}", out);
+    assert!(
+        out.contains("<tr><td>src/&lt;b&gt;&amp;.rs</td><td class=\"num\">1</td>"),
+        "{}",
+        out
+    );
+    assert!(!out.contains("<b>"), "{}", out);
+    // the totals are in the footer, so sorting the rows leaves them last
+    assert!(
+        out.contains("<tfoot>\n<tr><td>Total (3 files)</td><td class=\"num\">4</td>"),
+        "{}",
+        out
+    );
+    assert!(out.contains("addEventListener(\"click\""), "{}", out);
+
+    let out = run(&root, &["--output", "html", "--by-lang", "--by-dir"]);
+    assert_eq!(out.matches("<table>").count(), 2, "{}", out);
+    assert!(out.contains("<th>Language</th>"), "{}", out);
+    assert!(out.contains("<th>Directory</th>"), "{}", out);
+
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use common::{cloc, stdout, TempDir};
+
+fn git(repo: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .status()
+        .expect("Failed to run git");
+    assert!(status.success());
+}
+
+/// Create a repository with a committed source file and image, and a new source file that
+/// is not committed yet.
+fn make_repo(test: &str) -> TempDir {
+    let repo = common::temp_dir(&format!("git-{}", test));
+    git(&repo, &["init", "-q"]);
+    fs::write(repo.join("a.rs"), "fn a() {}\n").unwrap();
+    fs::write(repo.join("logo.png"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\n\n").unwrap();
+    git(&repo, &["add", "."]);
+    git(&repo, &["commit", "-q", "-m", "init"]);
+    fs::write(repo.join("b.rs"), "fn b() {}\n").unwrap();
+    repo
+}
+
+fn run(repo: &Path, args: &[&str]) -> String {
+    stdout(cloc().args(args).current_dir(repo))
+}
+
+#[test]
+fn pr_comment_skips_git_directory_and_binary_blobs() {
+    let repo = make_repo("pr-comment");
+    let out = run(
+        &repo,
+        &[".", "--format", "pr-comment", "--baseline", "HEAD"],
+    );
+
+    assert!(
+        out.contains("| **Total** | 2 (+1) | 2 (+1) | 0 | 0 |"),
+        "{}",
+        out
+    );
+    assert!(!out.contains("png"), "{}", out);
+    assert!(!out.contains(".git"), "{}", out);
+    assert!(out.contains("| 1 | `b.rs` |"), "{}", out);
+
+    fs::remove_dir_all(&repo).unwrap();
+}
+
+#[test]
+fn matrix_skips_binary_blobs() {
+    let repo = make_repo("matrix");
+    let out = run(&repo, &["diff", "--matrix", "HEAD"]);
+
+    assert!(!out.contains("png"), "{}", out);
+    assert!(out.contains("\nrs "), "{}", out);
+
+    fs::remove_dir_all(&repo).unwrap();
+}
+
+#[test]
+fn diff_skips_binary_files() {
+    let repo = make_repo("diff");
+    fs::write(repo.join("data.bin"), "x\n").unwrap();
+    git(&repo, &["add", "."]);
+    git(&repo, &["commit", "-q", "-m", "data"]);
+    fs::write(repo.join("a.rs"), "fn a() {}\n// b\nfn b() {}\n").unwrap();
+    fs::write(repo.join("data.bin"), "x\ny\n").unwrap();
+    let out = run(&repo, &[".", "--diff", "HEAD"]);
+
+    assert!(out.starts_with("Language"), "{}", out);
+    assert!(out.contains("\nRust\n"), "{}", out);
+    assert_eq!(out.matches("added").count(), 2, "{}", out);
+    assert!(
+        out.contains("  added                 0          0          1          1\n"),
+        "{}",
+        out
+    );
+}
@@ -0,0 +1,143 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+use common::{cloc, stdout, TempDir};
+
+/// Create a project and a languages file.
+fn make_tree(test: &str, languages: &str) -> TempDir {
+    let base = common::temp_dir(&format!("languages-{}", test));
+    fs::create_dir_all(base.join("project")).unwrap();
+
+    fs::write(
+        base.join("project/rules.dsl"),
+        ";; a comment\nrule = `;; not a comment`\n{-\nblock\n-}\n\n",
+    )
+    .unwrap();
+    fs::write(base.join("project/main.rs"), "# pragma\n// code\n").unwrap();
+    fs::write(base.join("languages.toml"), languages).unwrap();
+
+    base
+}
+
+fn run(base: &Path, args: &[&str]) -> Output {
+    common::run(
+        cloc()
+            .args(["--format", "csv", "--by-lang"])
+            .arg("--languages")
+            .arg(base.join("languages.toml"))
+            .arg(base.join("project"))
+            .args(args),
+    )
+}
+
+#[test]
+fn custom_and_redefined_languages() {
+    let base = make_tree(
+        "custom",
+        "[my_dsl]\nname = \"My DSL\"\nextensions = [\"dsl\"]\nline_comments = [\";;\"]\n\
+         block_comments = [[\"{-\", \"-}\"]]\nstring_quotes = [\"`\"]\n\n\
+         [rust]\nline_comments = [\"#\"]\n",
+    );
+    let output = run(&base, &[]);
+    assert!(output.status.success());
+    let out = String::from_utf8(output.stdout).unwrap();
+    assert!(out.contains("\nmy_dsl,1,1,4,1,"), "{}", out);
+    assert!(out.contains("\nrust,1,1,1,0,"), "{}", out);
+}
+
+#[test]
+fn invalid_languages_file_is_an_error() {
+    let base = make_tree("invalid", "[my_dsl]\nline_comments = [\";;\"]\n");
+    let output = run(&base, &[]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no extensions or filenames"));
+}
+
+#[test]
+fn exported_languages_load_back_unchanged() {
+    let base = make_tree(
+        "export",
+        "[my_dsl]\nname = \"My DSL\"\nextensions = [\"dsl\"]\nline_comments = [\";;\"]\n\
+         block_comments = [[\"{-\", \"-}\"]]\nstring_quotes = [\"`\"]\n",
+    );
+    let languages = |file: &Path, action: &[&str]| {
+        stdout(
+            cloc()
+                .arg("--languages-file")
+                .arg(file)
+                .arg("languages")
+                .args(action),
+        )
+    };
+
+    let exported = languages(&base.join("languages.toml"), &["export"]);
+    assert!(
+        exported.starts_with("builtins = false\n\n[my_dsl]\n"),
+        "{}",
+        exported
+    );
+    assert!(
+        exported.contains("\n[rust]\nname = \"Rust\"\n"),
+        "{}",
+        exported
+    );
+    let pinned = base.join("pinned.toml");
+    fs::write(&pinned, &exported).unwrap();
+
+    // the pinned table is the same table, and exporting it again gives the same file
+    assert_eq!(
+        languages(&pinned, &[]),
+        languages(&base.join("languages.toml"), &[])
+    );
+    assert_eq!(languages(&pinned, &["export"]), exported);
+
+    fs::copy(&pinned, base.join("languages.toml")).unwrap();
+    let out = String::from_utf8(run(&base, &[]).stdout).unwrap();
+    assert!(out.contains("\nmy_dsl,1,1,4,1,"), "{}", out);
+    assert!(out.contains("\nrust,1,1,1,0,"), "{}", out);
+}
+
+#[test]
+fn languages_file_without_builtins() {
+    let base = make_tree(
+        "no-builtins",
+        "builtins = false\n\n[my_dsl]\nextensions = [\"dsl\"]\nline_comments = [\";;\"]\n",
+    );
+    let output = run(&base, &[]);
+    assert!(output.status.success());
+    let out = String::from_utf8(output.stdout).unwrap();
+    // without the built-in languages, Rust files are only known by their extension
+    assert!(out.contains("\nrs,1,2,0,0,"), "{}", out);
+}
+
+#[test]
+fn pinned_classification_version() {
+    let export = |args: &[&str]| {
+        let output = common::run(cloc().args(args).args(["languages", "export"]));
+        (
+            output.status.code(),
+            String::from_utf8(output.stdout).unwrap(),
+        )
+    };
+
+    // the latest snapshot is the built-in table, so changing a built-in language needs a
+    // new classification version
+    assert_eq!(export(&["--classification-version", "1"]), export(&[]));
+    assert_eq!(export(&["--classification-version", "0"]).0, Some(2));
+    assert_eq!(export(&["--classification-version", "999"]).0, Some(2));
+
+    // languages files still redefine languages of the pinned snapshot
+    let base = make_tree(
+        "pinned",
+        "[my_dsl]\nextensions = [\"dsl\"]\nline_comments = [\";;\"]\n\n\
+         [rust]\nline_comments = [\"#\"]\n",
+    );
+    let output = run(&base, &["--classification-version", "1"]);
+    assert!(output.status.success());
+    let out = String::from_utf8(output.stdout).unwrap();
+    assert!(out.contains("\nmy_dsl,1,4,1,1,"), "{}", out);
+    assert!(out.contains("\nrust,1,1,1,0,"), "{}", out);
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+use common::cloc;
+
+/// Write an LFS pointer to an object with the given ID.
+fn write_pointer(path: &Path, oid: &str) {
+    let pointer = format!(
+        "version https://git-lfs.github.com/spec/v1\noid sha256:{}\nsize 12\n",
+        oid
+    );
+    fs::write(path, pointer).unwrap();
+}
+
+fn run(root: &Path, args: &[&str]) -> Output {
+    common::run(
+        cloc()
+            .args(["--by-file", "--format", "csv"])
+            .args(["--exclude", ".git"])
+            .arg(root)
+            .args(args),
+    )
+}
+
+#[test]
+fn pointers_are_skipped_or_resolved() {
+    let root = common::temp_dir("lfs");
+
+    // a downloaded object and a pointer to it, and a pointer to an object that is missing
+    let oid = "ab".repeat(32);
+    let objects = root.join(".git/lfs/objects/ab/ab");
+    fs::create_dir_all(&objects).unwrap();
+    fs::write(objects.join(&oid), "a,b\n1,2\n3,4\n").unwrap();
+    write_pointer(&root.join("data.csv"), &oid);
+    write_pointer(&root.join("missing.csv"), &"cd".repeat(32));
+    fs::write(root.join("main.py"), "x = 1\n").unwrap();
+
+    let output = run(&root, &[]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "path,code,comments,empty\nmain.py,1,0,0\n"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Skipped 2 Git LFS pointers"));
+
+    let output = run(&root, &["--resolve-lfs"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "path,code,comments,empty\ndata.csv,3,0,0\nmain.py,1,0,0\n"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Skipped 1 Git LFS pointers"));
+}
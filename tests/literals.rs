@@ -0,0 +1,25 @@
+mod common;
+
+use std::fs;
+
+use common::{cloc, stdout, temp_dir};
+
+#[test]
+fn literals_in_comments_are_not_counted() {
+    let root = temp_dir("literals");
+    fs::write(
+        root.join("a.rs"),
+        "// \"one\" and 42\n/* \"two\" */\nlet s = \"three\";\n",
+    )
+    .unwrap();
+
+    let out = stdout(cloc().arg("--literal-density").arg(&root));
+    assert!(
+        out.contains(
+            "In \"rs\" files, there are 1000.00 string literals and 0.00 numeric literals \
+             per 1000 lines of code."
+        ),
+        "{}",
+        out
+    );
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use std::fs;
+
+use common::{cloc, stdout, temp_dir};
+
+#[test]
+fn finds_reindented_copy() {
+    let root = temp_dir("similar");
+    fs::create_dir_all(root.join("service_a")).unwrap();
+    fs::create_dir_all(root.join("service_b")).unwrap();
+
+    let module = (0..30)
+        .map(|i| format!("let x{} = f({});\n", i, i))
+        .collect::<String>();
+    let diverged = module.replace("let ", "    let ") + "extra();\nmore();\n";
+    let unrelated = (0..30)
+        .map(|i| format!("y{} = {}\n", i, i))
+        .collect::<String>();
+    fs::write(root.join("service_a/util.rs"), &module).unwrap();
+    fs::write(root.join("service_b/util.rs"), diverged).unwrap();
+    fs::write(root.join("other.py"), unrelated).unwrap();
+
+    let out = stdout(
+        cloc()
+            .args(["--output", "plain", "--near-duplicates", "80"])
+            .arg(&root),
+    );
+    let pairs = out
+        .lines()
+        .skip_while(|l| !l.starts_with("Files that are"))
+        .skip(1)
+        .collect::<Vec<_>>();
+    assert_eq!(pairs.len(), 1, "{}", out);
+    assert!(pairs[0].starts_with(" 93.75%"), "{}", out);
+    assert!(
+        pairs[0].contains("(30 of 32 distinct lines shared)"),
+        "{}",
+        out
+    );
+}
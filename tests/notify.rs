@@ -0,0 +1,52 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+use common::{cloc, stdout};
+
+#[test]
+fn headers_are_not_passed_as_arguments() {
+    let root = common::temp_dir("notify");
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join("bin")).unwrap();
+    fs::write(root.join("src/a.rs"), "fn a() {}\n").unwrap();
+
+    // a stand-in for curl that saves its arguments and stdin
+    let curl = root.join("bin/curl");
+    fs::write(
+        &curl,
+        format!(
+            "#!/bin/sh\nprintf '%s\\n' \"$@\" > {0}/args\ncat > {0}/stdin\n",
+            root.display()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&curl, fs::Permissions::from_mode(0o755)).unwrap();
+
+    stdout(
+        cloc()
+            .env(
+                "PATH",
+                format!(
+                    "{}:{}",
+                    root.join("bin").display(),
+                    std::env::var("PATH").unwrap()
+                ),
+            )
+            .arg("--notify-webhook=-K/etc/passwd")
+            .args(["--notify-header", "Authorization: Bearer secret"])
+            .arg(root.join("src")),
+    );
+
+    let args = fs::read_to_string(root.join("args")).unwrap();
+    assert!(!args.contains("secret"), "{}", args);
+    assert!(args.ends_with("\n--url\n-K/etc/passwd\n"), "{}", args);
+    assert!(args.contains("\n-H\n@-\n"), "{}", args);
+    assert_eq!(
+        fs::read_to_string(root.join("stdin")).unwrap(),
+        "Content-Type: application/json\nAuthorization: Bearer secret\n"
+    );
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::Stdio;
+
+use common::{cloc, stdout, TempDir};
+
+/// Create two directories and a loose file.
+fn make_tree(test: &str) -> TempDir {
+    let base = common::temp_dir(&format!("paths-{}", test));
+    fs::create_dir_all(base.join("src")).unwrap();
+    fs::create_dir_all(base.join("tests")).unwrap();
+
+    fs::write(base.join("src/lib.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+    fs::write(base.join("tests/t.rs"), "fn t() {}\n\n").unwrap();
+    fs::write(base.join("build.rs"), "fn main() {}\n").unwrap();
+
+    base
+}
+
+/// Run the binary in a directory and return its stdout.
+fn run(dir: &Path, args: &[&str]) -> String {
+    stdout(
+        cloc()
+            .current_dir(dir)
+            .args(["--output", "plain"])
+            .args(args),
+    )
+}
+
+#[test]
+fn counts_single_file() {
+    let base = make_tree("single");
+    let out = run(&base, &["build.rs"]);
+    assert!(out.starts_with("There are 1 lines of code.\n"), "{}", out);
+}
+
+#[test]
+fn counts_several_paths() {
+    let base = make_tree("several");
+    for threads in ["1", "4"] {
+        let out = run(&base, &["src", "tests", "build.rs", "-j", threads]);
+        assert!(out.starts_with("There are 4 lines of code.\n"), "{}", out);
+    }
+}
+
+#[test]
+fn counts_overlapping_paths_once() {
+    let base = make_tree("overlap");
+    let out = run(&base, &["src", "src/lib.rs"]);
+    assert!(out.starts_with("There are 2 lines of code.\n"), "{}", out);
+}
+
+#[test]
+fn breaks_down_by_root() {
+    let base = make_tree("by-root");
+    let out = run(&base, &["src", "tests", "--by-root", "--output", "json"]);
+    assert!(
+        out.contains("{\"root\": \"src\", \"files\": 1, \"code\": 2,"),
+        "{}",
+        out
+    );
+    assert!(
+        out.contains("{\"root\": \"tests\", \"files\": 1, \"code\": 1,"),
+        "{}",
+        out
+    );
+}
+
+/// Run the binary in a directory with some input and return its stdout.
+fn run_with_stdin(dir: &Path, args: &[&str], stdin: &[u8]) -> String {
+    let mut child = cloc()
+        .current_dir(dir)
+        .args(["--output", "plain"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to run rust-cloc");
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn counts_files_from_stdin() {
+    let base = make_tree("files-from");
+    let out = run_with_stdin(&base, &["--files-from", "-"], b"src/lib.rs\r\nbuild.rs\n\n");
+    assert!(out.starts_with("There are 3 lines of code.\n"), "{}", out);
+}
+
+#[test]
+fn counts_nul_separated_files() {
+    let base = make_tree("files0-from");
+    fs::write(base.join("new\nline.rs"), "fn n() {}\n").unwrap();
+    let list = base.join("list");
+    fs::write(&list, b"new\nline.rs\0tests/t.rs\0").unwrap();
+    let out = run(&base, &["--files0-from", list.to_str().unwrap()]);
+    assert!(out.starts_with("There are 2 lines of code.\n"), "{}", out);
+}
@@ -0,0 +1,39 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+use common::{cloc, stdout, temp_dir};
+
+#[test]
+fn counts_executable_and_setuid_files() {
+    let root = temp_dir("perms");
+
+    for (name, mode) in [
+        ("build.sh", 0o755),
+        ("env.sh", 0o644),
+        ("helper.py", 0o4755),
+    ] {
+        let path = root.join(name);
+        fs::write(&path, "x = 1\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    let out = stdout(
+        cloc()
+            .args(["--output", "plain", "--perm-stats"])
+            .arg(&root),
+    );
+    assert!(
+        out.contains("In 1 Python files, 1 are executable and 1 are setuid."),
+        "{}",
+        out
+    );
+    assert!(
+        out.contains("In 2 Shell files, 1 are executable and 0 are setuid."),
+        "{}",
+        out
+    );
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use std::fs;
+
+use common::{cloc, run, temp_dir};
+
+#[test]
+fn json_progress_ends_with_done_event() {
+    let root = temp_dir("progress");
+    for i in 0..10 {
+        fs::write(root.join(format!("{}.rs", i)), "fn f() {}\n").unwrap();
+    }
+
+    let output = run(cloc().args(["--progress", "json", "-j", "2"]).arg(&root));
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let events = stderr.lines().collect::<Vec<_>>();
+    assert!(
+        events.iter().all(|e| e.starts_with("{\"event\": ")),
+        "{}",
+        stderr
+    );
+    let done = events.last().unwrap();
+    assert!(
+        done.starts_with(
+            "{\"event\": \"done\", \"discovered\": 10, \"counted\": 10, \"bytes\": 100, \
+             \"lines\": 10, "
+        ),
+        "{}",
+        done
+    );
+    assert!(done.ends_with("\"eta_ms\": 0}"), "{}", done);
+}
+
+#[test]
+fn bare_progress_shows_status_line() {
+    let root = temp_dir("progress-bar");
+    for i in 0..4 {
+        fs::write(root.join(format!("{}.rs", i)), "fn f() {}\n\n").unwrap();
+    }
+
+    let output = run(cloc().arg(&root).arg("--progress"));
+    assert!(output.status.success());
+
+    // the results on stdout are not mixed with the progress
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("Counted"), "{}", stdout);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let last = stderr.trim_end().rsplit('\r').next().unwrap();
+    assert_eq!(last, "Counted 4/4 files (100%), 8 lines, 0.0 MB\x1b[K");
+}
+
+#[test]
+fn verbose_logs_ignored_files() {
+    let root = temp_dir("verbose");
+    fs::create_dir_all(root.join("vendor")).unwrap();
+    fs::write(root.join("main.rs"), "fn main() {}\n").unwrap();
+    fs::write(root.join("vendor/lib.rs"), "fn f() {}\n").unwrap();
+    fs::write(root.join("blob.rs"), b"\0\0\0").unwrap();
+
+    let stderr = |verbose: &str| {
+        let mut cmd = cloc();
+        cmd.args(["--exclude", "vendor"]);
+        if !verbose.is_empty() {
+            cmd.arg(verbose);
+        }
+        let output = run(cmd.arg(&root));
+        assert!(output.status.success());
+        String::from_utf8(output.stderr).unwrap()
+    };
+
+    assert!(!stderr("").contains("Ignored"));
+    assert!(!stderr("-v").contains("Ignored"));
+    let stderr = stderr("-vv");
+    assert!(
+        stderr.contains(&format!(
+            "Ignored {}: excluded by vendor",
+            root.join("vendor").display()
+        )),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains(&format!(
+            "Ignored {}: binary file",
+            root.join("blob.rs").display()
+        )),
+        "{}",
+        stderr
+    );
+}
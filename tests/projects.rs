@@ -0,0 +1,40 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::{cloc, stdout};
+
+/// Run the binary with its own cache directory and return its stdout.
+fn run(cache: &Path, args: &[&str]) -> String {
+    stdout(cloc().env("XDG_CACHE_HOME", cache).args(args))
+}
+
+#[test]
+fn all_projects_merges_every_scan() {
+    let base = common::temp_dir("projects");
+    for (project, lines) in [("one", 2), ("two", 3)] {
+        fs::create_dir_all(base.join(project)).unwrap();
+        fs::write(base.join(project).join("a.rs"), "x;\n".repeat(lines)).unwrap();
+    }
+    let cache = base.join("cache");
+
+    assert!(run(&cache, &["stats", "--all-projects"]).starts_with("No projects"));
+    for project in ["one", "two", "one"] {
+        run(&cache, &["--history", base.join(project).to_str().unwrap()]);
+    }
+    // scans are only remembered when asked to
+    run(&cache, &[base.to_str().unwrap()]);
+
+    let out = run(&cache, &["stats", "--all-projects"]);
+    let sum = out
+        .lines()
+        .filter(|l| l.starts_with("SUM"))
+        .collect::<Vec<_>>();
+    assert_eq!(sum.len(), 2, "{}", out);
+    assert!(
+        sum[0].ends_with(" 2          0          0          5"),
+        "{}",
+        out
+    );
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use common::{cloc, stdout, temp_dir};
+
+/// Get the size and modification time of a file as saved in a checkpoint.
+fn stat(path: &Path) -> (u64, u128) {
+    let meta = fs::metadata(path).unwrap();
+    let mtime = meta
+        .modified()
+        .unwrap()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    (meta.len(), mtime)
+}
+
+/// Get the settings header that scans of `dir` with `args` write, which checkpoints share
+/// with caches.
+fn header(root: &Path, dir: &Path, args: &[&str]) -> String {
+    let cache = root.join("cache.tsv");
+    stdout(cloc().arg("--cache").arg(&cache).arg(dir).args(args));
+    let cache = fs::read_to_string(&cache).unwrap();
+    cache.lines().next().unwrap().to_owned()
+}
+
+#[test]
+fn resumes_only_unchanged_files() {
+    let root = temp_dir("resume");
+    fs::create_dir_all(root.join("src")).unwrap();
+    let (a, b) = (root.join("src/a.rs"), root.join("src/b.rs"));
+    fs::write(&a, "fn a() {}\n").unwrap();
+    fs::write(&b, "fn b() {}\n").unwrap();
+
+    // an interrupted scan counted both files, but b.rs changed since
+    let (a_size, a_mtime) = stat(&a);
+    let (b_size, b_mtime) = stat(&b);
+    let checkpoint = root.join("scan.checkpoint");
+    fs::write(
+        &checkpoint,
+        format!(
+            "{}\n{}\t{}\t10\t0\t0\t{}\n{}\t{}\t20\t0\t0\t{}\n",
+            header(&root, &root.join("src"), &[]),
+            a_size,
+            a_mtime,
+            a.display(),
+            b_size,
+            b_mtime + 1,
+            b.display()
+        ),
+    )
+    .unwrap();
+
+    let out = stdout(
+        cloc()
+            .args(["--resume", "--checkpoint"])
+            .arg(&checkpoint)
+            .arg(root.join("src")),
+    );
+    assert!(
+        out.contains("\nSUM         2          0          0         11\n"),
+        "{}",
+        out
+    );
+    assert!(!checkpoint.exists());
+}
+
+#[test]
+fn checkpoints_from_other_settings_are_discarded() {
+    let root = temp_dir("resume-settings");
+    fs::create_dir_all(root.join("src")).unwrap();
+    let a = root.join("src/a.rs");
+    fs::write(&a, "fn a() {}\n").unwrap();
+
+    // the interrupted scan counted binary files, so its results cannot be reused
+    let (size, mtime) = stat(&a);
+    let checkpoint = root.join("scan.checkpoint");
+    fs::write(
+        &checkpoint,
+        format!(
+            "{}\n{}\t{}\t10\t0\t0\t{}\n",
+            header(&root, &root.join("src"), &["--count-binary"]),
+            size,
+            mtime,
+            a.display()
+        ),
+    )
+    .unwrap();
+
+    let out = stdout(
+        cloc()
+            .args(["--resume", "--checkpoint"])
+            .arg(&checkpoint)
+            .arg(root.join("src")),
+    );
+    assert!(
+        out.contains("\nSUM         1          0          0          1\n"),
+        "{}",
+        out
+    );
+    assert!(!checkpoint.exists());
+}
+
+#[test]
+fn checkpoint_lives_in_the_cache_directory() {
+    let root = temp_dir("resume-cache");
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/a.rs"), "fn a() {}\n").unwrap();
+
+    stdout(
+        cloc()
+            .env("XDG_CACHE_HOME", root.join("cache"))
+            .arg("--resume")
+            .arg(root.join("src")),
+    );
+    assert!(root.join("cache/rust-cloc/checkpoints").is_dir());
+}
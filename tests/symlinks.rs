@@ -0,0 +1,153 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::Path;
+
+use common::{cloc, run, stdout, TempDir};
+
+/// Create a project with a link to a file in it, a link to a directory outside of it and
+/// links back to directories above.
+fn make_tree(test: &str) -> TempDir {
+    let base = common::temp_dir(&format!("symlinks-{}", test));
+    fs::create_dir_all(base.join("project/src")).unwrap();
+    fs::create_dir_all(base.join("shared")).unwrap();
+
+    fs::write(base.join("project/src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(base.join("shared/lib.rs"), "fn f() {}\n").unwrap();
+    symlink("main.rs", base.join("project/src/alias.rs")).unwrap();
+    symlink("../../shared", base.join("project/src/shared")).unwrap();
+    symlink("..", base.join("project/src/up")).unwrap();
+    symlink(base.join("project"), base.join("shared/project")).unwrap();
+
+    base
+}
+
+fn by_file(base: &Path, args: &[&str]) -> String {
+    stdout(
+        cloc()
+            .args(["--by-file", "--format", "csv"])
+            .arg(base.join("project"))
+            .args(args),
+    )
+}
+
+#[test]
+fn symlinks_are_left_out_by_default() {
+    let base = make_tree("default");
+    assert_eq!(
+        by_file(&base, &[]),
+        "path,code,comments,empty\nsrc/main.rs,1,0,0\n"
+    );
+}
+
+#[test]
+fn sandbox_leaves_out_symlinks_without_partial_results() {
+    let base = make_tree("sandbox");
+    assert_eq!(
+        by_file(&base, &["--sandbox"]),
+        "path,code,comments,empty\nsrc/main.rs,1,0,0\n"
+    );
+}
+
+#[test]
+fn followed_symlinks_count_each_file_once() {
+    let base = make_tree("follow");
+    for threads in ["1", "4"] {
+        assert_eq!(
+            by_file(&base, &["--follow-symlinks", "-j", threads]),
+            "path,code,comments,empty\nsrc/main.rs,1,0,0\nsrc/shared/lib.rs,1,0,0\n"
+        );
+    }
+}
+
+#[test]
+fn files_can_be_attributed_to_links_or_targets() {
+    let base = make_tree("attribute");
+    let shared = base.join("shared/lib.rs").canonicalize().unwrap();
+    for threads in ["1", "4"] {
+        assert_eq!(
+            by_file(
+                &base,
+                &["--follow-symlinks", "--attribute-to", "link", "-j", threads]
+            ),
+            "path,code,comments,empty\nsrc/alias.rs,1,0,0\nsrc/shared/lib.rs,1,0,0\n"
+        );
+        assert_eq!(
+            by_file(
+                &base,
+                &[
+                    "--follow-symlinks",
+                    "--attribute-to",
+                    "target",
+                    "-j",
+                    threads
+                ]
+            ),
+            format!(
+                "path,code,comments,empty\nsrc/main.rs,1,0,0\n{},1,0,0\n",
+                shared.display()
+            )
+        );
+    }
+}
+
+#[test]
+fn attribution_needs_following_symlinks() {
+    let output = run(cloc().args(["--attribute-to", "link", "."]));
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn broken_symlinks_are_recorded_or_stop_strict_runs() {
+    let base = make_tree("broken");
+    symlink("missing.rs", base.join("project/src/broken.rs")).unwrap();
+
+    let count = |strict: bool| {
+        let mut cmd = cloc();
+        cmd.arg("--follow-symlinks");
+        if strict {
+            cmd.arg("--strict");
+        }
+        run(cmd.arg(base.join("project")))
+    };
+
+    let broken = base.join("project/src/broken.rs");
+    let output = count(false);
+    assert_eq!(output.status.code(), Some(3));
+    // main.rs and shared/lib.rs, without the link to nothing
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("\nrs          2 "), "{}", stdout);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(&format!("  {}: broken symlink", broken.display())),
+        "{}",
+        stderr
+    );
+
+    let output = count(true);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!("Error: {}: broken symlink\n", broken.display())
+    );
+}
+
+#[test]
+fn each_scan_reports_its_own_skipped_paths() {
+    let base = make_tree("per-scan");
+    let broken = base.join("project/src/broken.rs");
+    symlink("missing.rs", &broken).unwrap();
+
+    let counter = rust_cloc::Counter::new().follow_symlinks(true);
+    for _ in 0..2 {
+        let report = counter.count(base.join("project"));
+        assert_eq!(
+            report.skipped,
+            vec![(broken.clone(), "broken symlink".to_owned())]
+        );
+    }
+}
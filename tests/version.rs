@@ -0,0 +1,32 @@
+mod common;
+
+use common::{cloc, stdout};
+
+fn version(args: &[&str]) -> String {
+    stdout(cloc().args(args))
+}
+
+#[test]
+fn plain_version() {
+    assert_eq!(
+        version(&["--version"]),
+        format!("rust-cloc {}\n", env!("CARGO_PKG_VERSION"))
+    );
+}
+
+#[test]
+fn json_version_lists_capabilities() {
+    let out = version(&["--version", "--format", "json"]);
+    assert!(
+        out.contains(&format!("\"version\": \"{}\"", env!("CARGO_PKG_VERSION"))),
+        "{}",
+        out
+    );
+    assert!(out.contains("\"features\": []"), "{}", out);
+    assert!(out.contains("\"statusline\""), "{}", out);
+    assert!(out.contains("\"json_schema_version\": 2"), "{}", out);
+    assert!(out.contains("\"hash\": \""), "{}", out);
+
+    // the language table hash is stable between runs
+    assert_eq!(out, version(&["-V", "--output", "json"]));
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::process::Stdio;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn watch_redraws_with_net_change() {
+    let root = common::temp_dir("watch");
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(root.join("src/old.rs"), "fn old() {}\n// old\n").unwrap();
+
+    let mut child = common::cloc()
+        .arg("--watch")
+        .arg(&root)
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to run rust-cloc");
+    let (tx, rx) = mpsc::channel();
+    let stdout = child.stdout.take().unwrap();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if line.starts_with("Net change") && tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    let next = || rx.recv_timeout(Duration::from_secs(10)).unwrap();
+
+    let changes = [
+        next(),
+        {
+            fs::write(root.join("src/main.rs"), "fn main() {\n    run();\n}\n").unwrap();
+            next()
+        },
+        {
+            fs::create_dir(root.join("src/new")).unwrap();
+            fs::write(root.join("src/new/lib.rs"), "\n").unwrap();
+            next()
+        },
+        {
+            fs::remove_file(root.join("src/old.rs")).unwrap();
+            next()
+        },
+    ];
+    let _ = child.kill();
+    let _ = child.wait();
+
+    let prefix = "Net change since watching started: ";
+    let expected = [
+        "+0 code, +0 comment, +0 empty lines",
+        "+2 code, +0 comment, +0 empty lines",
+        "+2 code, +0 comment, +1 empty lines",
+        "+1 code, -1 comment, +1 empty lines",
+    ];
+    for (change, expected) in changes.iter().zip(expected) {
+        assert_eq!(change.strip_prefix(prefix), Some(expected));
+    }
+}